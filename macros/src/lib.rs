@@ -7,10 +7,72 @@ extern crate convert_case;
 
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, TokenStream as TokenStream2};
-use syn::{parse_macro_input, parse::Parse, punctuated::{Punctuated}, Token, braced, Type, Expr};
+use syn::{parse_macro_input, parse::Parse, punctuated::{Punctuated}, Token, braced, Type, Expr, GenericArgument, PathArguments};
 use quote::quote;
 use convert_case::{Case, Casing};
 
+/// Find the config struct a field type contains, like `RawSource` in 
+/// `Vec<RawSource>`. Fields parsed with a custom function from a `Raw*` 
+/// struct (like `Vec<Arc<Source>>` from `RawSource`) are also detected
+fn nested_config(type_: &Type, known: &[String]) -> Option<Ident> {
+    let segment = match type_ {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    for name in [segment.ident.to_string(), format!("Raw{}", segment.ident)] {
+        if known.contains(&name) {
+            return Some(Ident::new(&name, segment.ident.span()));
+        }
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => nested_config(ty, known),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Generate `schema()` for a config struct, which lists its keys for 
+/// validating configs before they are deserialized
+fn gen_schema(name: &Ident, keys: &SubKeys, known: &[String]) -> TokenStream2 {
+    let mut entries = TokenStream2::new();
+    for key in &keys.keys {
+        let (required, list, nested) = match key {
+            Key::Field(_, _, _, Deserialize::Skip, _) => continue,
+            Key::Field(_, type_, optional, _, default) => (
+                !optional && default.is_none(),
+                match type_ {
+                    Type::Path(path) => path.path.segments.last().map_or(false, |s| s.ident == "Vec"),
+                    _ => false,
+                },
+                nested_config(type_, known),
+            ),
+            Key::Sub(_, _, optional) => (
+                !optional && !key.has_default_value(),
+                false,
+                key.type_name(),
+            ),
+        };
+        // Same as serde's kebab-case for snake_case names
+        let key_name = key.name().to_string().replace('_', "-");
+        let nested = match nested {
+            Some(ty) => quote!{ Some(#ty::schema) },
+            None => quote!{ None },
+        };
+        entries.extend(quote! {
+            ConfigKey { name: #key_name, required: #required, list: #list, keys: #nested },
+        });
+    }
+    quote! {
+        impl #name {
+            pub fn schema() -> Vec<ConfigKey> {
+                vec![#entries]
+            }
+        }
+    }
+}
+
 trait Gen {
     fn gen(&self) -> syn::Result<TokenStream2>;
     fn pregen(&self) -> syn::Result<TokenStream2>;
@@ -284,6 +346,29 @@ impl Gen for SubKeys {
     }
 }
 
+impl SubKeys {
+    /// Names of all the structs generated for sub keys
+    fn sub_names(&self, into: &mut Vec<String>) {
+        for key in &self.keys {
+            if let Key::Sub(_, sub, _) = key {
+                into.push(key.type_name().unwrap().to_string());
+                sub.sub_names(into);
+            }
+        }
+    }
+
+    fn gen_sub_schemas(&self, known: &[String]) -> TokenStream2 {
+        let mut stream = TokenStream2::new();
+        for key in &self.keys {
+            if let Key::Sub(_, sub, _) = key {
+                stream.extend(gen_schema(&key.type_name().unwrap(), sub, known));
+                stream.extend(sub.gen_sub_schemas(known));
+            }
+        }
+        stream
+    }
+}
+
 impl Parse for SubKeys {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         Ok(Self {
@@ -339,9 +424,17 @@ struct MultiConfigDecl {
 
 impl Gen for MultiConfigDecl {
     fn gen(&self) -> syn::Result<TokenStream2> {
+        let mut known = Vec::new();
+        for config in &self.configs {
+            known.push(config.name.to_string());
+            config.keys.sub_names(&mut known);
+        }
+
         let mut stream = TokenStream2::new();
         for config in &self.configs {
             stream.extend(config.gen()?);
+            stream.extend(gen_schema(&config.name, &config.keys, &known));
+            stream.extend(config.keys.gen_sub_schemas(&known));
         }
         Ok(stream)
     }
@@ -1,6 +1,12 @@
-use crate::{builder::builder::Builder, cmake, config::Config};
-use indicatif::{ProgressBar, ProgressStyle};
-use std::{fs, path::PathBuf, process::Command, sync::Arc, time::Duration};
+use crate::{
+    builder::{builder::Builder, coverage::Coverage, doctest::check_examples, linkcheck::check_links, shared::stable_hash, stats::{fmt_stats, Timings}},
+    buildsystem,
+    config::{Config, Report},
+    log::{self, Category, Level},
+};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use clang::{Index, TranslationUnit};
+use std::{fs, io, path::{Path, PathBuf}, process::Command, sync::Arc, time::Duration};
 
 fn run_command(cmd: &String) -> Result<(), String> {
     let args =
@@ -19,7 +25,16 @@ fn run_command(cmd: &String) -> Result<(), String> {
 }
 
 fn create_analyzable_file(config: Arc<Config>) -> Result<PathBuf, String> {
-    let out_path = config.output_dir.join("_analyze.cpp");
+    // Objective-C++ is a superset of C++, so headers with both in them can be 
+    // parsed as it. Pure C headers are parsed as C so C++ keywords can be 
+    // used as names in them
+    let out_path = config.output_dir.join(if config.analysis.c {
+        "_analyze.c"
+    } else if config.analysis.objc {
+        "_analyze.mm"
+    } else {
+        "_analyze.cpp"
+    });
 
     let mut data = String::from(
         "// File generated by Flash for including all headers in order to\n\
@@ -34,14 +49,88 @@ fn create_analyzable_file(config: Arc<Config>) -> Result<PathBuf, String> {
     Ok(out_path)
 }
 
-async fn analyze_with_clang(config: Arc<Config>, args: &[String]) -> Result<(), String> {
-    // Initialize clang
-    let clang = clang::Clang::new()?;
-    let index = clang::Index::new(&clang, false, true);
+fn collect_headers(dir: &Path, into: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_headers(&path, into)?;
+        }
+        else if path.extension().is_some_and(|e|
+            ["h", "hh", "hpp", "hxx", "inl", "ipp", "tpp"].contains(&e.to_string_lossy().as_ref())
+        ) {
+            into.push(path);
+        }
+    }
+    Ok(())
+}
 
-    // Create a single source file that includes all headers
-    let target_src = create_analyzable_file(config.clone())?;
+/// Hash of everything that affects the parsed AST: the compile arguments 
+/// and the headers in the source directories
+fn cache_key(config: &Config, args: &[String]) -> Result<u64, String> {
+    let mut headers = Vec::new();
+    for src in &config.sources {
+        collect_headers(&config.input_dir.join(src.dir.to_pathbuf()), &mut headers)
+            .map_err(|e| format!("Unable to read headers of {}: {e}", src.name))?;
+    }
+    headers.sort();
+    headers.dedup();
 
+    // The flags pick the language the headers are parsed as and whether 
+    // macros are recorded, so the AST depends on them too
+    let mut data = format!("c={} objc={}\n", config.analysis.c, config.analysis.objc).into_bytes();
+    data.extend(args.join("\n").into_bytes());
+    for hdr in headers {
+        data.extend(hdr.to_string_lossy().as_bytes());
+        data.extend(fs::read(&hdr).map_err(|e| format!(
+            "Unable to read {}: {e}", hdr.to_string_lossy()
+        ))?);
+    }
+    Ok(stable_hash(&data))
+}
+
+/// Parse the headers, reusing the AST saved by the previous run if caching 
+/// is enabled and nothing has changed since
+fn parse_cached<'i>(
+    config: Arc<Config>,
+    index: &'i Index<'i>,
+    target_src: &Path,
+    args: &[String],
+) -> Result<TranslationUnit<'i>, String> {
+    // Macros are only in the AST with a detailed preprocessing record, and 
+    // they're only documented for C
+    let parse = || index.parser(target_src)
+        .arguments(args)
+        .detailed_preprocessing_record(config.analysis.c)
+        .parse()
+        .map_err(|e| format!("{e}"));
+    if !config.analysis.cache {
+        return parse();
+    }
+
+    let dir = config.input_dir.join(&config.analysis.cache_dir);
+    let ast_file = dir.join("ast.bin");
+    let key_file = dir.join("key.txt");
+    let key = format!("{:016x}", cache_key(&config, args)?);
+
+    if fs::read_to_string(&key_file).is_ok_and(|k| k == key)
+        && let Ok(unit) = TranslationUnit::from_ast(index, &ast_file)
+    {
+        return Ok(unit);
+    }
+
+    let unit = parse()?;
+    let saved = fs::create_dir_all(&dir)
+        .map_err(|e| e.to_string())
+        .and_then(|_| unit.save(&ast_file).map_err(|e| format!("{e}")))
+        .and_then(|_| fs::write(&key_file, key).map_err(|e| e.to_string()));
+    if let Err(e) = saved {
+        log::warn(Category::Build, format!("Unable to save AST cache: {e}"));
+    }
+    Ok(unit)
+}
+
+/// A spinner showing the phase of the build
+fn spinner() -> Arc<ProgressBar> {
     let pbar = Arc::from(ProgressBar::new_spinner());
     pbar.set_style(
         ProgressStyle::with_template("{msg:>15} {spinner} [{elapsed_precise}]")
@@ -60,63 +149,151 @@ async fn analyze_with_clang(config: Arc<Config>, args: &[String]) -> Result<(),
                 "░░░░░░░",
             ]),
     );
-    pbar.set_message("Analyzing");
+    // The spinner would get in the way of JSON output and --quiet
+    if log::is_json() || !log::enabled(Level::Info) {
+        pbar.set_draw_target(ProgressDrawTarget::hidden());
+    }
     pbar.enable_steady_tick(Duration::from_millis(50));
+    pbar
+}
 
-    // Create parser
-    let unit = index.parser(&target_src).arguments(args).parse()?;
+async fn analyze_with_clang(configs: &[Arc<Config>], args: &[String]) -> Result<(), String> {
+    let config = configs[0].clone();
 
-    // Build the navbar first
-    pbar.set_message("Setting up");
-    let builder = Builder::new(config, unit.get_entity(), &clang, &index, args)?;
+    // Initialize clang
+    let clang = clang::Clang::new()?;
+    let index = clang::Index::new(&clang, false, true);
 
-    // Build the doc files
-    pbar.set_message("Building docs");
-    builder.build(Some(pbar.clone())).await?;
+    // Create a single source file that includes all headers
+    let target_src = create_analyzable_file(config.clone())?;
 
-    pbar.set_message("Cleaning up files");
+    let pbar = spinner();
+    let mut timings = Timings::default();
 
-    // Clean up analyzable file
-    fs::remove_file(target_src).unwrap();
+    // Create parser
+    timings.phase("Parsing", &pbar);
+    let unit = match parse_cached(config.clone(), &index, &target_src, args) {
+        Ok(unit) => unit,
+        Err(e) => {
+            pbar.finish_and_clear();
+            drop(fs::remove_file(target_src));
+            return Err(e);
+        }
+    };
 
-    pbar.finish_using_style();
+    // The headers are the same for every config, so they're only parsed once
+    let mut pbar = Some(pbar);
+    for config in configs {
+        let pbar = pbar.take().unwrap_or_else(spinner);
+        let res = build_parsed(
+            config.clone(), &unit, &clang, &index, args, pbar.clone(), std::mem::take(&mut timings),
+        ).await;
+        if let Err(e) = res {
+            pbar.finish_and_clear();
+            drop(fs::remove_file(target_src));
+            return Err(e);
+        }
+    }
+
+    // Clean up analyzable file
+    fs::remove_file(&target_src)
+        .map_err(|e| format!("Unable to remove {}: {e}", target_src.to_string_lossy()))?;
 
     Ok(())
 }
 
-async fn analyze_with_cmake(config: Arc<Config>) -> Result<(), String> {
-    // Configure the cmake project
-    cmake::cmake_configure(
-        &config.cmake.as_ref().unwrap().build_dir,
-        &config
-            .cmake
-            .as_ref()
-            .unwrap()
-            .config_args,
-    )?;
-
-    // Build the cmake project
-    if config.cmake.as_ref().unwrap().build {
-        cmake::cmake_build(
-            &config.cmake.as_ref().unwrap().build_dir,
-            &config
-                .cmake
-                .as_ref()
-                .unwrap()
-                .build_args,
-        )?;
-    }
-
-    analyze_with_clang(
-        config.clone(),
-        &cmake::cmake_compile_args_for(config).expect("Unable to infer CMake compile args"),
-    )
-    .await?;
+/// Generate the docs for one config from the parsed headers
+async fn build_parsed(
+    config: Arc<Config>,
+    unit: &TranslationUnit<'_>,
+    clang: &clang::Clang,
+    index: &Index<'_>,
+    args: &[String],
+    pbar: Arc<ProgressBar>,
+    mut timings: Timings,
+) -> Result<(), String> {
+    timings.phase("Loading entities", &pbar);
+    let builder = Builder::new(config.clone(), unit.get_entity(), clang, index, args)?;
+
+    // Copy & minify assets and build the navbar first
+    timings.phase("Minifying assets", &pbar);
+    let builder = builder.setup()?;
+
+    // Build the doc files
+    timings.phase("Generating", &pbar);
+    let pages = builder.build(Some(pbar.clone())).await?;
+
+    let mut examples = None;
+    if config.check_examples {
+        timings.phase("Checking examples", &pbar);
+        examples = Some(check_examples(&builder)?);
+    }
+    let mut links = None;
+    if config.check_links {
+        timings.phase("Checking links", &pbar);
+        links = Some(check_links(&builder)?);
+    }
+    timings.finish();
+
+    let failures = builder.diagnostics.failures();
+    if config.strict && !failures.is_empty() {
+        return Err(format!(
+            "Strict mode is enabled and {} page(s) or example(s) failed to build:\n  - {}",
+            failures.len(),
+            failures.join("\n  - ")
+        ));
+    }
+
+    let warnings = builder.diagnostics.warnings();
+    if config.strict && !warnings.is_empty() {
+        return Err(format!(
+            "Strict mode is enabled and the build produced {}",
+            builder.diagnostics.summary()
+        ));
+    }
+
+    pbar.finish_and_clear();
+
+    log::info(Category::Timing, timings.to_text().trim_end());
+    log::info(Category::Build, fmt_stats(&builder, pages).trim_end());
+    if let Some(count) = examples {
+        log::info(Category::Build, format!("All {count} example(s) compiled"));
+    }
+    if let Some(count) = links {
+        log::info(Category::Links, format!("Checked {count} internal link(s)"));
+    }
+
+    if !failures.is_empty() {
+        log::warn(Category::Build, format!(
+            "{} page(s) failed to build and were left out:\n  - {}",
+            failures.len(),
+            failures.join("\n  - ")
+        ));
+    }
+    if !warnings.is_empty() {
+        log::warn(Category::Build, format!("Docs built with {} documentation warning(s)", warnings.len()));
+    }
+
+    if config.report == Some(Report::Coverage) {
+        let coverage = Coverage::from_builder(&builder);
+        print!("{}", coverage.to_text());
+        fs::write(
+            config.output_dir.join("coverage.json"),
+            serde_json::to_string_pretty(&coverage).map_err(|e| e.to_string())?,
+        ).map_err(|e| format!("Unable to save coverage.json: {e}"))?;
+    }
 
     Ok(())
 }
 
-pub async fn create_docs(config: Arc<Config>) -> Result<(), String> {
+/// Build the docs for several configs of the same project that only differ
+/// in what's generated from the headers, like the languages in
+/// `i18n.languages`. The headers are only parsed once for all of them
+pub async fn create_docs(configs: &[Arc<Config>]) -> Result<(), String> {
+    let Some(config) = configs.first().cloned() else {
+        return Ok(());
+    };
+
     // Execute prebuild commands
     if let Some(cmds) = config.run.as_ref().map(|c| &c.prebuild) {
         for cmd in cmds {
@@ -124,12 +301,11 @@ pub async fn create_docs(config: Arc<Config>) -> Result<(), String> {
         }
     }
 
-    // Build based on mode
-    if config.cmake.is_some() {
-        analyze_with_cmake(config).await
-    }
-    // Build with extra compile args only
-    else {
-        analyze_with_clang(config.clone(), &config.analysis.compile_args).await
-    }
+    // Get the compile args from the build system if there is one, otherwise 
+    // build with the extra compile args only
+    let args = match buildsystem::infer_compile_args(config.clone())? {
+        Some(args) => args,
+        None => config.analysis_args()?,
+    };
+    analyze_with_clang(configs, &args).await
 }
@@ -67,16 +67,54 @@ impl<'a> Annotations<'a> {
         self.annotations.get_mut(self.next_in_iter - 1).unwrap().value = Some(value);
     }
 
+    /// Skip over a markdown link so its label and URL don't get annotated
+    fn skip_link(raw: &'a str, iter_ix: &mut usize) -> bool {
+        let rest = &raw[*iter_ix..];
+        if !rest.starts_with('[') {
+            return false;
+        }
+        // Only look as far as the end of the label, so text with brackets
+        // that aren't links isn't scanned to the end for every bracket
+        let Some(mid) = rest.find(']') else { return false; };
+        if !rest[mid..].starts_with("](") {
+            return false;
+        }
+        let Some(end) = rest[mid..].find(')') else { return false; };
+        *iter_ix += mid + end + 1;
+        true
+    }
+
+    /// Skip over inline code since entities in code spans are linked when 
+    /// the markdown is formatted instead
+    fn skip_code(raw: &'a str, iter_ix: &mut usize) -> bool {
+        let rest = &raw[*iter_ix..];
+        let ticks = rest.len() - rest.trim_start_matches('`').len();
+        if ticks == 0 {
+            return false;
+        }
+        let fence = "`".repeat(ticks);
+        let Some(end) = rest[ticks..].find(&fence) else { return false; };
+        *iter_ix += ticks + end + ticks;
+        true
+    }
+
     fn skip_to_next_word(raw: &'a str, iter_ix: &mut usize) {
-        while let Some(i) = raw.chars().nth(*iter_ix) && !i.is_alphanumeric() {
-            *iter_ix += 1;
+        loop {
+            if Self::skip_link(raw, iter_ix) || Self::skip_code(raw, iter_ix) {
+                continue;
+            }
+            match raw[*iter_ix..].chars().next() {
+                Some(i) if !i.is_alphanumeric() => *iter_ix += i.len_utf8(),
+                _ => break,
+            }
         }
     }
 
+    /// `iter_ix` is a byte offset into `raw`, so the ranges can be used to
+    /// replace the words directly
     fn next_word(raw: &'a str, iter_ix: &mut usize) -> Option<(Range<usize>, String)> {
         let start = *iter_ix;
-        let res: String = raw.chars()
-            .skip(*iter_ix)
+        let res: String = raw[*iter_ix..].chars()
             .take_while(|c| c.is_alphanumeric())
             .collect();
         *iter_ix += res.len();
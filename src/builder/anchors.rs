@@ -0,0 +1,75 @@
+use std::{cell::RefCell, collections::HashSet};
+
+thread_local! {
+    /// Ids used so far on the page being built on this thread
+    static PAGE_IDS: RefCell<Option<HashSet<String>>> = const { RefCell::new(None) };
+    /// Glossary terms that already have a tooltip on the page being built on
+    /// this thread
+    static PAGE_TERMS: RefCell<Option<HashSet<usize>>> = const { RefCell::new(None) };
+}
+
+/// Make an id unique among `used` by appending `-1`, `-2`, etc. like GitHub 
+/// does for headings with the same text
+pub fn unique_in(used: &mut HashSet<String>, id: &str) -> String {
+    let mut res = id.to_owned();
+    let mut num = 0;
+    while !used.insert(res.clone()) {
+        num += 1;
+        res = format!("{id}-{num}");
+    }
+    res
+}
+
+/// Keeps track of the ids on a page while it's being built, so headings in 
+/// different doc comments on the same page don't get the same id. Pages are 
+/// generated on one thread, so the ids are kept per thread until this is 
+/// dropped
+pub struct PageIds;
+
+impl PageIds {
+    pub fn begin() -> Self {
+        PAGE_IDS.with(|ids| *ids.borrow_mut() = Some(HashSet::new()));
+        PAGE_TERMS.with(|terms| *terms.borrow_mut() = Some(HashSet::new()));
+        Self
+    }
+}
+
+impl Drop for PageIds {
+    fn drop(&mut self) {
+        PAGE_IDS.with(|ids| *ids.borrow_mut() = None);
+        PAGE_TERMS.with(|terms| *terms.borrow_mut() = None);
+    }
+}
+
+/// Get an id for a heading that isn't used anywhere else on the page being 
+/// built. Outside of pages (like in docs.json) the id is left as is
+pub fn heading_id(id: &str) -> String {
+    PAGE_IDS.with(|ids| match ids.borrow_mut().as_mut() {
+        Some(used) => unique_in(used, id),
+        None => id.to_owned(),
+    })
+}
+
+/// Mark an id as used on the page being built without changing it, for ids 
+/// that are linked to directly like `{#custom}` heading ids
+pub fn reserve_id(id: &str) {
+    PAGE_IDS.with(|ids| {
+        if let Some(used) = ids.borrow_mut().as_mut() {
+            used.insert(id.to_owned());
+        }
+    });
+}
+
+/// Mark a glossary term as having a tooltip on the page being built
+pub fn mark_page_term(term: usize) {
+    PAGE_TERMS.with(|terms| {
+        if let Some(used) = terms.borrow_mut().as_mut() {
+            used.insert(term);
+        }
+    });
+}
+
+/// Glossary terms that already have a tooltip on the page being built
+pub fn page_terms() -> Vec<usize> {
+    PAGE_TERMS.with(|terms| terms.borrow().iter().flatten().copied().collect())
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use clang::{source::SourceRange, Entity};
+
+/// A `[[flash::name(args...)]]` attribute on an entity. LibClang drops
+/// attributes it doesn't know, so these are read from the tokens instead
+pub struct FlashAttribute {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// Get the tokens of the attributes written right before an entity, like
+/// `[[flash::hide]] void f();`, which aren't part of the entity's range
+fn leading_attribute_tokens(entity: &Entity) -> Vec<String> {
+    let Some(start) = entity.get_range().map(|r| r.get_start()) else {
+        return Vec::new();
+    };
+    let location = start.get_file_location();
+    let (Some(file), Some(end)) = (location.file, location.offset.checked_sub(1)) else {
+        return Vec::new();
+    };
+    let tokens = SourceRange::new(
+        file.get_offset_location(end.saturating_sub(512)),
+        file.get_offset_location(end),
+    )
+    .tokenize()
+    .into_iter()
+    .map(|t| t.get_spelling())
+    .collect::<Vec<_>>();
+
+    // Walk back over every `[[...]]` directly before the entity
+    let mut first = tokens.len();
+    while first >= 2 && tokens[first - 1] == "]" && tokens[first - 2] == "]" {
+        let mut depth = 0;
+        let mut ix = first;
+        while ix > 0 {
+            ix -= 1;
+            match tokens[ix].as_str() {
+                "]" => depth += 1,
+                "[" => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+        // The bracket that closes the last `]` has to be the first of a `[[`
+        if depth != 0 || tokens[ix + 1] != "[" {
+            break;
+        }
+        first = ix;
+    }
+    tokens[first..].to_vec()
+}
+
+/// Parse the `flash::` attributes out of a list of tokens with attribute
+/// specifiers in them
+fn parse_attributes(tokens: &[String]) -> Vec<FlashAttribute> {
+    let mut res = Vec::new();
+    let mut ix = 0;
+    while ix + 3 < tokens.len() {
+        if !(tokens[ix] == "[" && tokens[ix + 1] == "[") {
+            ix += 1;
+            continue;
+        }
+        ix += 2;
+        // Each attribute in the specifier, like `flash::category("Audio")`
+        while ix < tokens.len() && tokens[ix] != "]" {
+            let is_flash = tokens.get(ix).is_some_and(|t| t == "flash")
+                && tokens.get(ix + 1).is_some_and(|t| t == "::");
+            let name = tokens.get(ix + 2).cloned().unwrap_or_default();
+            ix += if is_flash { 3 } else { 1 };
+            let mut args = Vec::new();
+            if tokens.get(ix).is_some_and(|t| t == "(") {
+                let mut depth = 0;
+                let mut arg = String::new();
+                while let Some(token) = tokens.get(ix) {
+                    ix += 1;
+                    match token.as_str() {
+                        "(" => {
+                            depth += 1;
+                            if depth == 1 {
+                                continue;
+                            }
+                        }
+                        ")" => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        "," if depth == 1 => {
+                            args.push(std::mem::take(&mut arg));
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    arg += token.trim_matches('"');
+                }
+                if !arg.is_empty() {
+                    args.push(arg);
+                }
+            }
+            if is_flash {
+                res.push(FlashAttribute { name, args });
+            }
+            if tokens.get(ix).is_some_and(|t| t == ",") {
+                ix += 1;
+            }
+        }
+    }
+    res
+}
+
+/// The attributes of a declaration, read from its tokens once
+struct Attributes {
+    flash: Vec<FlashAttribute>,
+    cpp: Vec<String>,
+}
+
+/// Get the attributes of an entity, either written before it or inside its
+/// declaration (like `class [[flash::hide]] Foo`). Reading them means
+/// tokenizing the declaration, so they're cached by where the entity is
+fn attributes(entity: &Entity) -> Arc<Attributes> {
+    type Key = (PathBuf, u32, Option<String>);
+    static CACHE: OnceLock<Mutex<HashMap<Key, Arc<Attributes>>>> = OnceLock::new();
+
+    let location = entity.get_location().map(|l| l.get_file_location());
+    let Some((Some(file), offset)) = location.map(|l| (l.file, l.offset)) else {
+        return Arc::new(read_attributes(entity));
+    };
+    let key = (file.get_path(), offset, entity.get_name());
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(attrs) = cache.lock().unwrap().get(&key) {
+        return attrs.clone();
+    }
+    let attrs = Arc::new(read_attributes(entity));
+    cache.lock().unwrap().insert(key, attrs.clone());
+    attrs
+}
+
+fn read_attributes(entity: &Entity) -> Attributes {
+    let name = entity.get_name().unwrap_or_default();
+    let own = entity.get_range()
+        .map(|r| r.tokenize())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|t| t.get_spelling())
+        .take_while(|t| t != "{" && t != ";")
+        .collect::<Vec<_>>();
+    // Don't read the leading attributes twice if they're part of the range
+    let mut tokens = if own.first().is_some_and(|t| t == "[") {
+        Vec::new()
+    } else {
+        leading_attribute_tokens(entity)
+    };
+    // Only the attributes before the name are ones of the declaration
+    let before_name = tokens.len() + own.iter().position(|t| *t == name).unwrap_or(own.len());
+    tokens.extend(own);
+    Attributes {
+        flash: parse_attributes(&tokens),
+        cpp: parse_cpp_attributes(&tokens[..before_name]),
+    }
+}
+
+/// Whether an entity has `[[flash::hide]]`. The `[[flash::...]]` attributes
+/// are an alternative to comment commands for headers that are generated
+pub fn has_hide_attribute(entity: &Entity) -> bool {
+    attributes(entity).flash.iter().any(|a| a.name == "hide")
+}
+
+/// Get the arguments of every `[[flash::name(...)]]` of an entity
+fn flash_args(entity: &Entity, name: &str) -> Vec<String> {
+    attributes(entity).flash
+        .iter()
+        .filter(|a| a.name == name)
+        .flat_map(|a| a.args.iter().cloned())
+        .collect()
+}
+
+/// Get the modules an entity was added to with `[[flash::category("Name")]]`
+pub fn attribute_categories(entity: &Entity) -> Vec<String> {
+    flash_args(entity, "category")
+}
+
+/// Get the custom badges of an entity from `[[flash::badge("Text")]]`
+pub fn attribute_badges(entity: &Entity) -> Vec<String> {
+    flash_args(entity, "badge")
+}
+
+/// Join the spellings of tokens back into code, only adding spaces between
+/// words
+fn join_spellings(tokens: &[String]) -> String {
+    let is_word = |t: &str| t.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+    let mut res = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && ((is_word(&tokens[i - 1]) && is_word(token)) || tokens[i - 1] == ",") {
+            res.push(' ');
+        }
+        res += token;
+    }
+    res
+}
+
+/// Find the index of the token that closes the bracket at `start`
+fn closing(tokens: &[String], start: usize, open: &str, close: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (ix, token) in tokens.iter().enumerate().skip(start) {
+        if token == open {
+            depth += 1;
+        } else if token == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(ix);
+            }
+        }
+    }
+    None
+}
+
+/// Get the C++ attributes of a declaration as written, like `[[nodiscard]]`,
+/// `alignas(16)` or `__attribute__((visibility("default")))`, since these
+/// affect how it can be used. `[[flash::...]]` attributes are left out.
+/// Attributes hidden behind macros aren't found since their tokens are the
+/// macro's name
+pub fn cpp_attributes(entity: &Entity) -> Vec<String> {
+    attributes(entity).cpp.clone()
+}
+
+/// Find the C++ attributes in the tokens before the name of a declaration
+fn parse_cpp_attributes(tokens: &[String]) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut ix = 0;
+    while ix < tokens.len() {
+        let end = match tokens[ix].as_str() {
+            "[" if tokens.get(ix + 1).is_some_and(|t| t == "[") => closing(tokens, ix, "[", "]"),
+            "alignas" | "__attribute__" | "__declspec" if tokens.get(ix + 1).is_some_and(|t| t == "(") =>
+                closing(tokens, ix + 1, "(", ")"),
+            _ => None,
+        };
+        let Some(end) = end else {
+            ix += 1;
+            continue;
+        };
+        let attr = &tokens[ix..=end];
+        if !attr.iter().any(|t| t == "flash") {
+            res.push(join_spellings(attr));
+        }
+        ix = end + 1;
+    }
+    res
+}
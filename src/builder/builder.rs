@@ -1,16 +1,17 @@
 use clang::{Clang, Entity};
-use indicatif::ProgressBar;
-use std::{collections::HashMap, fs, sync::Arc};
-use strfmt::strfmt;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{any::Any, collections::{HashMap, HashSet}, fs, io::Read, panic::{self, AssertUnwindSafe}, path::{Path, PathBuf}, sync::{Arc, Mutex, OnceLock}};
 use tokio::task::JoinHandle;
 
 use crate::{
-    config::{Config},
-    html::{GenHtml, Html, process::{minify_js, minify_css, minify_html}},
+    config::{Config, LinkMode, OutputMode},
+    html::{GenHtml, Html, HtmlElement, escape::escape_attr, process::{minify_js, minify_css, minify_html}},
+    log::Category,
+    template::{render, render_with_lists},
     url::UrlPath,
 };
 
-use super::{files::Root, namespace::{Namespace}, tutorial::TutorialFolder, traits::{OutputEntry, BuildResult, Entry}};
+use super::{anchors::PageIds, bundle::build_bundle, changelog::Changelog, diagnostics::Diagnostics, feed::build_feed, files::Root, glossary::Glossary, group::Group, headers::HeaderList, includes::IncludeGraph, index::Index, json::output_json, namespace::{Namespace}, objc::ObjCItem, page::Page, permalinks::build_permalinks, previews::{build_previews, collect_previews, Preview}, print::build_print, references::References, shared::{collect_relates, stable_hash}, symbols::build_symbols, tutorial::TutorialFolder, traits::{ASTEntry, EntityMethods, OutputEntry, BuildResult, Entry, NavItem}};
 
 pub struct Builder<'e> {
     pub config: Arc<Config>,
@@ -18,9 +19,33 @@ pub struct Builder<'e> {
     pub clang: &'e Clang,
     pub index: &'e clang::Index<'e>,
     pub args: &'e [String],
+    pub diagnostics: Diagnostics,
+    pub changelog: Changelog,
     file_roots: Vec<Root>,
     tutorials: TutorialFolder,
+    pages: Vec<Page>,
+    groups: Vec<Group<'e>>,
+    indexes: Vec<Index>,
+    headers: Option<HeaderList>,
+    glossary: Option<Glossary>,
+    /// Objective-C interfaces, protocols and categories
+    objc: Vec<ObjCItem<'e>>,
     nav_cache: Option<String>,
+    /// Fingerprinted names of CSS & JS files
+    assets: Arc<HashMap<String, String>>,
+    /// What refers to each entity, indexed the first time it's needed
+    references: OnceLock<References>,
+    /// What each documented header includes, read the first time it's needed
+    include_graph: OnceLock<IncludeGraph>,
+    /// Links to these get a hover preview card, collected the first time
+    /// a link is formatted
+    previews: OnceLock<HashMap<String, Preview>>,
+    /// The `@relates` targets of free functions, read the first time a
+    /// class' related functions are listed
+    relates: OnceLock<HashMap<String, String>>,
+    /// Contents of headers whose text is read for comments libclang doesn't 
+    /// know about, so each is only read once
+    header_sources: Mutex<HashMap<PathBuf, Option<Arc<String>>>>,
 }
 
 impl<'e> Builder<'e> {
@@ -31,34 +56,109 @@ impl<'e> Builder<'e> {
         index: &'e clang::Index<'e>,
         args: &'e [String],
     ) -> Result<Self, String> {
-        Self {
+        let objc = ObjCItem::from_config(config.clone(), &root);
+        let root = Namespace::new_root(root, config.clone());
+        let file_roots = Root::from_config(config.clone());
+        let builder = Self {
             config: config.clone(),
-            root: Namespace::new_root(root),
+            groups: Group::from_config(config.clone(), &root)?,
+            root,
             clang,
             index,
             args,
-            file_roots: Root::from_config(config.clone()),
-            tutorials: TutorialFolder::from_config(config),
+            diagnostics: Diagnostics::new(config.strict),
+            changelog: Changelog::new(config.clone())?,
+            indexes: Index::all(config.clone(), &file_roots),
+            headers: HeaderList::new(config.clone(), &file_roots),
+            glossary: Glossary::new(config.clone()),
+            objc,
+            file_roots,
+            tutorials: TutorialFolder::from_config(config.clone())?,
+            pages: Page::from_config(config)?,
             nav_cache: None,
+            assets: Arc::new(HashMap::new()),
+            references: OnceLock::new(),
+            include_graph: OnceLock::new(),
+            previews: OnceLock::new(),
+            relates: OnceLock::new(),
+            header_sources: Mutex::new(HashMap::new()),
+        };
+        for collision in builder.root.collisions() {
+            builder.diagnostics.warn(Category::Parse, collision);
         }
-        .setup()
+        Ok(builder)
     }
 
-    fn setup(mut self) -> Result<Self, String> {
+    pub fn references(&self) -> &References {
+        self.references.get_or_init(|| References::build(self))
+    }
+
+    pub fn include_graph(&self) -> &IncludeGraph {
+        self.include_graph.get_or_init(|| IncludeGraph::new(self.config.clone(), &self.file_roots))
+    }
+
+    pub fn header_source(&self, path: &Path) -> Option<Arc<String>> {
+        if let Some(source) = self.header_sources.lock().unwrap().get(path) {
+            return source.clone();
+        }
+        let source = fs::read_to_string(path).ok().map(Arc::new);
+        self.header_sources.lock().unwrap().insert(path.to_owned(), source.clone());
+        source
+    }
+
+    pub fn previews(&self) -> &HashMap<String, Preview> {
+        self.previews.get_or_init(|| collect_previews(self))
+    }
+
+    pub fn relates(&self) -> &HashMap<String, String> {
+        self.relates.get_or_init(|| collect_relates(self))
+    }
+
+    /// Copy and minify assets and prebuild anything shared by all pages
+    pub fn setup(mut self) -> Result<Self, String> {
+        let mut assets = HashMap::new();
+
         // copy & minify CSS
         for script in &self.config.scripts.css {
-            fs::write(
-                self.config.output_dir.join(&script.name),
-                minify_css(script.content.to_string())?,
-            ).map_err(|e| format!("Unable to copy {}: {e}", script.name))?;
+            write_asset(
+                self.config.clone(), &mut assets, &script.name,
+                minify_css(&self.config, script.content.to_string())?,
+            )?;
         }
 
+        // generate custom theme colors
+        write_asset(
+            self.config.clone(), &mut assets, "theme.css",
+            minify_css(&self.config, self.config.theme_css())?,
+        )?;
+
         // transpile, minify, and copy JS
         for script in &self.config.scripts.js {
-            fs::write(
-                &self.config.output_dir.join(&script.name),
-                minify_js(script.content.to_string())?,
-            ).map_err(|e| format!("Unable to copy {}: {e}", script.name))?;
+            write_asset(
+                self.config.clone(), &mut assets, &script.name,
+                minify_js(&self.config, script.content.to_string())?,
+            )?;
+        }
+
+        self.assets = Arc::new(assets);
+
+        // copy static asset folders as-is
+        for dir in &self.config.static_dirs {
+            copy_dir(&self.config.input_dir.join(dir), &self.config.output_dir.join(dir))
+                .map_err(|e| format!("Unable to copy static directory '{}': {e}", dir.to_string_lossy()))?;
+        }
+
+        // copy local copies of the diagram and math renderers, including the
+        // chunks next to the mermaid module
+        if is_local_asset(&self.config.scripts.mermaid) {
+            let module = self.config.input_dir.join(&self.config.scripts.mermaid);
+            copy_dir(module.parent().unwrap_or(Path::new("")), &self.config.output_dir.join("mermaid"))
+                .map_err(|e| format!("Unable to copy mermaid from '{}': {e}", module.to_string_lossy()))?;
+        }
+        if is_local_asset(&self.config.scripts.katex) {
+            let dir = self.config.input_dir.join(&self.config.scripts.katex);
+            copy_dir(&dir, &self.config.output_dir.join("katex"))
+                .map_err(|e| format!("Unable to copy KaTeX from '{}': {e}", dir.to_string_lossy()))?;
         }
 
         // copy icon
@@ -106,19 +206,73 @@ impl<'e> Builder<'e> {
             }
         }
 
+        // versions list and the latest alias are shared by all versions
+        self.setup_versions()?;
+
         // prebuild nav for performance
         self.prebuild()?;
 
         Ok(self)
     }
 
+    fn setup_versions(&self) -> Result<(), String> {
+        let Some(ref versions) = self.config.versions else {
+            return Ok(());
+        };
+        let root = self.config.output_dir.parent().unwrap();
+        let root_url = self.config.output_url.as_ref().unwrap_or(&UrlPath::new()).parent();
+
+        fs::write(
+            root.join("versions.json"),
+            serde_json::to_string(&all_versions(self.config.clone()))
+                .map_err(|e| format!("Unable to save versions.json: {e}"))?,
+        ).map_err(|e| format!("Unable to save versions.json: {e}"))?;
+
+        if let Some(ref latest) = versions.latest {
+            let target = root_url.join(UrlPath::part(latest));
+            fs::create_dir_all(root.join("latest"))
+                .map_err(|e| format!("Unable to create latest alias: {e}"))?;
+            fs::write(
+                root.join("latest").join("index.html"),
+                format!(
+                    "<!DOCTYPE html><html><head>\
+                    <meta http-equiv=\"refresh\" content=\"0; url={target}/\">\
+                    <link rel=\"canonical\" href=\"{target}/\">\
+                    </head></html>"
+                ),
+            ).map_err(|e| format!("Unable to create latest alias: {e}"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Start building the page for an entry. If the entry can't be built, 
+    /// because of an error or a panic for some unusual declaration, it's 
+    /// skipped and reported so the rest of the docs still get built
     pub fn create_output_for<E: OutputEntry<'e>>(&'e self, entry: &E) -> BuildResult {
-        let (template, vars) = entry.output(self);
+        let error = match panic::catch_unwind(AssertUnwindSafe(|| self.try_create_output_for(entry))) {
+            Ok(Ok(handles)) => return Ok(handles),
+            Ok(Err(e)) => e,
+            Err(payload) => panic_message(payload),
+        };
+        let name = panic::catch_unwind(AssertUnwindSafe(|| entry.name()))
+            .unwrap_or_else(|_| String::from("an entry"));
+        self.diagnostics.failed(format!("Unable to build {name}: {error}"));
+        Ok(Vec::new())
+    }
+
+    fn try_create_output_for<E: OutputEntry<'e>>(&'e self, entry: &E) -> BuildResult {
+        let (template, vars) = {
+            let _ids = PageIds::begin();
+            entry.output(self)
+        };
         Ok(vec![Self::create_output_in_thread(
             self.config.clone(),
+            self.assets.clone(),
             self.build_nav()?,
             entry.name(),
             entry.description(self),
+            entry.signatures(),
             entry.url(),
             template,
             vars,
@@ -127,9 +281,11 @@ impl<'e> Builder<'e> {
 
     fn create_output_in_thread(
         config: Arc<Config>,
+        assets: Arc<HashMap<String, String>>,
         nav: String,
         name: String,
         description: String,
+        signatures: Vec<String>,
         target_url: UrlPath,
         template: Arc<String>,
         vars: Vec<(&'static str, Html)>,
@@ -141,14 +297,17 @@ impl<'e> Builder<'e> {
                 format!("{} - {} Docs", name, config.project.name)
             };
 
+            let page_url = target_url.to_absolute(config.clone()).to_string();
+
             let mut fmt = default_format(config.clone());
             fmt.extend(HashMap::from([
+                ("page_url".to_owned(), escape_attr(&page_url)),
+                ("page_title".to_owned(), escape_attr(&title)),
+                ("page_description".to_owned(), escape_attr(&description)),
                 (
-                    "page_url".to_owned(),
-                    target_url.to_absolute(config.clone()).to_string(),
+                    "page_meta".to_owned(),
+                    fmt_page_meta(config.clone(), &title, &description, &page_url),
                 ),
-                ("page_title".to_owned(), title.clone()),
-                ("page_description".to_owned(), description.clone()),
             ]));
             fmt.extend(
                 vars.into_iter()
@@ -156,27 +315,52 @@ impl<'e> Builder<'e> {
                     .collect::<Vec<_>>(),
             );
 
-            let content = minify_html(
-                strfmt(&template, &fmt)
-                .map_err(|e| format!("Unable to format {target_url}: {e}"))?
-            )?;
+            let lists = HashMap::from([
+                ("signatures".to_owned(), signatures.iter().map(|s| escape_attr(s)).collect()),
+                ("versions".to_owned(), all_versions(config.clone()).iter().map(|v| escape_attr(v)).collect()),
+            ]);
+            let raw_content = rewrite_asset_refs(
+                config.clone(),
+                &assets,
+                render_with_lists(&template, &fmt, &lists)
+                    .map_err(|e| format!("Unable to format {target_url}: {e}"))?,
+            );
+            let content = minify_html(&config, raw_content.clone())?;
+
+            // The content used for client-side navigation and bundling keeps 
+            // its absolute links, only the full page is made relative
+            let page_file = target_url.page_file(config.clone());
+            let relative = config.site.links == LinkMode::Relative;
+            let main_content = if relative {
+                minify_html(&config, relative_links(config.clone(), raw_content, &page_file))?
+            } else {
+                content.clone()
+            };
 
             let mut page_fmt = default_format(config.clone());
             page_fmt.extend(HashMap::from([
                 (
                     "head_content".to_owned(),
-                    strfmt(&config.templates.head, &fmt)
+                    render_with_lists(&config.templates.head, &fmt, &lists)
                         .map_err(|e| format!("Unable to format head for {target_url}: {e}"))?,
                 ),
                 ("navbar_content".to_owned(), nav),
-                ("main_content".to_owned(), content.clone()),
+                ("main_content".to_owned(), main_content),
             ]));
-            let page = minify_html(
-                strfmt(&config.templates.page, &page_fmt)
-                .map_err(|e| format!("Unable to format {target_url}: {e}"))?
-            )?;
+            let mut page = rewrite_asset_refs(
+                config.clone(),
+                &assets,
+                render_with_lists(&config.templates.page, &page_fmt, &lists)
+                    .map_err(|e| format!("Unable to format {target_url}: {e}"))?,
+            );
+            if relative {
+                page = relative_links(config.clone(), page, &page_file);
+            }
+            let page = minify_html(&config, page)?;
             
-            let output_dir = config.output_dir.join(target_url.to_pathbuf());
+            // With file URLs the page is saved as Class.html, but the content 
+            // and metadata for client-side navigation still go in Class/
+            let output_dir = config.output_dir.join(target_url.cased(config.clone()).to_pathbuf());
 
             // Make sure output directory exists
             fs::create_dir_all(&output_dir)
@@ -185,36 +369,31 @@ impl<'e> Builder<'e> {
             // Save metadata to a file
             fs::write(
                 output_dir.join("metadata.json"),
-                format!(
-                    r#"{{"title": "{}", "description": "{}"}}"#,
-                    title, description,
-                )
+                serde_json::json!({
+                    "title": title,
+                    "description": description,
+                    "signatures": signatures,
+                }).to_string(),
             ).map_err(|e| format!("Unable to save metadata for {target_url}: {e}"))?;
 
             // Write the plain content output
-            fs::write(
-                config
-                    .output_dir
-                    .join(target_url.to_pathbuf())
-                    .join("content.html"),
-                content,
-            )
+            fs::write(output_dir.join("content.html"), content)
             .map_err(|e| format!("Unable to save {target_url}: {e}"))?;
 
             // Write the full page
-            fs::write(
-                config
-                    .output_dir
-                    .join(target_url.to_pathbuf())
-                    .join("index.html"),
-                page,
-            )
+            fs::write(config.output_dir.join(page_file), page)
             .map_err(|e| format!("Unable to save {target_url}: {e}"))?;
 
             Ok(target_url)
         })
     }
 
+    /// Name of a CSS or JS file in the output directory, which may have been 
+    /// fingerprinted
+    pub fn asset_file(&self, name: &str) -> String {
+        self.assets.get(name).cloned().unwrap_or(name.to_owned())
+    }
+
     fn all_entries(&self) -> Vec<&dyn Entry<'e>> {
         self.root
             .entries
@@ -222,6 +401,12 @@ impl<'e> Builder<'e> {
             .map(|p| p.1 as &dyn Entry<'e>)
             .chain(self.file_roots.iter().map(|p| p as &dyn Entry<'e>))
             .chain([&self.tutorials as &dyn Entry])
+            .chain(self.pages.iter().map(|p| p as &dyn Entry<'e>))
+            .chain(self.groups.iter().map(|p| p as &dyn Entry<'e>))
+            .chain(self.indexes.iter().map(|p| p as &dyn Entry<'e>))
+            .chain(self.headers.iter().map(|p| p as &dyn Entry<'e>))
+            .chain(self.glossary.iter().map(|p| p as &dyn Entry<'e>))
+            .chain(self.objc.iter().map(|p| p as &dyn Entry<'e>))
             .collect()
     }
 
@@ -232,32 +417,86 @@ impl<'e> Builder<'e> {
         Ok(())
     }
 
-    pub async fn build(&self, pbar: Option<Arc<ProgressBar>>) -> Result<(), String> {
+    /// Build the docs, returning the number of pages generated
+    pub async fn build(&self, pbar: Option<Arc<ProgressBar>>) -> Result<usize, String> {
+        let mut pages = 0;
+        if self.config.output.format.has_html() {
+            pages = self.build_html(pbar.clone()).await?;
+        }
+
+        if self.config.output.format.has_json() {
+            if let Some(pbar) = pbar.clone() {
+                pbar.set_message("Generating JSON".to_string());
+            }
+
+            fs::write(
+                &self.config.output_dir.join("docs.json"),
+                serde_json::to_string(&output_json(self))
+                    .map_err(|e| format!("Unable to save docs.json {e}"))?
+            ).map_err(|e| format!("Unable to save docs.json {e}"))?;
+        }
+
+        Ok(pages)
+    }
+
+    async fn build_html(&self, pbar: Option<Arc<ProgressBar>>) -> Result<usize, String> {
         let mut handles = Vec::new();
 
         // Spawn threads for creating docs for all entries
         for entry in self.all_entries() {
-            handles.extend(entry.build(self)?);
+            match entry.build(self) {
+                Ok(built) => handles.extend(built),
+                Err(e) => self.diagnostics.failed(format!("Unable to build {}: {e}", entry.name())),
+            }
         }
 
+        let total = handles.len();
         if let Some(pbar) = pbar.clone() {
             pbar.set_message("Generating output".to_string());
+            pbar.set_style(
+                ProgressStyle::with_template(
+                    "{msg:>15} [{bar:30}] {pos}/{len} [{elapsed_precise}]"
+                )
+                .unwrap()
+                .progress_chars("=> "),
+            );
+            pbar.set_length(total as u64);
         }
 
-        futures::future::join_all(handles.into_iter().map(|handle| {
+        let results = futures::future::join_all(handles.into_iter().map(|handle| {
             let pbar = pbar.clone();
             tokio::spawn(async move {
-                let res = handle.await.map_err(|e| format!("Unable to join {e}"))??;
+                let url = handle.await.map_err(|e| format!("Unable to join {e}"));
                 if let Some(pbar) = pbar {
-                    pbar.set_message(format!("Built {res}"));
+                    pbar.inc(1);
                 }
-                Result::<(), String>::Ok(())
+                url
             })
         }))
-        .await
-        .into_iter()
-        .collect::<Result<Result<Vec<_>, _>, _>>()
-        .map_err(|e| format!("Unable to join {e}"))??;
+        .await;
+
+        // Pages that failed to render are left out like entries that failed 
+        // to build
+        let mut urls = Vec::new();
+        for res in results {
+            match res.map_err(|e| format!("Unable to join {e}")).and_then(|r| r).and_then(|r| r) {
+                Ok(url) => urls.push(url),
+                Err(e) => self.diagnostics.failed(e),
+            }
+        }
+        let pages = urls.len();
+
+        // Pages with the same URL overwrite each other. This happens with 
+        // entities whose names only differ in case when URLs are lowercased, 
+        // and on case-insensitive file systems
+        let mut paths = HashSet::new();
+        for url in &urls {
+            if !paths.insert(url.cased(self.config.clone()).to_raw_string().to_lowercase()) {
+                self.diagnostics.warn(Category::Build, format!(
+                    "Multiple pages are generated at {url} (ignoring case), so only one of them is kept"
+                ));
+            }
+        }
 
         if let Some(pbar) = pbar.clone() {
             pbar.set_message("Generating metadata".to_string());
@@ -272,8 +511,29 @@ impl<'e> Builder<'e> {
                     .collect::<Vec<_>>()
             ).map_err(|e| format!("Unable to save metadata {e}"))?
         ).map_err(|e| format!("Unable to save metadata {e}"))?;
+        build_symbols(self)?;
+        build_previews(self)?;
+        build_permalinks(self)?;
 
-        Ok(())
+        if self.config.output.mode == OutputMode::SingleFile {
+            if let Some(pbar) = pbar.clone() {
+                pbar.set_message("Bundling".to_string());
+            }
+            build_bundle(self, &urls)?;
+        }
+
+        if self.config.feed.is_some() {
+            build_feed(self, &self.tutorials)?;
+        }
+
+        if self.config.export.is_some() {
+            if let Some(pbar) = pbar.clone() {
+                pbar.set_message("Exporting".to_string());
+            }
+            build_print(self, &urls)?;
+        }
+
+        Ok(pages)
     }
 
     pub fn build_nav(&self) -> Result<String, String> {
@@ -282,14 +542,85 @@ impl<'e> Builder<'e> {
         }
         let mut fmt = default_format(self.config.clone());
         fmt.extend([
+            (
+                "tutorials_title".into(),
+                self.config.tr("Tutorials").to_owned(),
+            ),
+            (
+                "entities_title".into(),
+                // C libraries have no classes
+                self.config.tr(if self.config.analysis.c { "API" } else { "Classes" }).to_owned(),
+            ),
+            (
+                "page_content".into(),
+                NavItem::new_root(None, self.pages.iter().map(|p| p.nav()).collect())
+                    .to_html(self.config.clone())
+                    .gen_html(),
+            ),
             (
                 "tutorial_content".into(),
                 self.tutorials.nav().to_html(self.config.clone()).gen_html(),
             ),
+            (
+                "group_content".into(),
+                if self.groups.is_empty() {
+                    String::new()
+                } else {
+                    NavItem::new_root(
+                        Some(self.config.tr("Modules")),
+                        self.groups.iter().map(|g| g.nav()).collect()
+                    )
+                    .to_html(self.config.clone())
+                    .gen_html()
+                },
+            ),
+            (
+                "concept_content".into(),
+                {
+                    let mut concepts = self.root.concepts();
+                    concepts.sort_by_key(|c| c.entity().full_name());
+                    if concepts.is_empty() {
+                        String::new()
+                    } else {
+                        NavItem::new_root(
+                            Some(self.config.tr("Concepts")),
+                            concepts.iter().map(|c| c.nav()).collect()
+                        )
+                        .to_html(self.config.clone())
+                        .gen_html()
+                    }
+                },
+            ),
+            (
+                "objc_content".into(),
+                if self.objc.is_empty() {
+                    String::new()
+                } else {
+                    NavItem::new_root(
+                        Some(self.config.tr("Objective-C")),
+                        self.objc.iter().map(|o| o.nav()).collect()
+                    )
+                    .to_html(self.config.clone())
+                    .gen_html()
+                },
+            ),
             (
                 "entity_content".into(),
                 self.root.nav().to_html(self.config.clone()).gen_html(),
             ),
+            (
+                "index_content".into(),
+                NavItem::new_root(
+                    Some(self.config.tr("Index")),
+                    self.headers.iter()
+                        .map(|h| h.nav())
+                        .chain(self.glossary.iter().map(|g| g.nav()))
+                        .chain(self.indexes.iter().map(|i| i.nav()))
+                        .collect()
+                )
+                    .to_html(self.config.clone())
+                    .gen_html(),
+            ),
             (
                 "file_content".into(),
                 self.file_roots
@@ -299,23 +630,330 @@ impl<'e> Builder<'e> {
                     .join("\n"),
             ),
         ]);
-        strfmt(&self.config.templates.nav, &fmt)
+        render(&self.config.templates.nav, &fmt)
             .map_err(|e| format!("Unable to format navbar: {e}"))
     }
 
+    /// URLs of all pages in the order they appear in the navbar, starting 
+    /// with the index page
+    pub fn nav_order(&self) -> Vec<UrlPath> {
+        let mut concepts = self.root.concepts();
+        concepts.sort_by_key(|c| c.entity().full_name());
+        [UrlPath::new()].into_iter()
+            .chain(self.pages.iter().flat_map(|p| p.nav().links()))
+            .chain(self.tutorials.nav().links())
+            .chain(self.groups.iter().flat_map(|g| g.nav().links()))
+            .chain(concepts.iter().flat_map(|c| c.nav().links()))
+            .chain(self.root.nav().links())
+            .chain(self.objc.iter().flat_map(|o| o.nav().links()))
+            .chain(self.file_roots.iter().flat_map(|r| r.nav().links()))
+            .collect()
+    }
+
     fn prebuild_nav(&mut self) -> Result<(), String> {
         self.nav_cache = Some(self.build_nav()?);
         Ok(())
     }
 }
 
-fn default_format(config: Arc<Config>) -> HashMap<String, String> {
-    HashMap::from([
-        ("project_name".into(), config.project.name.clone()),
-        ("project_version".into(), config.project.version.clone()),
+/// Save a CSS or JS file to the output directory. With fingerprinting 
+/// enabled the file name gets a hash of its content (`default.1a2b3c4d.css`) 
+/// so browsers don't use stale cached versions after the docs are rebuilt
+fn write_asset(
+    config: Arc<Config>,
+    assets: &mut HashMap<String, String>,
+    name: &str,
+    content: String,
+) -> Result<(), String> {
+    let file_name = if config.output.fingerprint {
+        let hash = format!("{:08x}", stable_hash(content.as_bytes()) as u32);
+        let hashed = match name.rsplit_once('.') {
+            Some((stem, ext)) => format!("{stem}.{hash}.{ext}"),
+            None => format!("{name}.{hash}"),
+        };
+        assets.insert(name.to_owned(), hashed.clone());
+        hashed
+    } else {
+        name.to_owned()
+    };
+    let path = config.output_dir.join(&file_name);
+    remove_old_fingerprints(&config.output_dir.join(name), &path);
+    fs::write(&path, content)
+        .map_err(|e| format!("Unable to copy {name}: {e}"))
+}
+
+/// Remove the copies of an asset left in the output by earlier builds, which
+/// have other hashes in their names
+fn remove_old_fingerprints(original: &Path, current: &Path) {
+    let (Some(dir), Some(name)) = (original.parent(), original.file_name().and_then(|n| n.to_str())) else {
+        return;
+    };
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) => (stem, Some(ext)),
+        None => (name, None),
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path == current {
+            continue;
+        }
+        let Some(file) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let hash = file.strip_prefix(stem)
+            .and_then(|f| f.strip_prefix('.'))
+            .and_then(|f| match ext {
+                Some(ext) => f.strip_suffix(ext)?.strip_suffix('.'),
+                None => Some(f),
+            });
+        if hash.is_some_and(|h| h.len() == 8 && h.chars().all(|c| c.is_ascii_hexdigit())) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Point references to CSS & JS files in rendered templates to their
+/// fingerprinted names
+fn rewrite_asset_refs(config: Arc<Config>, assets: &HashMap<String, String>, html: String) -> String {
+    let root = config.output_url.as_ref().unwrap_or(&UrlPath::new()).to_string();
+    assets.iter().fold(html, |html, (name, hashed)| {
+        html.replace(&format!("{root}/{name}\""), &format!("{root}/{hashed}\""))
+    })
+}
+
+/// Make links that start at the output root relative to a page saved at 
+/// `page_file`, which is relative to the output directory. Only attribute 
+/// values and `navigate()` calls are rewritten so text in code blocks is 
+/// left alone
+fn relative_links(config: Arc<Config>, html: String, page_file: &Path) -> String {
+    let root = config.output_url.as_ref().unwrap_or(&UrlPath::new()).to_string();
+    let depth = page_file.components().count().saturating_sub(1);
+    let prefix = if depth == 0 { String::from(".") } else { vec![".."; depth].join("/") };
+
+    let mut html = html.replace(
+        &format!("FLASH_OUTPUT_URL = \"{}\"", escape_attr(&root)),
+        &format!("FLASH_OUTPUT_URL = \"{prefix}\""),
+    );
+    for lead in ["=\"", "='", "navigate('"] {
+        let pattern = format!("{lead}{root}/");
+        let mut res = String::with_capacity(html.len());
+        let mut rest = html.as_str();
+        while let Some(ix) = rest.find(&pattern) {
+            let end = ix + pattern.len();
+            res.push_str(&rest[..ix]);
+            // Protocol-relative URLs like //cdn.example.com point elsewhere
+            if rest[end..].starts_with('/') {
+                res.push_str(&pattern);
+            } else {
+                res += &format!("{lead}{prefix}/");
+            }
+            rest = &rest[end..];
+        }
+        res.push_str(rest);
+        html = res;
+    }
+    html
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `scripts.mermaid` or `scripts.katex` is a path to a local copy
+/// instead of a URL
+fn is_local_asset(value: &str) -> bool {
+    !value.contains("://") && !value.starts_with("//")
+}
+
+/// The URL mermaid or KaTeX is loaded from, with local copies at `local`
+/// in the output
+fn renderer_url(config: &Config, value: &str, local: &str) -> String {
+    if !is_local_asset(value) {
+        return value.to_owned();
+    }
+    format!("{}/{local}", config.output_url.as_ref().unwrap_or(&UrlPath::new()))
+}
+
+/// Get the message a panic was started with
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload.downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
+
+/// Read the width and height of a PNG from its header
+fn png_size(path: &Path) -> Option<(u32, u32)> {
+    let mut header = [0u8; 24];
+    fs::File::open(path).ok()?.read_exact(&mut header).ok()?;
+    if &header[12..16] != b"IHDR" {
+        return None;
+    }
+    Some((
+        u32::from_be_bytes(header[16..20].try_into().ok()?),
+        u32::from_be_bytes(header[20..24].try_into().ok()?),
+    ))
+}
+
+/// Create the OpenGraph and Twitter card tags for a page so links to it get
+/// nice previews on sites like Discord and Twitter
+pub fn fmt_page_meta(config: Arc<Config>, title: &str, description: &str, page_url: &str) -> String {
+    fn meta(attr: &str, key: &str, value: &str) -> String {
+        format!(
+            "<meta {attr}=\"{key}\" content=\"{}\">",
+            escape_attr(value)
+        )
+    }
+
+    // Previews need absolute URLs to work, so prefix with the site URL if 
+    // one is provided
+    let site_url = config.project.url.as_ref()
+        .map(|u| u.trim_end_matches('/').to_owned())
+        .unwrap_or_default();
+    let image = config.project.icon.as_ref().map(|_| format!(
+        "{site_url}{}/icon.png",
+        config.output_url.as_ref().unwrap_or(&UrlPath::new())
+    ));
+    let image_size = config.project.icon.as_ref()
+        .and_then(|icon| png_size(&config.input_dir.join(icon)));
+
+    [
+        Some(meta("property", "og:type", "website")),
+        Some(meta("property", "og:site_name", &format!("{} Docs", config.project.name))),
+        Some(meta("property", "og:title", title)),
+        Some(meta("property", "og:description", description)),
+        Some(meta("property", "og:url", &format!("{site_url}{page_url}"))),
+        image.as_ref().map(|i| meta("property", "og:image", i)),
+        image_size.map(|(w, _)| meta("property", "og:image:width", &w.to_string())),
+        image_size.map(|(_, h)| meta("property", "og:image:height", &h.to_string())),
+        Some(meta("name", "twitter:card", "summary")),
+        Some(meta("name", "twitter:title", title)),
+        Some(meta("name", "twitter:description", description)),
+        image.as_ref().map(|i| meta("name", "twitter:image", i)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// All versions of the docs, including the one currently being built
+fn all_versions(config: Arc<Config>) -> Vec<String> {
+    let mut all = config.versions.as_ref().map(|v| v.all.clone()).unwrap_or_default();
+    if !all.contains(&config.project.version) {
+        all.push(config.project.version.clone());
+    }
+    all
+}
+
+fn fmt_version_picker(config: Arc<Config>) -> String {
+    if config.versions.is_none() {
+        return String::new();
+    }
+    let root_url = config.output_url.as_ref().unwrap_or(&UrlPath::new()).parent();
+    // The versions known when building are a fallback, script.js replaces
+    // them with versions.json so older versions list the newer ones too
+    HtmlElement::new("select")
+        .with_class("version-picker")
+        .with_attr("aria-label", config.tr("Version"))
+        .with_attr("data-versions", root_url.join(UrlPath::part("versions.json")))
+        .with_attr("data-current", &config.project.version)
+        .with_attr("onchange", "window.location.href = this.value")
+        .with_children(
+            all_versions(config.clone())
+                .into_iter()
+                .map(|v| {
+                    HtmlElement::new("option")
+                        .with_attr("value", format!("{}/", root_url.join(UrlPath::part(&v))))
+                        .with_attr_opt("selected", (v == config.project.version).then_some(""))
+                        .with_text(&v)
+                        .into()
+                })
+                .collect()
+        )
+        .gen_html()
+}
+
+/// Dropdown for switching between the projects of a workspace
+fn fmt_project_picker(config: Arc<Config>) -> String {
+    if config.projects.is_empty() {
+        return String::new();
+    }
+    HtmlElement::new("select")
+        .with_classes(&["version-picker", "project-picker"])
+        .with_attr("aria-label", config.tr("Project"))
+        .with_attr("onchange", "window.location.href = this.value")
+        .with_children(
+            config.projects
+                .iter()
+                .map(|(name, url)| {
+                    HtmlElement::new("option")
+                        .with_attr("value", format!("{url}/"))
+                        .with_attr_opt("selected", (*name == config.project.name).then_some(""))
+                        .with_text(name)
+                        .into()
+                })
+                .collect()
+        )
+        .gen_html()
+}
+
+/// Name of a language in that language, for the language picker
+fn language_name(code: &str) -> &str {
+    match code {
+        "en" => "English",
+        "de" => "Deutsch",
+        "fr" => "Français",
+        "ja" => "日本語",
+        _ => code,
+    }
+}
+
+/// Dropdown for switching between the languages of the docs. Switching 
+/// keeps you on the same page since every language has the same URLs
+fn fmt_language_picker(config: Arc<Config>) -> String {
+    let Some((_, current)) = config.languages.iter().find(|(lang, _)| *lang == config.language) else {
+        return String::new();
+    };
+    HtmlElement::new("select")
+        .with_classes(&["version-picker", "language-picker"])
+        .with_attr("aria-label", config.tr("Language"))
+        .with_attr("data-current", format!("{current}/"))
+        .with_attr("onchange", "switchLanguage(this)")
+        .with_children(
+            config.languages
+                .iter()
+                .map(|(lang, url)| {
+                    HtmlElement::new("option")
+                        .with_attr("value", format!("{url}/"))
+                        .with_attr_opt("selected", (*lang == config.language).then_some(""))
+                        .with_text(language_name(lang))
+                        .into()
+                })
+                .collect()
+        )
+        .gen_html()
+}
+
+pub fn default_format(config: Arc<Config>) -> HashMap<String, String> {
+    let mut fmt = HashMap::from([
+        ("project_name".into(), escape_attr(&config.project.name)),
+        ("project_version".into(), escape_attr(&config.project.version)),
+        ("language".into(), escape_attr(&config.language)),
         (
             "project_repository".into(),
-            config.project.repository.clone().unwrap_or(String::new()),
+            escape_attr(config.project.repository.as_deref().unwrap_or_default()),
         ),
         (
             "project_icon".into(),
@@ -332,13 +970,57 @@ fn default_format(config: Arc<Config>) -> HashMap<String, String> {
                 )))
                 .unwrap_or(String::new()),
         ),
+        (
+            "feed_link".into(),
+            config.feed.as_ref().map(|_| format!(
+                "<link rel=\"alternate\" type=\"application/atom+xml\" title=\"{} Docs\" href=\"{}/feed.xml\">",
+                escape_attr(&config.project.name),
+                config.output_url.as_ref().unwrap_or(&UrlPath::new()),
+            )).unwrap_or_default(),
+        ),
+        ("theme_default".into(), escape_attr(&config.theme.default)),
+        ("skip_to_content".into(), escape_attr(config.tr("Skip to content"))),
+        ("search_placeholder".into(), escape_attr(config.tr("Search items..."))),
+        ("search_label".into(), escape_attr(config.tr("Search items"))),
+        ("no_results".into(), escape_attr(config.tr("No results found"))),
+        ("search_button_label".into(), escape_attr(config.tr("Search"))),
+        ("clear_search_label".into(), escape_attr(config.tr("Clear search"))),
+        ("navigation_label".into(), escape_attr(config.tr("Navigation"))),
+        ("repository_label".into(), escape_attr(config.tr("Repository"))),
+        ("theme_label".into(), escape_attr(config.tr("Theme"))),
+        ("dark_label".into(), escape_attr(config.tr("Dark"))),
+        ("peach_label".into(), escape_attr(config.tr("Peach"))),
+        ("ocean_label".into(), escape_attr(config.tr("Ocean"))),
+        ("menu_label".into(), escape_attr(config.tr("Menu"))),
+        ("relative_links".into(), (config.site.links == LinkMode::Relative).to_string()),
+        ("prefetch".into(), config.site.prefetch.to_string()),
+        (
+            "mermaid_url".into(),
+            escape_attr(&renderer_url(&config, &config.scripts.mermaid, &format!(
+                "mermaid/{}",
+                Path::new(&config.scripts.mermaid).file_name().unwrap_or_default().to_string_lossy(),
+            ))),
+        ),
+        ("katex_url".into(), escape_attr(&renderer_url(&config, &config.scripts.katex, "katex"))),
+        ("version_picker".into(), fmt_version_picker(config.clone())),
+        ("project_picker".into(), fmt_project_picker(config.clone())),
+        ("language_picker".into(), fmt_language_picker(config.clone())),
+        // Overridden by pages that have a table of contents
+        ("page_toc".into(), String::new()),
         (
             "output_url".into(),
-            config
-                .output_url
-                .as_ref()
-                .unwrap_or(&UrlPath::new())
-                .to_string(),
+            escape_attr(
+                &config
+                    .output_url
+                    .as_ref()
+                    .unwrap_or(&UrlPath::new())
+                    .to_string()
+            ),
         ),
-    ])
+    ]);
+    // Custom variables from `templates.vars` can't replace the built-in ones
+    for (name, value) in &config.templates.vars {
+        fmt.entry(name.clone()).or_insert_with(|| value.clone());
+    }
+    fmt
 }
@@ -0,0 +1,258 @@
+use std::{collections::HashMap, fs, process::Command};
+
+use regex::{Captures, Regex};
+
+use crate::{
+    html::{escape::{escape_attr, escape_script}, process::minify_html},
+    log::{self, Category},
+    template::render,
+    url::UrlPath,
+};
+
+use super::builder::{default_format, fmt_page_meta, Builder};
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut res = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                res.push(BASE64_CHARS[(n >> (18 - i * 6) & 63) as usize] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+    res
+}
+
+/// Prism languages bundled ahead of time, since the autoloader would fetch
+/// them from the CDN. Dependencies come before the languages that need them
+const PRISM_LANGUAGES: &[&str] = &[
+    "clike", "c", "cpp", "objectivec", "markup", "javascript", "json", "bash", "cmake",
+];
+
+/// Google Fonts only serves WOFF2 to browsers it recognizes
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+    (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+/// Download a file, warning if it can't be reached
+fn fetch(url: &str) -> Option<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["-sfL", "-A", USER_AGENT, url])
+        .output()
+        .ok()
+        .filter(|o| o.status.success());
+    if output.is_none() {
+        log::warn(Category::Build, format!(
+            "Unable to download {url} for bundling, it will be loaded from the web instead"
+        ));
+    }
+    output.map(|o| o.stdout)
+}
+
+fn mime_for(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    match path.rsplit('.').next().unwrap_or("") {
+        "woff2" => "font/woff2",
+        "woff" => "font/woff",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Download an external stylesheet, with the fonts and images it uses as
+/// data URIs
+fn fetch_css(url: &str) -> Option<String> {
+    let css = String::from_utf8_lossy(&fetch(url)?).into_owned();
+    let url_re = Regex::new(r#"url\(['"]?(https://[^)'"]+)['"]?\)"#).unwrap();
+    Some(url_re.replace_all(&css, |caps: &Captures| {
+        match fetch(&caps[1]) {
+            Some(data) => format!("url(\"data:{};base64,{}\")", mime_for(&caps[1]), base64(&data)),
+            None => caps[0].to_owned(),
+        }
+    }).into_owned())
+}
+
+/// Inline the scripts and stylesheets the head loads from CDNs. Anything
+/// that can't be downloaded is left external
+fn inline_external(head: &str) -> String {
+    let script_re = Regex::new(r#"<script src="(https://[^"]+)"[^>]*></script>"#).unwrap();
+    let style_re = Regex::new(
+        r#"<link (?:rel="stylesheet" href="(https://[^"]+)"|href="(https://[^"]+)" rel="stylesheet")[^>]*>"#
+    ).unwrap();
+    let inline_script = |url: &str| fetch(url).map(|js| format!(
+        "<script>{}</script>", escape_script(&String::from_utf8_lossy(&js))
+    ));
+
+    let head = script_re.replace_all(head, |caps: &Captures| {
+        let url = &caps[1];
+        let Some(mut res) = inline_script(url) else {
+            return caps[0].to_owned();
+        };
+        if url.ends_with("/components/prism-core.min.js") {
+            for lang in PRISM_LANGUAGES {
+                res += &inline_script(&url.replace("prism-core", &format!("prism-{lang}")))
+                    .unwrap_or_default();
+            }
+        }
+        // The autoloader finds other languages relative to its own script,
+        // which an inlined script doesn't have
+        if url.ends_with("/plugins/autoloader/prism-autoloader.min.js") {
+            res += &format!(
+                "<script>Prism.plugins.autoloader.languages_path = \"{}\";</script>",
+                url.replace("plugins/autoloader/prism-autoloader.min.js", "components/"),
+            );
+        }
+        res
+    });
+    style_re.replace_all(&head, |caps: &Captures| {
+        let url = caps.get(1).or(caps.get(2)).unwrap().as_str();
+        match fetch_css(url) {
+            Some(css) => format!("<style>{css}</style>"),
+            None => caps[0].to_owned(),
+        }
+    }).into_owned()
+}
+
+/// Format the head template with all stylesheets and CDN libraries inlined.
+/// Scripts from the config are removed from the head and returned
+/// separately so they can be placed wherever they are needed
+pub fn inline_head(builder: &Builder, title: &str) -> Result<(String, Vec<String>), String> {
+    let config = builder.config.clone();
+    let root = config.output_url.clone().unwrap_or_default().to_string();
+    let read_asset = |name: &str| fs::read_to_string(
+        config.output_dir.join(builder.asset_file(name))
+    ).map_err(|e| format!("Unable to read {name}: {e}"));
+
+    let mut fmt = default_format(config.clone());
+    fmt.extend(HashMap::from([
+        ("page_url".to_owned(), escape_attr(&root)),
+        ("page_title".to_owned(), escape_attr(title)),
+        ("page_description".to_owned(), String::new()),
+        ("page_meta".to_owned(), fmt_page_meta(config.clone(), title, "", &root)),
+    ]));
+    let mut head = inline_external(
+        &render(&config.templates.head, &fmt).map_err(|e| format!("Unable to format head: {e}"))?
+    );
+
+    let mut scripts = Vec::new();
+    let css = config.scripts.css.iter().map(|s| s.name.as_str()).chain(["theme.css"]);
+    for name in css {
+        head = head.replace(
+            &format!("<link rel=\"stylesheet\" href=\"{root}/{name}\">"),
+            &format!("<style>{}</style>", read_asset(name)?),
+        );
+    }
+    for script in &config.scripts.js {
+        let tag = format!("<script defer src=\"{root}/{}\"></script>", script.name);
+        if head.contains(&tag) {
+            head = head.replace(&tag, "");
+            scripts.push(read_asset(&script.name)?);
+        }
+    }
+    Ok((head, scripts))
+}
+
+/// Read the content and metadata saved for a built page
+pub fn read_page(builder: &Builder, url: &UrlPath) -> Result<(String, String), String> {
+    let config = builder.config.clone();
+    let dir = config.output_dir.join(url.cased(config.clone()).to_pathbuf());
+    Ok((
+        fs::read_to_string(dir.join("content.html"))
+            .map_err(|e| format!("Unable to read the built page {url}: {e}"))?,
+        fs::read_to_string(dir.join("metadata.json"))
+            .map_err(|e| format!("Unable to read metadata of {url}: {e}"))?,
+    ))
+}
+
+/// Replace references to the images generated from the project icon with
+/// data URIs
+pub fn inline_icons(builder: &Builder, mut page: String) -> String {
+    let config = builder.config.clone();
+    let root = config.output_url.clone().unwrap_or_default().to_string();
+    for (name, mime) in [("favicon.ico", "image/x-icon"), ("icon.png", "image/png")] {
+        if let Ok(data) = fs::read(config.output_dir.join(name)) {
+            page = page.replace(
+                &format!("\"{root}/{name}\""),
+                &format!("\"data:{mime};base64,{}\"", base64(&data)),
+            );
+        }
+    }
+    page
+}
+
+/// Bundle all of the built pages into a single `offline.html` that can be
+/// opened without a server. CSS, JS, fonts and icons are inlined, including
+/// the libraries loaded from CDNs, and the content of every page is
+/// embedded so the client-side navigation can swap pages without fetching
+/// anything
+pub fn build_bundle(builder: &Builder, pages: &[UrlPath]) -> Result<(), String> {
+    let config = builder.config.clone();
+
+    // The scripts go at the end of the body so they run after the embedded
+    // pages have been parsed
+    let (head, scripts) = inline_head(builder, &format!("{} Docs", config.project.name))
+        .map_err(|e| format!("Unable to bundle the docs: {e}"))?;
+
+    // Embed every page's content and metadata
+    let mut embedded = String::new();
+    let mut main_content = String::new();
+    for url in pages {
+        let (content, metadata) = read_page(builder, url)
+            .map_err(|e| format!("Unable to bundle the docs: {e}"))?;
+        if url.is_empty() {
+            main_content = content.clone();
+        }
+        embedded += &format!(
+            "<div data-url=\"{}\" data-metadata=\"{}\">{content}</div>",
+            escape_attr(&url.cased(config.clone()).to_absolute_unstyled(config.clone()).to_string()),
+            escape_attr(&metadata),
+        );
+    }
+    let functions = fs::read_to_string(config.output_dir.join("functions.json"))
+        .map_err(|e| format!("Unable to read functions.json for bundling: {e}"))?;
+    let symbols = fs::read_to_string(config.output_dir.join("symbols.json"))
+        .map_err(|e| format!("Unable to read symbols.json for bundling: {e}"))?;
+    let previews = if config.output.link_previews {
+        fs::read_to_string(config.output_dir.join("previews.json"))
+            .map_err(|e| format!("Unable to read previews.json for bundling: {e}"))?
+    } else {
+        String::from("{}")
+    };
+
+    let mut page_fmt = default_format(config.clone());
+    page_fmt.extend(HashMap::from([
+        ("head_content".to_owned(), head),
+        ("navbar_content".to_owned(), builder.build_nav()?),
+        ("main_content".to_owned(), main_content),
+    ]));
+    let mut page = render(&config.templates.page, &page_fmt)
+        .map_err(|e| format!("Unable to format bundle: {e}"))?;
+
+    let body_end = format!(
+        "<template id=\"flash-embedded-pages\">{embedded}</template>\
+        <script>const FLASH_FUNCTIONS = {}; const FLASH_SYMBOLS = {}; const FLASH_PREVIEWS = {};</script>\
+        {}</body>",
+        escape_script(&functions),
+        escape_script(&symbols),
+        escape_script(&previews),
+        scripts.iter()
+            .map(|s| format!("<script>{}</script>", escape_script(s)))
+            .collect::<String>(),
+    );
+    if let Some(pos) = page.rfind("</body>") {
+        page.replace_range(pos..pos + "</body>".len(), &body_end);
+    } else {
+        page += &body_end;
+    }
+
+    fs::write(config.output_dir.join("offline.html"), minify_html(&config, inline_icons(builder, page))?)
+        .map_err(|e| format!("Unable to save offline.html: {e}"))
+}
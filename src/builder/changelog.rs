@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Arc, Mutex},
+};
+
+use clang::Entity;
+
+use crate::config::Config;
+
+/// Infers which release introduced an entity from the git history, for
+/// entities that don't have an `@since`
+pub struct Changelog {
+    /// Repository to get the history from, if the `changelog` section is set
+    repo: Option<PathBuf>,
+    tags: Option<glob::Pattern>,
+    /// Commit that last changed each line of each file, with the number the
+    /// line had in that commit
+    blames: Mutex<HashMap<PathBuf, Arc<Vec<Option<(String, usize)>>>>>,
+    /// Commit that added each line, by the commit that last changed it and
+    /// its number there
+    origins: Mutex<HashMap<(String, usize), Option<String>>>,
+    /// First release tag that contains each commit
+    releases: Mutex<HashMap<String, Option<String>>>,
+}
+
+fn git(repo: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).current_dir(repo).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Get the commit that last changed every line of a file and the line's
+/// number in it from `git blame --porcelain`. Lines that haven't been
+/// committed yet have no commit
+fn blame(repo: &Path, file: &Path) -> Vec<Option<(String, usize)>> {
+    let Some(output) = git(repo, &["blame", "--porcelain", &file.to_string_lossy()]) else {
+        return Vec::new();
+    };
+    let mut res = Vec::new();
+    for line in output.lines() {
+        // Each line of the file has a header like `<hash> <orig> <final> [count]`
+        let parts = line.split(' ').collect::<Vec<_>>();
+        if parts.len() < 3 || parts[0].len() != 40 || !parts[0].chars().all(|c| c.is_ascii_hexdigit()) {
+            continue;
+        }
+        let (Ok(orig_line), Ok(final_line)) = (parts[1].parse::<usize>(), parts[2].parse::<usize>()) else {
+            continue;
+        };
+        if res.len() < final_line {
+            res.resize(final_line, None);
+        }
+        res[final_line - 1] = parts[0].chars().any(|c| c != '0').then(|| (parts[0].to_owned(), orig_line));
+    }
+    res
+}
+
+/// Follow a line back from the commit that last changed it to the commit
+/// that added it, which is the oldest commit `git log -L` lists for it
+fn origin(repo: &Path, file: &Path, commit: &str, line: usize) -> Option<String> {
+    let file = file.strip_prefix(repo).unwrap_or(file);
+    let output = git(repo, &[
+        "log", "--format=%H", &format!("-L{line},{line}:{}", file.to_string_lossy()), commit,
+    ])?;
+    // The diffs are printed too, but none of their lines are just a hash
+    output.lines()
+        .filter(|l| l.len() == 40 && l.chars().all(|c| c.is_ascii_hexdigit()))
+        .last()
+        .map(|l| l.to_owned())
+}
+
+impl Changelog {
+    pub fn new(config: Arc<Config>) -> Result<Self, String> {
+        let changelog = config.changelog.as_ref();
+        Ok(Self {
+            repo: changelog.map(|c| config.input_dir.join(c.repo.clone().unwrap_or_default())),
+            tags: changelog.and_then(|c| c.tags.as_ref())
+                .map(|t| glob::Pattern::new(t).map_err(|e| format!("Invalid changelog.tags pattern '{t}': {e}")))
+                .transpose()?,
+            blames: Mutex::new(HashMap::new()),
+            origins: Mutex::new(HashMap::new()),
+            releases: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn first_release(&self, repo: &Path, commit: &str) -> Option<String> {
+        if let Some(release) = self.releases.lock().unwrap().get(commit) {
+            return release.clone();
+        }
+        let release = git(repo, &["tag", "--contains", commit, "--sort=v:refname"])
+            .and_then(|tags| tags.lines()
+                .map(|t| t.trim())
+                .find(|t| self.tags.as_ref().map_or(true, |p| p.matches(t)))
+                .map(|t| t.to_owned())
+            );
+        self.releases.lock().unwrap().insert(commit.to_owned(), release.clone());
+        release
+    }
+
+    /// Find the first release tag that contains the commit that added the
+    /// line the entity is declared on. The locks aren't held while git runs
+    /// so pages on other threads don't wait for each other
+    pub fn since(&self, entity: &Entity) -> Option<String> {
+        let repo = self.repo.as_ref()?;
+        let location = entity.get_location()?.get_file_location();
+        let file = location.file?.get_path();
+
+        let cached = self.blames.lock().unwrap().get(&file).cloned();
+        let blames = match cached {
+            Some(blames) => blames,
+            None => {
+                let blames = Arc::new(blame(repo, &file));
+                self.blames.lock().unwrap().insert(file.clone(), blames.clone());
+                blames
+            }
+        };
+        let (last, line) = blames.get((location.line as usize).checked_sub(1)?).cloned().flatten()?;
+
+        let cached = self.origins.lock().unwrap().get(&(last.clone(), line)).cloned();
+        let commit = match cached {
+            Some(commit) => commit,
+            None => {
+                let commit = origin(repo, &file, &last, line);
+                self.origins.lock().unwrap().insert((last, line), commit.clone());
+                commit
+            }
+        }?;
+        self.first_release(repo, &commit)
+    }
+}
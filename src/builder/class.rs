@@ -1,21 +1,34 @@
 use std::sync::Arc;
 
-use crate::{html::Html, url::UrlPath};
+use crate::{config::Config, html::Html, url::UrlPath};
 use clang::Entity;
 
 use super::{
     builder::Builder,
+    member::build_member_pages,
+    namespace::{load_nested, CppItem},
     traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem, OutputEntry, SubItem},
-    shared::output_classlike,
+    shared::{fmt_specializations, output_classlike},
+    signature::signature,
 };
 
 pub struct Class<'e> {
     entity: Entity<'e>,
+    specializations: Vec<Entity<'e>>,
+    nested: Vec<CppItem<'e>>,
 }
 
 impl<'e> Class<'e> {
-    pub fn new(entity: Entity<'e>) -> Self {
-        Self { entity }
+    pub fn new(entity: Entity<'e>, config: Arc<Config>) -> Self {
+        Self { entity, specializations: Vec::new(), nested: load_nested(&entity, config) }
+    }
+
+    pub fn add_specialization(&mut self, spec: Entity<'e>) {
+        self.specializations.push(spec);
+    }
+
+    pub fn nested(&self) -> &[CppItem<'e>] {
+        &self.nested
     }
 }
 
@@ -27,11 +40,16 @@ impl<'e> Entry<'e> for Class<'e> {
     }
 
     fn url(&self) -> UrlPath {
-        self.entity.rel_docs_url().expect("Unable to get class URL")
+        self.entity.page_url()
     }
 
     fn build(&self, builder: &Builder<'e>) -> BuildResult {
-        builder.create_output_for(self)
+        let mut handles = builder.create_output_for(self)?;
+        handles.extend(build_member_pages(&self.entity, builder)?);
+        for nested in &self.nested {
+            handles.extend(nested.build(builder)?);
+        }
+        Ok(handles)
     }
 
     fn nav(&self) -> NavItem {
@@ -54,13 +72,16 @@ impl<'e> ASTEntry<'e> for Class<'e> {
 
 impl<'e> OutputEntry<'e> for Class<'e> {
     fn output(&self, builder: &Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
-        (
-            builder.config.templates.class.clone(),
-            output_classlike(self, builder),
-        )
+        let mut vars = output_classlike(self, builder);
+        vars.push(("specializations", fmt_specializations(&self.specializations, builder)));
+        (builder.config.templates.class.clone(), vars)
     }
 
     fn description(&self, builder: &'e Builder<'e>) -> String {
         self.output_description(builder)
     }
+
+    fn signatures(&self) -> Vec<String> {
+        signature(&self.entity).into_iter().collect()
+    }
 }
@@ -1,25 +1,88 @@
-use std::{collections::HashMap, fs, str::Chars};
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, str::Chars};
 
 use clang::{
+    diagnostic::{Diagnostic, Severity},
     token::{Token, TokenKind},
-    Entity, EntityKind,
+    Entity, EntityKind, TranslationUnit,
 };
 use multipeek::{IteratorExt, MultiPeek};
+use serde_json::{json, Value};
 
 use crate::{
     html::{Html, HtmlElement, HtmlList, HtmlText},
+    log::Category,
     url::UrlPath,
 };
 
 use super::{
     builder::Builder,
     traits::EntityMethods,
-    shared::fmt_autolinks,
-    markdown::fmt_markdown,
+    shared::{fmt_autolinks, fmt_refs, join_tokens, resolve_entity, resolve_ref},
+    signature::{exception_spec, ExceptionSpec},
+    markdown::fmt_comment_markdown,
+    snippet::load_snippet,
 };
 
+/// Doxygen commands that may also be written with a backslash, like `\param`
+const BACKSLASH_COMMANDS: &[&str] = &[
+    "attention", "brief", "code", "copydoc", "desc", "description", "details",
+    "endcode", "example", "exception", "file", "hide", "include", "ingroup", "internal", "link",
+    "note", "param", "private", "ref", "related", "relates", "remark", "remarks",
+    "result", "return", "returns", "sa", "see", "short", "since", "snippet", "throw",
+    "throws", "tparam", "version", "warning",
+];
+
+/// How many levels of @copydoc are followed before giving up
+const MAX_COPYDOC_DEPTH: usize = 8;
+
+/// Convert Doxygen-style backslash commands to their @ equivalents. Only 
+/// known commands at the start of a word are converted so things like `\n` 
+/// in descriptions are left alone
+fn normalize_commands(raw: &str) -> String {
+    let mut res = String::with_capacity(raw.len());
+    let mut prev: Option<char> = None;
+    for (i, c) in raw.char_indices() {
+        let at_word_start = prev.map_or(true, |p| p.is_whitespace() || "*{(".contains(p));
+        if c == '\\' && at_word_start {
+            let name = raw[i + 1..]
+                .chars()
+                .take_while(|c| c.is_alphanumeric())
+                .collect::<String>();
+            if BACKSLASH_COMMANDS.contains(&name.as_str()) {
+                res.push('@');
+                prev = Some('@');
+                continue;
+            }
+        }
+        res.push(c);
+        prev = Some(c);
+    }
+    res
+}
+
+/// Whether a raw comment hides its entity with `@internal`, `@private` or 
+/// `@hide`. This is checked while loading entities, before there's a 
+/// builder to fully parse the comment with
+pub fn is_hidden_comment(raw: &str) -> bool {
+    let text = normalize_commands(raw);
+    text.match_indices('@').any(|(i, _)| {
+        let at_word_start = text[..i].chars()
+            .last()
+            .map_or(true, |p| p.is_whitespace() || "*{(".contains(p));
+        let name = text[i + 1..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric())
+            .collect::<String>();
+        at_word_start && matches!(name.as_str(), "internal" | "private" | "hide")
+    })
+}
+
 struct CommentLexer<'s> {
     raw: MultiPeek<Chars<'s>>,
+    /// Line of the comment the lexer is on, counting from zero
+    line: usize,
+    /// Warnings with the line of the comment they're about
+    warnings: Vec<(usize, String)>,
 }
 
 impl<'s> CommentLexer<'s> {
@@ -30,13 +93,28 @@ impl<'s> CommentLexer<'s> {
                 .trim_start_matches("/*")
                 .chars()
                 .multipeek(),
+            line: 0,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Consume a character, keeping track of which line it was on
+    fn bump(&mut self) -> Option<char> {
+        let c = self.raw.next();
+        if c == Some('\n') {
+            self.line += 1;
         }
+        c
+    }
+
+    fn warn(&mut self, cmd: &ParsedCommand, msg: String) {
+        self.warnings.push((cmd.line, msg));
     }
 
     fn skip_while<P: FnMut(char) -> bool>(&mut self, mut pred: P) -> usize {
         let mut count = 0;
         while self.raw.peek().is_some_and(|c| pred(*c)) {
-            self.raw.next();
+            self.bump();
             count += 1;
         }
         count
@@ -49,7 +127,7 @@ impl<'s> CommentLexer<'s> {
         // Make sure this line was started with a star
         if self.raw.peek().is_some_and(|c| *c == '*') {
             // Consume the star
-            self.raw.next();
+            self.bump();
             // If indentation was provided, remove that amount of whitespace if possible
             if let Some(max) = indentation {
                 let mut i = 0;
@@ -77,11 +155,20 @@ impl<'s> CommentLexer<'s> {
         self.skip_while(|c| c.is_whitespace() || c == '*');
     }
 
+    /// Whether the upcoming @ starts an inline command like `@ref` that is 
+    /// part of a value rather than the start of a new command
+    fn is_inline_command(&mut self) -> bool {
+        ["ref", "link"].iter().any(|cmd| {
+            cmd.chars().enumerate().all(|(i, c)| self.raw.peek_nth(i + 1) == Some(&c))
+                && self.raw.peek_nth(cmd.len() + 1).map_or(true, |c| !c.is_alphanumeric())
+        })
+    }
+
     fn eat_until<P: FnMut(char) -> bool>(&mut self, mut pred: P) -> Option<String> {
         let mut res = String::new();
         let mut indent_size = None;
         while let Some(c) = self.raw.peek().copied() {
-            if pred(c) {
+            if pred(c) && !(c == '@' && self.is_inline_command()) {
                 break;
             }
             // On newlines, skip whitespace and the next line's starting star
@@ -93,7 +180,7 @@ impl<'s> CommentLexer<'s> {
                 }
                 res.push('\n');
             } else {
-                self.raw.next();
+                self.bump();
                 res.push(c);
             }
         }
@@ -112,16 +199,18 @@ impl<'s> CommentLexer<'s> {
 
         // todo: handle escaped @ symbol
 
-        match self.raw.peek()? {
+        let line = self.line;
+        let cmd = match self.raw.peek()? {
             '@' => {
                 // Consume param symbol
-                self.raw.next();
-                // Eat command name
-                let cmd = self.eat_until(|c| c.is_whitespace() || c == '[')?;
+                self.bump();
+                // Eat command name. Doxygen's `@code{.py}` has the 
+                // language right after the name
+                let cmd = self.eat_until(|c| c.is_whitespace() || c == '[' || c == '{')?;
                 // Parse attributes if those are provided
                 if self.raw.peek().is_some_and(|c| *c == '[') {
                     // Consume opening bracket
-                    self.raw.next();
+                    self.bump();
                     let mut attrs = HashMap::new();
                     loop {
                         let Some(key) = self
@@ -134,7 +223,7 @@ impl<'s> CommentLexer<'s> {
                         // Value provided
                         if self.raw.peek().is_some_and(|c| *c == '=') {
                             // Consume =
-                            self.raw.next();
+                            self.bump();
 
                             // Eat value
                             let value = self
@@ -150,20 +239,21 @@ impl<'s> CommentLexer<'s> {
                         }
 
                         // Next value or end of list
-                        let Some(next) = self.raw.next() else { break; };
+                        let Some(next) = self.bump() else { break; };
                         match next {
                             ']' => break,
                             ',' => continue,
                             _ => unreachable!(),
                         }
                     }
-                    Some(ParsedCommand::new_with(cmd, attrs))
+                    ParsedCommand::new_with(cmd, attrs)
                 } else {
-                    Some(ParsedCommand::new(cmd))
+                    ParsedCommand::new(cmd)
                 }
             }
-            _ => Some(ParsedCommand::new("description")),
-        }
+            _ => ParsedCommand::new("description"),
+        };
+        Some(ParsedCommand { line, ..cmd })
     }
 
     pub fn next_param(&mut self) -> Option<String> {
@@ -171,26 +261,36 @@ impl<'s> CommentLexer<'s> {
         self.eat_word()
     }
 
+    /// Get the next word if it's on the same line, for commands with an 
+    /// optional parameter like `@file [name]`
+    pub fn param_on_line(&mut self) -> Option<String> {
+        self.skip_while(|c| c.is_whitespace() && c != '\n');
+        if self.raw.peek().map_or(true, |c| *c == '\n') {
+            return None;
+        }
+        self.eat_word()
+    }
+
     pub fn next_value(&mut self) -> Option<String> {
         self.eat_until(|c| c == '@')
     }
 
     pub fn param_for(&mut self, cmd: &ParsedCommand) -> String {
         self.next_param().unwrap_or_else(|| {
-            println!(
-                "Warning parsing JSDoc comment: Expected parameter for command {}",
+            self.warn(cmd, format!(
+                "Expected parameter for command @{} in JSDoc comment",
                 cmd.cmd
-            );
+            ));
             String::new()
         })
     }
 
     pub fn value_for(&mut self, cmd: &ParsedCommand) -> String {
         self.next_value().unwrap_or_else(|| {
-            println!(
-                "Warning parsing JSDoc comment: Expected value for command {}",
+            self.warn(cmd, format!(
+                "Expected value for command @{} in JSDoc comment",
                 cmd.cmd
-            );
+            ));
             String::new()
         })
     }
@@ -203,6 +303,8 @@ struct ParsedCommand {
     /// Trimmed but otherwise arbitary (except for cannot contain equals sign
     /// or comma)
     attrs: HashMap<String, Option<String>>,
+    /// Line of the comment the command is on
+    line: usize,
 }
 
 impl ParsedCommand {
@@ -210,6 +312,7 @@ impl ParsedCommand {
         Self {
             cmd: cmd.as_ref().to_owned(),
             attrs: HashMap::new(),
+            line: 0,
         }
     }
 
@@ -217,6 +320,7 @@ impl ParsedCommand {
         Self {
             cmd: cmd.as_ref().to_owned(),
             attrs,
+            line: 0,
         }
     }
 }
@@ -249,6 +353,20 @@ impl Annotation {
     }
 }
 
+/// Get the syntax highlighting classes for a token
+pub fn token_classes(token: &Token) -> &'static [&'static str] {
+    match token.get_kind() {
+        TokenKind::Comment => &["comment"],
+        TokenKind::Identifier => &["identifier"],
+        TokenKind::Keyword => match token.get_spelling().as_str() {
+            "true" | "false" | "this" => &["keyword", "value"],
+            _ => &["keyword"],
+        },
+        TokenKind::Literal => &["literal"],
+        TokenKind::Punctuation => &["punctuation"],
+    }
+}
+
 fn annotate(base: Entity, annotations: &[Annotation]) -> Vec<Html> {
     let mut list = Vec::new();
 
@@ -279,16 +397,7 @@ fn annotate(base: Entity, annotations: &[Annotation]) -> Vec<Html> {
             );
         }
 
-        let classes: &[&str] = match token.get_kind() {
-            TokenKind::Comment => &["comment"],
-            TokenKind::Identifier => &["identifier"],
-            TokenKind::Keyword => match token.get_spelling().as_str() {
-                "true" | "false" | "this" => &["keyword", "value"],
-                _ => &["keyword"],
-            },
-            TokenKind::Literal => &["literal"],
-            TokenKind::Punctuation => &["punctuation"],
-        };
+        let classes = token_classes(&token);
 
         // Add link
         if let Some(a) = annotations
@@ -321,6 +430,17 @@ fn annotate(base: Entity, annotations: &[Annotation]) -> Vec<Html> {
     list
 }
 
+/// Syntax highlight the full source of an entity
+pub fn fmt_highlighted(entity: &Entity) -> Html {
+    HtmlElement::new("pre")
+        .with_child(
+            HtmlElement::new("code")
+                .with_classes(&["example"])
+                .with_children(annotate(*entity, &[])),
+        )
+        .into()
+}
+
 fn print(entity: &Entity) {
     for child in entity.get_children() {
         println!(
@@ -355,21 +475,141 @@ fn print(entity: &Entity) {
     }
 }
 
+/// Where a doc comment is written, so problems in it can be pointed at the 
+/// header
+#[derive(Clone)]
+pub struct CommentSource {
+    /// Full name of the documented entity
+    pub entity: String,
+    pub file: Option<PathBuf>,
+    /// Line the comment starts on
+    pub line: u32,
+}
+
+impl CommentSource {
+    pub fn new(entity: &Entity) -> Self {
+        let loc = entity.get_comment_range()
+            .or(entity.get_range())
+            .map(|r| r.get_start().get_spelling_location());
+        Self {
+            entity: entity.full_name().join("::"),
+            file: loc.as_ref().and_then(|l| l.file).map(|f| f.get_path()),
+            line: loc.map(|l| l.line).unwrap_or(1),
+        }
+    }
+
+    /// Format a line of the comment (counting from zero) as `file:line`, or 
+    /// the entity's name if the file isn't known
+    pub fn location(&self, line: u32) -> String {
+        match self.file {
+            Some(ref file) => format!("{}:{}", file.to_string_lossy(), self.line + line),
+            None => self.entity.clone(),
+        }
+    }
+}
+
 pub struct Example<'e> {
     builder: &'e Builder<'e>,
     data: String,
     analyze: bool,
+    /// Language for client-side highlighting, C++ if not specified
+    language: String,
+    /// Where the first line of the example is in the header, and how many 
+    /// columns the comment's indentation and stars take up
+    source: Option<(CommentSource, u32)>,
 }
 
 impl<'e> Example<'e> {
-    pub fn new(data: String, analyze: bool, builder: &'e Builder<'e>) -> Self {
+    /// Create an example from an @example or @code command. The language can 
+    /// be given either as `@code[lang=python]` or Doxygen-style as 
+    /// `@code{.py}`. Only C++ examples can be analyzed with `[flash]`
+    fn new(cmd: &ParsedCommand, value: String, builder: &'e Builder<'e>) -> Self {
+        let (lang, data) = match value.strip_prefix("{.").and_then(|v| v.split_once('}')) {
+            Some((lang, data)) => (Some(lang.to_owned()), data.trim_start_matches(['\r', '\n']).to_owned()),
+            None => (None, value),
+        };
+        Self::new_with_language(cmd, data, lang, builder)
+    }
+
+    /// Create an example in a language other than C++ by default, which 
+    /// can still be changed with `[lang=...]`
+    fn new_with_language(
+        cmd: &ParsedCommand, data: String, lang: Option<String>, builder: &'e Builder<'e>
+    ) -> Self {
+        let lang = cmd.attrs.get("lang")
+            .or(cmd.attrs.get("language"))
+            .cloned()
+            .flatten()
+            .or(lang)
+            .map(|l| match l.trim().to_lowercase().as_str() {
+                "c++" | "cxx" | "hpp" | "h" => "cpp".to_owned(),
+                other => other.chars()
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+                    .collect(),
+            })
+            .filter(|l| !l.is_empty())
+            .unwrap_or("cpp".into());
         Self {
             builder,
             data,
-            analyze,
+            analyze: cmd.attrs.contains_key("flash") && lang == "cpp",
+            language: lang,
+            source: None,
+        }
+    }
+
+    /// Find where the example is written in the raw comment, starting from 
+    /// line `from` so identical examples are found in order. Returns the 
+    /// line after the example
+    fn locate(&mut self, raw: &str, source: &CommentSource, from: usize) -> usize {
+        let Some((data_ix, first)) = self.data.lines().enumerate().find(|(_, l)| !l.trim().is_empty()) else {
+            return from;
+        };
+        let Some((raw_ix, column)) = raw.lines()
+            .enumerate()
+            .skip(from)
+            .find_map(|(ix, l)| l.find(first.trim()).map(|col| (ix, col)))
+        else {
+            return from;
+        };
+        let indent = first.len() - first.trim_start().len();
+        let mut source = source.clone();
+        source.line += (raw_ix - data_ix.min(raw_ix)) as u32;
+        self.source = Some((source, column.saturating_sub(indent) as u32));
+        raw_ix + self.data.lines().count() - data_ix
+    }
+
+    /// Format a clang diagnostic with its location in the header the 
+    /// example is written in
+    fn fmt_diagnostic(&self, diag: &Diagnostic) -> String {
+        let loc = diag.get_location().get_spelling_location();
+        let in_example = loc.file.is_some_and(|f| f.get_path()
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with("_example_"))
+        );
+        match (&self.source, loc.file) {
+            (Some((source, column)), _) if in_example => format!(
+                "{}:{}: {}",
+                source.location(loc.line.saturating_sub(1)),
+                loc.column + column,
+                diag.get_text()
+            ),
+            (_, _) if in_example => format!("line {}:{}: {}", loc.line, loc.column, diag.get_text()),
+            (_, Some(file)) => format!(
+                "{}:{}:{}: {}", file.get_path().to_string_lossy(), loc.line, loc.column, diag.get_text()
+            ),
+            (_, None) => diag.get_text(),
         }
     }
 
+    fn errors(&self, unit: &TranslationUnit) -> Vec<String> {
+        unit.get_diagnostics()
+            .iter()
+            .filter(|d| d.get_severity() >= Severity::Error)
+            .map(|d| self.fmt_diagnostic(d))
+            .collect()
+    }
+
     fn get_annotations(&self, entity: Entity<'e>) -> Vec<Annotation> {
         if !entity.is_in_main_file() {
             return Vec::new();
@@ -419,7 +659,15 @@ impl<'e> Example<'e> {
         res
     }
 
-    fn try_to_analyzed_html(&self) -> Result<Html, String> {
+    /// Whether this example is C++ code that should be analyzed (and 
+    /// checked with `--check-examples`)
+    pub fn is_analyzed(&self) -> bool {
+        self.analyze
+    }
+
+    /// Parse the example as its own source file with the project's compile 
+    /// arguments
+    fn with_unit<T, F: FnOnce(&TranslationUnit<'e>) -> T>(&self, f: F) -> Result<T, String> {
         // Create a temporary file to store the example's code in
         let mut num = 0;
         let path = loop {
@@ -444,16 +692,7 @@ impl<'e> Example<'e> {
             .parse()
             .map_err(|e| e.to_string())?;
 
-        let res = HtmlElement::new("pre")
-            .with_child(
-                HtmlElement::new("code")
-                    .with_classes(&["example"])
-                    .with_children(annotate(
-                        unit.get_entity(),
-                        &self.get_annotations(unit.get_entity()),
-                    )),
-            )
-            .into();
+        let res = f(&unit);
 
         // We don't really care if we can remove the file or not
         drop(fs::remove_file(path));
@@ -461,18 +700,67 @@ impl<'e> Example<'e> {
         Ok(res)
     }
 
+    fn try_to_analyzed_html(&self) -> Result<(Html, Vec<String>), String> {
+        self.with_unit(|unit| (
+            HtmlElement::new("pre")
+                .with_child(
+                    HtmlElement::new("code")
+                        .with_classes(&["example"])
+                        .with_children(annotate(
+                            unit.get_entity(),
+                            &self.get_annotations(unit.get_entity()),
+                        )),
+                )
+                .into(),
+            self.errors(unit),
+        ))
+    }
+
+    /// Analyze the example for links, reporting it if it doesn't compile. 
+    /// Examples with errors are still shown as long as clang could parse 
+    /// them at all
+    fn analyzed_html(&self) -> Option<Html> {
+        let (html, errors) = match self.try_to_analyzed_html() {
+            Ok((html, errors)) => (Some(html), errors),
+            Err(e) => (None, vec![format!("Unable to parse example: {e}")]),
+        };
+        if !errors.is_empty() {
+            let name = match self.source {
+                Some((ref source, _)) => format!("Example of {}", source.entity),
+                None => "Example".into(),
+            };
+            let location = self.source.as_ref().map(|(source, _)| source.location(0));
+            self.builder.diagnostics.broken_example(location.as_deref(), format!(
+                "{name} doesn't compile:\n      {}", errors.join("\n      ")
+            ));
+        }
+        html
+    }
+
+    /// Compile the example, returning the errors if it doesn't compile
+    pub fn check(&self) -> Result<(), Vec<String>> {
+        let errors = self.with_unit(|unit| self.errors(unit)).map_err(|e| vec![e])?;
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
     pub fn to_html(&self) -> Html {
+        // Diagrams are rendered client-side
+        if self.language == "mermaid" {
+            HtmlElement::new("pre")
+                .with_class("mermaid")
+                .with_text(&self.data)
+                .into()
+        }
         // Custom syntax highlighting with links
-        if self.analyze && let Ok(sweet) = self.try_to_analyzed_html().inspect_err(|e|
-            println!("Unable to parse example: {e}")
-        ) {
+        else if self.analyze && let Some(sweet) = self.analyzed_html() {
             sweet
         }
         // Otherwise create a regular code block
         else {
             HtmlElement::new("pre")
                 .with_child(HtmlElement::new("code")
-                    .with_classes(&["example", "language-cpp"])
+                    .with_class("example")
+                    .with_class(&format!("language-{}", self.language))
                     .with_text(&self.data)
                 )
                 .into()
@@ -480,6 +768,29 @@ impl<'e> Example<'e> {
     }
 }
 
+/// Get the groups an entity has been added to with @ingroup without fully 
+/// parsing (and reporting warnings for) the comment
+pub fn find_groups(raw: &str) -> Vec<String> {
+    let raw = normalize_commands(raw);
+    let mut lexer = CommentLexer::new(&raw);
+    let mut res = Vec::new();
+    while let Some(cmd) = lexer.next_command() {
+        if cmd.cmd == "ingroup" {
+            res.extend(lexer.next_param());
+        } else {
+            lexer.next_value();
+        }
+    }
+    res
+}
+
+/// Get the default value of a parameter as written, like `nullptr`
+fn default_argument(param: &Entity) -> Option<String> {
+    let tokens = param.get_range()?.tokenize();
+    let eq = tokens.iter().position(|t| t.get_spelling() == "=")?;
+    Some(join_tokens(&tokens[eq + 1..]))
+}
+
 pub struct JSDocComment<'e> {
     /// Description (duh)
     description: Option<String>,
@@ -491,6 +802,8 @@ pub struct JSDocComment<'e> {
     returns: Option<String>,
     /// What this throws
     throws: Option<String>,
+    /// Whether the documented function can throw, from the AST
+    exception_spec: Option<ExceptionSpec>,
     /// Refer to other doc item(s)
     see: Vec<String>,
     /// Notes about this item
@@ -505,22 +818,122 @@ pub struct JSDocComment<'e> {
     since: Option<String>,
     /// Examples
     examples: Vec<Example<'e>>,
+    /// Groups (modules) this item belongs to; specified with @ingroup
+    groups: Vec<String>,
+    /// Class this free function is related to; specified with @relates
+    relates: Option<String>,
+    /// Whether this item is an implementation detail; specified with
+    /// @internal or @private
+    internal: bool,
+    /// Parameters of the documented function from the AST as (name, type, 
+    /// default value), shown together with the @param descriptions
+    arguments: Vec<(String, String, Option<String>)>,
+    /// Whether the description is in the language being built, from 
+    /// @description[lang=...]
+    localized: bool,
+    /// Where the comment is, if it was parsed from an entity
+    source: Option<CommentSource>,
     /// Reference to builder
     builder: &'e Builder<'e>,
 }
 
+/// LibClang only knows about the comments of declarations, so the comment 
+/// of a macro is read from the lines right above its `#define`
+pub fn macro_comment(entity: &Entity, builder: &Builder) -> Option<String> {
+    let location = entity.get_location()?.get_file_location();
+    let source = builder.header_source(&location.file?.get_path())?;
+    let lines = source.lines()
+        .take(location.line.checked_sub(1)? as usize)
+        .map(|l| l.trim())
+        .collect::<Vec<_>>();
+    let start = if lines.last()?.ends_with("*/") {
+        let start = lines.iter().rposition(|l| l.contains("/*"))?;
+        if !lines[start].starts_with("/**") && !lines[start].starts_with("/*!") {
+            return None;
+        }
+        start
+    } else {
+        lines.iter()
+            .rposition(|l| !(l.starts_with("///") || l.starts_with("//!")))
+            .map_or(0, |i| i + 1)
+    };
+    (start < lines.len()).then(|| lines[start..].join("\n"))
+}
+
+/// Whether the doc comment of an entity hides it, without parsing all of it
+pub fn has_hidden_comment(entity: &Entity) -> bool {
+    entity.get_comment().is_some_and(|c| !is_file_comment(&c) && is_hidden_comment(&c))
+}
+
+/// Format a brief description, like the one from [`JSDocComment::brief`].
+/// `location` is where the comment is, for warnings about its references
+pub fn fmt_brief(builder: &Builder, brief: &str, location: Option<&str>) -> Html {
+    fmt_comment_markdown(builder, &fmt_autolinks(builder, &fmt_refs(builder, brief, location), None))
+}
+
+/// Whether a raw comment has the `@file` command
+fn is_file_comment(raw: &str) -> bool {
+    let text = normalize_commands(raw);
+    text.match_indices("@file").any(|(i, cmd)|
+        !text[i + cmd.len()..].starts_with(|c: char| c.is_alphanumeric())
+    )
+}
+
+/// Find the `@file` comment of a header and the line it starts on. It 
+/// documents the header itself, so it can be anywhere in it
+pub fn file_comment(source: &str) -> Option<(String, u32)> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut start = 0;
+    while start < lines.len() {
+        let line = lines[start].trim_start();
+        if !(line.starts_with("/**") || line.starts_with("/*!")) {
+            start += 1;
+            continue;
+        }
+        let end = (start..lines.len()).find(|i| lines[*i].contains("*/"))?;
+        let raw = lines[start..=end].join("\n");
+        if is_file_comment(&raw) {
+            return Some((raw, start as u32 + 1));
+        }
+        start = end + 1;
+    }
+    None
+}
+
 impl<'e> JSDocComment<'e> {
-    fn parse_mut(mut self, raw: String) -> Self {
+    fn parse_mut(mut self, original: String, depth: usize) -> Self {
+        let raw = normalize_commands(&original);
         let mut lexer = CommentLexer::new(&raw);
+        // Line in the comment to look for the next example from
+        let mut example_line = 0;
 
         while let Some(cmd) = lexer.next_command() {
             match cmd.cmd.as_str() {
-                "description" | "desc" | "brief" =>
+                "description" | "desc" | "brief" | "short" =>
                 // Empty descriptions shouldn't result in warnings
                 // This does make it so empty @description doesn't warn but eh
                 // good enough
                 {
-                    self.description = lexer.next_value()
+                    let value = lexer.next_value();
+                    match cmd.attrs.get("lang").cloned().flatten() {
+                        // Descriptions in other languages are skipped, and 
+                        // one in the language being built replaces the 
+                        // default one
+                        Some(lang) if lang != self.builder.config.language => {}
+                        Some(_) => {
+                            self.description = value;
+                            self.localized = true;
+                        }
+                        None if !self.localized => self.description = value,
+                        None => {}
+                    }
+                }
+                // Doxygen's detailed description follows the brief one
+                "details" => if let Some(details) = lexer.next_value() {
+                    self.description = Some(match self.description.take() {
+                        Some(brief) => format!("{brief}\n\n{details}"),
+                        None => details,
+                    });
                 }
                 "param" | "arg" => self
                     .params
@@ -528,9 +941,9 @@ impl<'e> JSDocComment<'e> {
                 "tparam" | "targ" => self
                     .tparams
                     .push((lexer.param_for(&cmd), lexer.value_for(&cmd))),
-                "return" | "returns" => self.returns = lexer.value_for(&cmd).into(),
-                "throws" => self.throws = lexer.value_for(&cmd).into(),
-                "see" => self.see.push(lexer.value_for(&cmd)),
+                "return" | "returns" | "result" => self.returns = lexer.value_for(&cmd).into(),
+                "throws" | "throw" | "exception" => self.throws = lexer.value_for(&cmd).into(),
+                "see" | "sa" => self.see.push(lexer.value_for(&cmd)),
                 "note" =>
                     if cmd.attrs.contains_key("short") {
                         self.short_notes.push(lexer.value_for(&cmd))
@@ -538,25 +951,95 @@ impl<'e> JSDocComment<'e> {
                     else {
                         self.notes.push(lexer.value_for(&cmd))
                     },
-                "warning" | "warn" => self.warnings.push(lexer.value_for(&cmd)),
+                "remark" | "remarks" => self.notes.push(lexer.value_for(&cmd)),
+                "warning" | "warn" | "attention" => self.warnings.push(lexer.value_for(&cmd)),
                 "version" => self.version = lexer.value_for(&cmd).into(),
                 "since" => self.since = lexer.value_for(&cmd).into(),
-                "example" | "code" => self.examples.push(Example::new(
-                    lexer.value_for(&cmd),
-                    cmd.attrs.contains_key("flash"),
-                    self.builder,
-                )),
-                // _ => println!("Warning parsing JSDoc comment: Unknown command {cmd}"),
+                "ingroup" => self.groups.push(lexer.param_for(&cmd)),
+                "relates" | "related" => self.relates = Some(lexer.param_for(&cmd)),
+                "internal" | "private" | "hide" => self.internal = true,
+                // The name of the file is optional since the comment is 
+                // already in it
+                "file" => {
+                    lexer.param_on_line();
+                }
+                "example" | "code" => {
+                    let value = lexer.value_for(&cmd);
+                    let mut example = Example::new(&cmd, value, self.builder);
+                    if let Some(ref source) = self.source {
+                        example_line = example.locate(&original, source, example_line);
+                    }
+                    self.examples.push(example);
+                }
+                // Code from the project's files. @snippet needs a tag 
+                // unless it's given a range of lines
+                "snippet" | "include" => {
+                    let path = lexer.param_for(&cmd);
+                    let lines = cmd.attrs.get("lines").cloned().flatten();
+                    let tag = (cmd.cmd == "snippet" && lines.is_none()).then(|| lexer.param_for(&cmd));
+                    match load_snippet(self.builder, &path, tag.as_deref(), lines.as_deref()) {
+                        Ok((code, lang)) => self.examples.push(
+                            Example::new_with_language(&cmd, code, Some(lang), self.builder)
+                        ),
+                        Err(e) => lexer.warn(&cmd, e),
+                    }
+                }
+                // The code block was already consumed by @code
+                "endcode" => {}
+                "copydoc" => {
+                    let target = lexer.param_for(&cmd);
+                    if depth >= MAX_COPYDOC_DEPTH {
+                        lexer.warn(&cmd, format!(
+                            "Too many nested @copydoc commands while copying docs from {target}"
+                        ));
+                    }
+                    else if let Some(raw) = resolve_entity(self.builder, &target)
+                        .and_then(|e| e.get_comment())
+                    {
+                        self.copy_from(Self::new(self.builder).parse_mut(raw, depth + 1));
+                    }
+                    else {
+                        lexer.warn(&cmd, format!(
+                            "Unable to find documentation to copy from {target} for @copydoc"
+                        ));
+                    }
+                }
                 _ => {
+                    lexer.warn(&cmd, format!("Unknown command @{} in JSDoc comment", cmd.cmd));
                     // eat a value even though this is an unknown command
                     lexer.next_value();
                 }
             }
         }
 
+        for (line, warning) in lexer.warnings {
+            let location = self.source.as_ref().map(|s| s.location(line as u32));
+            self.builder.diagnostics.warn_at(Category::Parse, location.as_deref(), warning);
+        }
+
         self
     }
 
+    /// Fill in everything this comment doesn't document yet from another 
+    /// comment (for @copydoc)
+    fn copy_from(&mut self, other: Self) {
+        self.description = self.description.take().or(other.description);
+        self.returns = self.returns.take().or(other.returns);
+        self.throws = self.throws.take().or(other.throws);
+        self.version = self.version.take().or(other.version);
+        self.since = self.since.take().or(other.since);
+        self.relates = self.relates.take().or(other.relates);
+        self.params.extend(other.params);
+        self.tparams.extend(other.tparams);
+        self.see.extend(other.see);
+        self.notes.extend(other.notes);
+        self.short_notes.extend(other.short_notes);
+        self.warnings.extend(other.warnings);
+        self.examples.extend(other.examples);
+        self.groups.extend(other.groups);
+        self.internal |= other.internal;
+    }
+
     pub fn new(builder: &'e Builder<'e>) -> Self {
         Self {
             description: None,
@@ -564,6 +1047,7 @@ impl<'e> JSDocComment<'e> {
             tparams: Vec::new(),
             returns: None,
             throws: None,
+            exception_spec: None,
             see: Vec::new(),
             notes: Vec::new(),
             short_notes: Vec::new(),
@@ -571,15 +1055,97 @@ impl<'e> JSDocComment<'e> {
             version: None,
             since: None,
             examples: Vec::new(),
+            groups: Vec::new(),
+            relates: None,
+            internal: false,
+            arguments: Vec::new(),
+            localized: false,
+            source: None,
             builder,
         }
     }
 
-    pub fn parse(raw: String, builder: &'e Builder<'e>) -> Self {
-        Self::new(builder).parse_mut(raw)
+    /// Parse the doc comment of an entity if it has one, keeping track of 
+    /// where it is so warnings and examples that don't compile can point at 
+    /// the line in the header
+    pub fn parse_for(entity: &Entity, builder: &'e Builder<'e>) -> Option<Self> {
+        let raw = match entity.get_kind() {
+            EntityKind::MacroDefinition => macro_comment(entity, builder)?,
+            _ => entity.get_comment()?,
+        };
+        // LibClang attaches the `@file` comment at the top of a header to the
+        // first declaration after it, but it documents the header
+        if is_file_comment(&raw) {
+            return None;
+        }
+        let mut res = Self::new(builder);
+        res.source = Some(CommentSource::new(entity));
+        Some(res.parse_mut(raw, 0))
+    }
+
+    /// Parse the `@file` comment of a header if it has one
+    pub fn parse_file(path: &Path, builder: &'e Builder<'e>) -> Option<Self> {
+        let (raw, line) = file_comment(&builder.header_source(path)?)?;
+        let mut res = Self::new(builder);
+        res.source = Some(CommentSource {
+            entity: path.to_string_lossy().to_string(),
+            file: Some(path.to_owned()),
+            line,
+        });
+        Some(res.parse_mut(raw, 0))
+    }
+
+    /// Note that the comment is the documentation of the method `base` that 
+    /// the undocumented override `fun` overrides. The override's parameters
+    /// may be named differently, so the `@param`s are matched to them by
+    /// position
+    pub fn inherit_from(&mut self, base: &Entity, fun: &Entity) {
+        let class = base.get_semantic_parent().map(|p| p.full_name().join("::")).unwrap_or_default();
+        self.short_notes.push(
+            self.builder.config.tr("Inherited from {class}").replace("{class}", &class)
+        );
+        let names = |f: &Entity| f.get_children()
+            .into_iter()
+            .filter(|c| c.get_kind() == EntityKind::ParmDecl)
+            .map(|c| c.get_name().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let (base_names, names) = (names(base), names(fun));
+        for param in &mut self.params {
+            if let Some(ix) = base_names.iter().position(|n| n == &param.0)
+                && let Some(name) = names.get(ix)
+                && !name.is_empty()
+            {
+                param.0 = name.clone();
+            }
+        }
+    }
+
+    /// Get the release that introduced the entity from the git history if 
+    /// there's no @since
+    pub fn infer_since(&mut self, entity: &Entity) {
+        if self.since.is_none() {
+            self.since = self.builder.changelog.since(entity);
+        }
+    }
+
+    /// The first paragraph of the description, for lists that only have
+    /// room for a summary
+    pub fn brief(&self) -> Option<&str> {
+        self.description.as_ref()?.split("\n\n").next()
+    }
+
+    /// The brief description formatted as HTML
+    pub fn brief_html(&self) -> Option<Html> {
+        Some(fmt_brief(self.builder, self.brief()?, self.location().as_deref()))
+    }
+
+    /// Where the comment starts, for warnings about it
+    pub fn location(&self) -> Option<String> {
+        self.source.as_ref().map(|s| s.location(0))
     }
 
     pub fn to_html(&self, include_examples: bool) -> Html {
+        let config = &self.builder.config;
         HtmlList::new(vec![HtmlElement::new("div")
             .with_class("description")
             .with_child_opt(
@@ -589,9 +1155,11 @@ impl<'e> JSDocComment<'e> {
                         .with_child_opt(
                             self.version
                                 .as_ref()
-                                .map(|v| Html::p(format!("Version {v}"))),
+                                .map(|v| Html::p(config.tr("Version {version}").replace("{version}", v))),
                         )
-                        .with_child_opt(self.since.as_ref().map(|v| Html::p(format!("Since {v}"))))
+                        .with_child_opt(self.since.as_ref().map(|v|
+                            Html::p(config.tr("Since {version}").replace("{version}", v))
+                        ))
                         .with_children(
                             self.short_notes.iter().map(Html::p).collect()
                         )
@@ -601,18 +1169,18 @@ impl<'e> JSDocComment<'e> {
             .with_child(
                 self.description
                     .as_ref()
-                    .map(|d| fmt_markdown(
+                    .map(|d| fmt_comment_markdown(
                         self.builder,
-                        &fmt_autolinks(self.builder, d, None),
-                        None::<fn(_) -> _>
+                        &fmt_autolinks(self.builder, &fmt_refs(self.builder, d, self.location().as_deref()), None),
                     ))
-                    .unwrap_or(Html::span(&["no-desc"], "No description provided")),
+                    .unwrap_or(Html::span(&["no-desc"], config.tr("No description provided"))),
             )
+            .with_child_opt(self.fmt_arguments())
             .with_child_opt(
-                (!self.params.is_empty()).then_some(
+                (self.arguments.is_empty() && !self.params.is_empty()).then_some(
                     HtmlElement::new("section")
                         .with_class("params")
-                        .with_child(Html::span(&["title"], "Parameters"))
+                        .with_child(Html::span(&["title"], config.tr("Parameters")))
                         .with_child(
                             HtmlElement::new("div").with_class("grid").with_children(
                                 self.params
@@ -629,7 +1197,7 @@ impl<'e> JSDocComment<'e> {
                 (!self.tparams.is_empty()).then_some(
                     HtmlElement::new("section")
                         .with_classes(&["params", "template"])
-                        .with_child(Html::span(&["title"], "Template parameters"))
+                        .with_child(Html::span(&["title"], config.tr("Template parameters")))
                         .with_child(
                             HtmlElement::new("div").with_class("grid").with_children(
                                 self.tparams
@@ -645,16 +1213,11 @@ impl<'e> JSDocComment<'e> {
             .with_child_opt(self.returns.as_ref().map(|ret| {
                 HtmlElement::new("section")
                     .with_classes(&["params", "returns", "grid"])
-                    .with_child(Html::span(&["title"], "Return value"))
+                    .with_child(Html::span(&["title"], config.tr("Return value")))
                     .with_child(Html::div(ret.clone()))
             }))
-            .with_child_opt(self.throws.as_ref().map(|ret| {
-                HtmlElement::new("section")
-                    .with_classes(&["params", "throws", "grid"])
-                    .with_child(Html::span(&["title"], "Exceptions"))
-                    .with_child(Html::div(ret.clone()))
-            }))
-            // todo: see
+            .with_child_opt(self.fmt_exceptions())
+            .with_child_opt(self.fmt_see())
             .with_children(
                 self.notes
                     .iter()
@@ -689,7 +1252,191 @@ impl<'e> JSDocComment<'e> {
         .into()
     }
 
+    /// Format the parameters of a function with their types and default 
+    /// values from the AST and their descriptions from @param
+    fn fmt_arguments(&self) -> Option<HtmlElement> {
+        if self.arguments.is_empty() {
+            return None;
+        }
+        Some(HtmlElement::new("section")
+            .with_class("params")
+            .with_child(Html::span(&["title"], self.builder.config.tr("Parameters")))
+            .with_child(
+                HtmlElement::new("div").with_classes(&["grid", "arguments"]).with_children(
+                    self.arguments
+                        .iter()
+                        .flat_map(|(name, ty, default)| {
+                            let desc = self.params.iter()
+                                .find(|p| &p.0 == name)
+                                .map(|p| p.1.clone())
+                                .unwrap_or_default();
+                            vec![
+                                Html::p(name),
+                                HtmlElement::new("code")
+                                    .with_text(ty)
+                                    .with_child_opt(default.as_ref().map(|d|
+                                        Html::span(&["default", "space-before"], &format!("= {d}"))
+                                    ))
+                                    .into(),
+                                Html::div(desc),
+                            ]
+                        })
+                        .collect(),
+                ),
+            ))
+    }
+
+    /// Format the @see references as a list of links. References that 
+    /// resolve to an entity link to its docs, anything else is rendered as 
+    /// markdown
+    fn fmt_see(&self) -> Option<HtmlElement> {
+        if self.see.is_empty() {
+            return None;
+        }
+        let fmt_text = |text: &str| fmt_comment_markdown(
+            self.builder,
+            &fmt_autolinks(self.builder, &fmt_refs(self.builder, text, self.location().as_deref()), None),
+        );
+        Some(HtmlElement::new("section")
+            .with_classes(&["params", "see"])
+            .with_child(Html::span(&["title"], self.builder.config.tr("See also")))
+            .with_child(
+                HtmlElement::new("ul").with_children(
+                    self.see.iter().map(|see| {
+                        let (target, rest) = see.split_once(char::is_whitespace)
+                            .map(|(t, r)| (t, r.trim()))
+                            .unwrap_or((see.as_str(), ""));
+                        HtmlElement::new("li")
+                            .with_child(match resolve_ref(self.builder, target) {
+                                Some(url) => Html::from(HtmlList::new(vec![
+                                    HtmlElement::new("a")
+                                        .with_attr("href", url)
+                                        .with_child(HtmlElement::new("code").with_text(target))
+                                        .into(),
+                                    if rest.is_empty() {
+                                        Html::Raw(String::new())
+                                    } else {
+                                        fmt_text(rest)
+                                    },
+                                ])),
+                                None => fmt_text(see),
+                            })
+                            .into()
+                    }).collect()
+                )
+            ))
+    }
+
+    /// Warn about @param commands that don't match the function's actual 
+    /// parameters, and parameters missing from an otherwise documented list
+    pub fn check_params(&self, fun: &Entity) {
+        let names = fun.get_children()
+            .into_iter()
+            .filter(|c| c.get_kind() == EntityKind::ParmDecl)
+            .filter_map(|a| a.get_name())
+            .collect::<Vec<_>>();
+        let full_name = fun.full_name().join("::");
+        for (param, _) in &self.params {
+            if !names.contains(param) {
+                self.builder.diagnostics.warn_at(Category::Parse, self.location().as_deref(), format!(
+                    "{full_name} documents parameter '{param}' which doesn't exist"
+                ));
+            }
+        }
+        if !self.params.is_empty() {
+            for name in &names {
+                if !self.params.iter().any(|p| &p.0 == name) {
+                    self.builder.diagnostics.warn_at(Category::Parse, self.location().as_deref(), format!(
+                        "{full_name} is missing documentation for parameter '{name}'"
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Take the exception specification of a function from the AST. 
+    /// Functions that are `noexcept` but document @throws are reported
+    pub fn merge_exception_spec(&mut self, fun: &Entity) {
+        let spec = exception_spec(fun);
+        if spec == ExceptionSpec::Noexcept && self.throws.is_some() {
+            self.builder.diagnostics.warn_at(Category::Parse, self.location().as_deref(), format!(
+                "{} is noexcept but documents exceptions with @throws",
+                fun.full_name().join("::")
+            ));
+        }
+        self.exception_spec = Some(spec);
+    }
+
+    /// Format the exception safety of a function together with its @throws 
+    /// docs. Functions that may throw only get the section if they document 
+    /// what they throw
+    fn fmt_exceptions(&self) -> Option<HtmlElement> {
+        let config = &self.builder.config;
+        let safety = match self.exception_spec {
+            Some(ExceptionSpec::Noexcept) => Some(config.tr("Never throws (noexcept)").to_owned()),
+            Some(ExceptionSpec::Conditional(ref cond)) => Some(
+                config.tr("Never throws if {condition}").replace("{condition}", cond)
+            ),
+            Some(ExceptionSpec::MayThrow) if self.throws.is_some() => Some(config.tr("May throw").to_owned()),
+            _ => None,
+        };
+        if safety.is_none() && self.throws.is_none() {
+            return None;
+        }
+        Some(HtmlElement::new("section")
+            .with_classes(&["params", "throws", "grid"])
+            .with_child(Html::span(&["title"], config.tr("Exceptions")))
+            .with_child(HtmlElement::new("div")
+                .with_child_opt(safety.map(|s| Html::span(&["exception-safety"], &s)))
+                .with_child_opt(self.throws.as_ref().map(Html::div))
+            )
+        )
+    }
+
+    /// Take the parameters of a function from the AST, so they're shown 
+    /// with their types and default values even if they aren't documented
+    pub fn merge_params(&mut self, fun: &Entity) {
+        self.arguments = fun.get_children()
+            .into_iter()
+            .filter(|c| c.get_kind() == EntityKind::ParmDecl)
+            .map(|arg| (
+                arg.get_name().unwrap_or_default(),
+                arg.get_type().map(|t| t.get_display_name()).unwrap_or_default(),
+                default_argument(&arg),
+            ))
+            .collect();
+    }
+
     pub fn examples(&self) -> &Vec<Example> {
         &self.examples
     }
+
+    pub fn relates(&self) -> Option<&String> {
+        self.relates.as_ref()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let params_json = |params: &Vec<(String, String)>| {
+            params.iter()
+                .map(|(name, desc)| json!({ "name": name, "description": desc }))
+                .collect::<Vec<_>>()
+        };
+        json!({
+            "description": self.description,
+            "params": params_json(&self.params),
+            "tparams": params_json(&self.tparams),
+            "returns": self.returns,
+            "throws": self.throws,
+            "see": self.see,
+            "notes": self.notes,
+            "short_notes": self.short_notes,
+            "warnings": self.warnings,
+            "version": self.version,
+            "since": self.since,
+            "examples": self.examples.iter().map(|e| &e.data).collect::<Vec<_>>(),
+            "groups": self.groups,
+            "relates": self.relates,
+            "internal": self.internal,
+        })
+    }
 }
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use crate::{html::Html, url::UrlPath};
+use clang::{token::TokenKind, Entity, EntityKind};
+
+use super::{
+    traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem, OutputEntry},
+    builder::Builder,
+    comment::fmt_highlighted,
+    namespace::{CppItem, CppItemKind, Namespace},
+    shared::{fmt_classlike_decl, fmt_fun_decl, fmt_section, output_entity},
+    signature::signature,
+};
+
+/// Get the name of a C++20 concept declaration. LibClang only exposes
+/// concepts as unexposed declarations, so they have to be detected from
+/// the tokens
+pub fn concept_name(entity: &Entity) -> Option<String> {
+    if entity.get_kind() != EntityKind::UnexposedDecl {
+        return None;
+    }
+    let tokens = entity.get_range()?.tokenize();
+    if tokens.first()?.get_spelling() != "template" {
+        return None;
+    }
+    let mut iter = tokens.iter().skip_while(|t| t.get_spelling() != "concept");
+    iter.next()?;
+    iter.next()
+        .filter(|t| t.get_kind() == TokenKind::Identifier)
+        .map(|t| t.get_spelling())
+}
+
+/// Whether the template parameters or requires clause of an entity mention
+/// a concept
+fn uses_concept(entity: &Entity, name: &str) -> bool {
+    entity.get_range().is_some_and(|range|
+        range.tokenize()
+            .iter()
+            .take_while(|t| t.get_spelling() != "{")
+            .any(|t| t.get_kind() == TokenKind::Identifier && t.get_spelling() == name)
+    )
+}
+
+pub struct Concept<'e> {
+    entity: Entity<'e>,
+}
+
+impl<'e> Concept<'e> {
+    pub fn new(entity: Entity<'e>) -> Self {
+        Self { entity }
+    }
+
+    fn fmt_used_by(&self, builder: &Builder) -> Vec<Html> {
+        fn collect<'e>(ns: &Namespace<'e>, out: &mut Vec<Entity<'e>>) {
+            for entry in ns.entries.values() {
+                match entry {
+                    CppItem::Namespace(ns) => collect(ns, out),
+                    CppItem::Function(fun) => out.extend(fun.overloads()),
+                    CppItem::Concept(_) => {}
+                    other => out.push(*other.entity()),
+                }
+            }
+        }
+
+        let name = self.name();
+        let mut entities = Vec::new();
+        collect(&builder.root, &mut entities);
+        entities.sort_by_key(|e| e.full_name());
+        entities.into_iter()
+            .filter(|e| uses_concept(e, &name))
+            .map(|e| match CppItemKind::from(&e) {
+                Some(CppItemKind::Function) => fmt_fun_decl(&e, builder),
+                Some(CppItemKind::Struct) => fmt_classlike_decl(&e, "struct", builder),
+                _ => fmt_classlike_decl(&e, "class", builder),
+            })
+            .collect()
+    }
+}
+
+impl<'e> Entry<'e> for Concept<'e> {
+    fn name(&self) -> String {
+        concept_name(&self.entity).unwrap_or("`Anonymous concept`".into())
+    }
+
+    fn url(&self) -> UrlPath {
+        self.entity.page_url()
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        NavItem::new_link(
+            &self.entity.full_name().join("::"),
+            self.url(),
+            Some(("check-square", false)),
+            Vec::new()
+        )
+    }
+}
+
+impl<'e> ASTEntry<'e> for Concept<'e> {
+    fn entity(&self) -> &Entity<'e> {
+        &self.entity
+    }
+
+    fn category(&self) -> &'static str {
+        "concept"
+    }
+}
+
+impl<'e> OutputEntry<'e> for Concept<'e> {
+    fn output(&self, builder: &Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        let mut vars = output_entity(self, builder);
+        vars.extend([
+            ("definition", fmt_highlighted(&self.entity)),
+            ("used_by", fmt_section(builder.config.tr("Used by"), self.fmt_used_by(builder))),
+        ]);
+        (builder.config.templates.concept.clone(), vars)
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        self.output_description(builder)
+    }
+
+    fn signatures(&self) -> Vec<String> {
+        signature(self.entity()).into_iter().collect()
+    }
+}
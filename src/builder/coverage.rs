@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use clang::{Accessibility, Entity, EntityKind};
+use serde::Serialize;
+
+use super::{
+    builder::Builder,
+    namespace::CppItem,
+    shared::is_member_variable,
+    traits::{get_member_functions, ASTEntry, Access, EntityMethods, Include},
+};
+
+#[derive(Default, Serialize, Clone)]
+pub struct Counts {
+    pub documented: usize,
+    pub total: usize,
+}
+
+impl Counts {
+    fn add(&mut self, documented: bool) {
+        self.total += 1;
+        if documented {
+            self.documented += 1;
+        }
+    }
+
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.documented as f64 / self.total as f64 * 100.0
+        }
+    }
+}
+
+impl std::fmt::Display for Counts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1}% ({}/{})", self.percentage(), self.documented, self.total)
+    }
+}
+
+/// Report of how many public entities have doc comments
+#[derive(Default, Serialize)]
+pub struct Coverage {
+    pub total: Counts,
+    pub classes: Counts,
+    pub functions: Counts,
+    pub fields: Counts,
+    pub namespaces: BTreeMap<String, Counts>,
+    pub sources: BTreeMap<String, Counts>,
+    pub undocumented: Vec<String>,
+}
+
+enum Category {
+    Class,
+    Function,
+    Field,
+}
+
+impl Coverage {
+    fn add(&mut self, entity: &Entity, category: Category, builder: &Builder) {
+        let documented = entity.get_comment().is_some();
+        let full_name = entity.full_name();
+
+        self.total.add(documented);
+        match category {
+            Category::Class => self.classes.add(documented),
+            Category::Function => self.functions.add(documented),
+            Category::Field => self.fields.add(documented),
+        }
+
+        let namespace = entity.ancestorage()
+            .iter()
+            .filter(|e| e.get_kind() == EntityKind::Namespace)
+            .map(|e| e.get_name().unwrap_or("_anon".into()))
+            .collect::<Vec<_>>()
+            .join("::");
+        self.namespaces
+            .entry(if namespace.is_empty() { "(global)".into() } else { namespace })
+            .or_default()
+            .add(documented);
+
+        self.sources
+            .entry(
+                entity.config_source(builder.config.clone())
+                    .map(|s| s.name.clone())
+                    .unwrap_or("(unknown)".into())
+            )
+            .or_default()
+            .add(documented);
+
+        if !documented {
+            self.undocumented.push(full_name.join("::"));
+        }
+    }
+
+    fn add_item(&mut self, item: &CppItem, builder: &Builder) {
+        match item {
+            CppItem::Namespace(ns) => {
+                for entry in ns.entries.values() {
+                    self.add_item(entry, builder);
+                }
+            }
+            CppItem::Class(_) | CppItem::Struct(_) => {
+                let entity = item.entity();
+                self.add(entity, Category::Class, builder);
+                for fun in get_member_functions(entity, Access::Public, Include::All) {
+                    self.add(&fun, Category::Function, builder);
+                }
+                for field in entity.get_children().iter().filter(|c|
+                    is_member_variable(c)
+                        && c.get_accessibility() == Some(Accessibility::Public)
+                ) {
+                    self.add(field, Category::Field, builder);
+                }
+                for nested in item.nested() {
+                    self.add_item(nested, builder);
+                }
+            }
+            CppItem::Function(fun) => {
+                for overload in fun.overloads() {
+                    self.add(&overload, Category::Function, builder);
+                }
+            }
+            CppItem::Concept(_) => {}
+        }
+    }
+
+    pub fn from_builder(builder: &Builder) -> Self {
+        let mut res = Self::default();
+        for entry in builder.root.entries.values() {
+            res.add_item(entry, builder);
+        }
+        res.undocumented.sort();
+        res
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut res = format!("Documentation coverage: {}\n", self.total);
+        res += &format!("  Classes:   {}\n", self.classes);
+        res += &format!("  Functions: {}\n", self.functions);
+        res += &format!("  Fields:    {}\n", self.fields);
+        res += "By namespace:\n";
+        for (name, counts) in &self.namespaces {
+            res += &format!("  {name}: {counts}\n");
+        }
+        res += "By source:\n";
+        for (name, counts) in &self.sources {
+            res += &format!("  {name}: {counts}\n");
+        }
+        res
+    }
+}
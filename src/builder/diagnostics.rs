@@ -0,0 +1,110 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use clang::Entity;
+
+use crate::log::{self, Category, Level};
+
+use super::{comment::CommentSource, traits::EntityMethods};
+
+/// Collects documentation warnings produced while building the docs so they
+/// can be summarized (and fail the build in strict mode)
+pub struct Diagnostics {
+    strict: bool,
+    warnings: Mutex<Vec<String>>,
+    /// Entries and pages that couldn't be built and were left out
+    failures: Mutex<Vec<String>>,
+    /// Warnings already reported by their category, location and message
+    seen: Mutex<HashSet<(Category, Option<String>, String)>>,
+}
+
+impl Diagnostics {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            warnings: Mutex::new(Vec::new()),
+            failures: Mutex::new(Vec::new()),
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// The same comment may be parsed multiple times (class page, file page,
+    /// etc.) so each warning is only reported once. The same message about
+    /// different places, like two overloads missing the same parameter, is
+    /// still reported for each place. Returns the message to report with the
+    /// location in front of it
+    fn is_new(&self, category: Category, location: Option<&str>, msg: &str) -> Option<String> {
+        let new = self.seen.lock().unwrap()
+            .insert((category, location.map(str::to_owned), msg.to_owned()));
+        new.then(|| match location {
+            Some(location) => format!("{location}: {msg}"),
+            None => msg.to_owned(),
+        })
+    }
+
+    pub fn warn<T: AsRef<str>>(&self, category: Category, msg: T) {
+        self.warn_at(category, None, msg);
+    }
+
+    /// Report a warning about something at `location`, like `file:line`
+    pub fn warn_at<T: AsRef<str>>(&self, category: Category, location: Option<&str>, msg: T) {
+        if let Some(msg) = self.is_new(category, location, msg.as_ref()) {
+            self.warnings.lock().unwrap().push(msg.clone());
+            log::warn(category, msg);
+        }
+    }
+
+    /// Missing docs are only warnings in strict mode since most projects have
+    /// plenty of undocumented entities. Otherwise they're shown with 
+    /// `--verbose`
+    pub fn missing_docs(&self, entity: &Entity) {
+        let location = CommentSource::new(entity).location(0);
+        let msg = format!("{} has no documentation", entity.full_name().join("::"));
+        let Some(msg) = self.is_new(Category::MissingDocs, Some(&location), &msg) else {
+            return;
+        };
+        if self.strict {
+            self.warnings.lock().unwrap().push(msg.clone());
+            log::warn(Category::MissingDocs, msg);
+        } else {
+            log::debug(Category::MissingDocs, msg);
+        }
+    }
+
+    /// Examples that don't compile fail the build in strict mode, and are 
+    /// warnings otherwise
+    pub fn broken_example<T: AsRef<str>>(&self, location: Option<&str>, msg: T) {
+        let Some(msg) = self.is_new(Category::Parse, location, msg.as_ref()) else {
+            return;
+        };
+        if self.strict {
+            self.failed(msg);
+        } else {
+            self.warnings.lock().unwrap().push(msg.clone());
+            log::warn(Category::Parse, msg);
+        }
+    }
+
+    /// Report something that couldn't be built at all
+    pub fn failed<T: AsRef<str>>(&self, msg: T) {
+        log::log(Level::Error, Category::Build, msg.as_ref());
+        self.failures.lock().unwrap().push(msg.as_ref().to_owned());
+    }
+
+    pub fn failures(&self) -> Vec<String> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.lock().unwrap().clone()
+    }
+
+    pub fn summary(&self) -> String {
+        let warnings = self.warnings();
+        let mut res = format!("{} documentation warning(s)", warnings.len());
+        for warning in warnings {
+            res += "\n  - ";
+            res += &warning;
+        }
+        res
+    }
+}
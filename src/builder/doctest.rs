@@ -0,0 +1,70 @@
+use clang::Entity;
+
+use super::{
+    builder::Builder,
+    comment::JSDocComment,
+    namespace::CppItem,
+    traits::{ASTEntry, EntityMethods},
+};
+
+/// Get every documented entity (including class members) that could have
+/// examples
+fn collect<'e>(item: &CppItem<'e>, out: &mut Vec<Entity<'e>>) {
+    match item {
+        CppItem::Namespace(ns) => {
+            for entry in ns.entries.values() {
+                collect(entry, out);
+            }
+        }
+        CppItem::Class(_) | CppItem::Struct(_) => {
+            out.push(*item.entity());
+            out.extend(item.entity().get_children());
+            for nested in item.nested() {
+                collect(nested, out);
+            }
+        }
+        CppItem::Function(fun) => out.extend(fun.overloads()),
+        CppItem::Concept(concept) => out.push(*concept.entity()),
+    }
+}
+
+/// Compile every `@example[flash]` snippet against the project's compile
+/// arguments, returning the number of examples checked or a list of the
+/// ones that failed
+pub fn check_examples<'e>(builder: &'e Builder<'e>) -> Result<usize, String> {
+    let mut entities = Vec::new();
+    for entry in builder.root.entries.values() {
+        collect(entry, &mut entities);
+    }
+    entities.sort_by_key(|e| e.full_name());
+    entities.dedup();
+
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for entity in entities {
+        let Some(comment) = JSDocComment::parse_for(&entity, builder) else {
+            continue;
+        };
+        for (i, example) in comment.examples().iter().enumerate().filter(|(_, e)| e.is_analyzed()) {
+            checked += 1;
+            if let Err(errors) = example.check() {
+                failures.push(format!(
+                    "Example #{} of {} doesn't compile:\n      {}",
+                    i + 1,
+                    entity.full_name().join("::"),
+                    errors.join("\n      "),
+                ));
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(checked)
+    } else {
+        Err(format!(
+            "{} of {checked} example(s) failed to compile:\n  - {}",
+            failures.len(),
+            failures.join("\n  - "),
+        ))
+    }
+}
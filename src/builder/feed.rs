@@ -0,0 +1,105 @@
+use std::{fs, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{
+    html::escape::{escape_attr, escape_text},
+    log::{self, Category},
+    url::UrlPath,
+};
+
+use super::{builder::Builder, traits::Entry, tutorial::{Tutorial, TutorialFolder}};
+
+/// Format a time as an RFC 3339 UTC timestamp, like `2023-01-31T12:00:00Z`
+fn fmt_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+
+    // Convert days since the epoch to a civil date
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600, rem % 3600 / 60, rem % 60,
+    )
+}
+
+/// When the tutorial's markdown file was last modified
+fn modified(builder: &Builder, tutorial: &Tutorial) -> SystemTime {
+    let dir = &builder.config.tutorials.as_ref().unwrap().dir;
+    fs::metadata(builder.config.input_dir.join(dir).join(tutorial.path().to_pathbuf()))
+        .and_then(|m| m.modified())
+        .unwrap_or(UNIX_EPOCH)
+}
+
+/// Generate an Atom feed of the tutorials (or the ones in `feed.dir`),
+/// newest first, so readers can subscribe to documentation updates
+pub fn build_feed(builder: &Builder, tutorials: &TutorialFolder) -> Result<(), String> {
+    let config = builder.config.clone();
+    let feed = config.feed.as_ref().unwrap();
+    if config.tutorials.is_none() {
+        log::warn(Category::Config, "feed is enabled but there are no tutorials to list in it");
+        return Ok(());
+    }
+
+    // Feed readers need absolute links
+    let site_url = match config.project.url {
+        Some(ref url) => url.trim_end_matches('/').to_owned(),
+        None => {
+            log::warn(Category::Config, "project.url isn't set, so links in feed.xml won't be absolute");
+            String::new()
+        }
+    };
+    let link = |url: UrlPath| escape_attr(&format!("{site_url}{}", url.to_absolute(config.clone())));
+
+    let prefix = feed.dir.as_ref().map(UrlPath::try_from).transpose()?;
+    let mut entries = tutorials.all_tutorials()
+        .into_iter()
+        .filter(|tut| prefix.as_ref().map_or(true, |p| tut.path().starts_with(p)))
+        .map(|tut| (modified(builder, tut), tut))
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.0.cmp(&a.0));
+    if let Some(limit) = feed.limit {
+        entries.truncate(limit);
+    }
+
+    let updated = entries.first().map(|e| e.0).unwrap_or(SystemTime::now());
+    let mut res = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+        <feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+        <title>{} Docs</title>\n\
+        <link href=\"{root}/\"/>\n\
+        <link rel=\"self\" href=\"{root}/feed.xml\"/>\n\
+        <id>{root}/</id>\n\
+        <updated>{}</updated>\n",
+        escape_text(&config.project.name),
+        fmt_time(updated),
+        root = link(UrlPath::new()),
+    );
+    for (time, tut) in entries {
+        let url = link(tut.url());
+        res += &format!(
+            "<entry>\n\
+            <title>{}</title>\n\
+            <link href=\"{url}\"/>\n\
+            <id>{url}</id>\n\
+            <updated>{}</updated>\n\
+            <summary>{}</summary>\n\
+            </entry>\n",
+            escape_text(&tut.name()),
+            fmt_time(time),
+            escape_text(tut.metadata().description.as_deref().unwrap_or_default()),
+        );
+    }
+    res += "</feed>\n";
+
+    fs::write(config.output_dir.join("feed.xml"), res)
+        .map_err(|e| format!("Unable to save feed.xml: {e}"))
+}
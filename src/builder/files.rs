@@ -1,26 +1,175 @@
 
 use super::{
     builder::Builder,
+    comment::{token_classes, JSDocComment},
+    includes::fmt_includes,
     traits::{BuildResult, Entry, NavItem, OutputEntry, ASTEntry},
-    shared::{fmt_fun_decl, fmt_section, fmt_classlike_decl},
+    shared::{fmt_classlike_decl, fmt_enum_decl, fmt_field, fmt_fun_decl, fmt_macro, fmt_section, fmt_typedef, is_include_guard},
     namespace::CppItemKind
 };
 use crate::{
     config::{Config, Source},
-    html::{Html, HtmlText},
+    html::{Html, HtmlElement, HtmlList, HtmlText},
+    log::Category,
     url::UrlPath,
 };
-use std::{collections::HashMap, path::Path, sync::Arc};
+use clang::{source::SourceRange, Entity, EntityKind};
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::Arc};
 
 pub struct File {
     source: Arc<Source>,
-    path: UrlPath,
+    pub path: UrlPath,
 }
 
 impl File {
     pub fn new(def: Arc<Source>, path: UrlPath) -> Self {
         Self { source: def, path }
     }
+
+    pub fn full_path(&self, config: Arc<Config>) -> PathBuf {
+        config.input_dir.join(self.include_path())
+    }
+
+    /// The path this file is included with, like `foo/bar.hpp`
+    pub fn include_path(&self) -> String {
+        self.source.dir.join(&self.path).to_raw_string()
+    }
+}
+
+/// Render a whole file with syntax highlighting, with an anchor for each line
+/// so links can point to exact definitions
+fn fmt_source(builder: &Builder, path: &Path) -> Result<Html, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read {}: {e}", path.to_string_lossy()))?;
+
+    // Collect the byte ranges of every token with their highlight classes. If 
+    // the file isn't part of the translation unit it just gets no highlighting
+    let mut tokens = Vec::new();
+    if let Some(file) = builder.root.entity().get_translation_unit().get_file(path) {
+        let range = SourceRange::new(
+            file.get_offset_location(0),
+            file.get_offset_location(content.len() as u32),
+        );
+        for token in range.tokenize() {
+            let start = token.get_range().get_start().get_file_location().offset as usize;
+            let end = token.get_range().get_end().get_file_location().offset as usize;
+            tokens.push((start, end.min(content.len()), token_classes(&token)));
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    let mut tokens = tokens.into_iter().peekable();
+    for (num, line) in content.split('\n').enumerate() {
+        let line_end = line_start + line.len();
+        let mut children = vec![
+            HtmlElement::new("a")
+                .with_class("line-number")
+                .with_attr("href", format!("#L{}", num + 1))
+                .with_text((num + 1).to_string())
+                .into(),
+        ];
+
+        let mut pos = line_start;
+        while let Some(&(start, end, classes)) = tokens.peek() && start < line_end {
+            let start = start.max(line_start);
+            if pos < start {
+                children.push(HtmlText::new(&content[pos..start]).into());
+            }
+            pos = end.min(line_end);
+            children.push(Html::span(classes, &content[start..pos]));
+
+            // Tokens spanning multiple lines (block comments) continue on the 
+            // next line
+            if end > line_end {
+                break;
+            }
+            tokens.next();
+        }
+        if pos < line_end {
+            children.push(HtmlText::new(&content[pos..line_end]).into());
+        }
+
+        lines.push(
+            HtmlElement::new("span")
+                .with_class("line")
+                .with_attr("id", format!("L{}", num + 1))
+                .with_children(children)
+                .into()
+        );
+        lines.push(HtmlText::new("\n").into());
+
+        // Skip over the newline
+        line_start = line_end + 1;
+    }
+
+    Ok(HtmlElement::new("pre")
+        .with_child(
+            HtmlElement::new("code")
+                .with_class("source")
+                .with_child(HtmlList::new(lines))
+        )
+        .into())
+}
+
+/// Source code browser page for a file
+struct SourceView<'f> {
+    file: &'f File,
+}
+
+impl<'e, 'f> Entry<'e> for SourceView<'f> {
+    fn name(&self) -> String {
+        format!("{} (source)", self.file.name())
+    }
+
+    fn url(&self) -> UrlPath {
+        self.file.url().join(UrlPath::part("source"))
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        NavItem::new_link(&self.name(), self.url(), Some(("code", false)), Vec::new())
+    }
+}
+
+impl<'e, 'f> OutputEntry<'e> for SourceView<'f> {
+    fn output(&self, builder: &'e Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        let path = self.file.full_path(builder.config.clone());
+        (
+            builder.config.templates.source.clone(),
+            vec![
+                ("name", HtmlText::new(self.file.name()).into()),
+                (
+                    "file_path",
+                    HtmlText::new(self.file.source.dir.join(&self.file.path).to_raw_string()).into(),
+                ),
+                (
+                    "file_page_url",
+                    HtmlText::new(
+                        self.file.url().to_absolute(builder.config.clone()).to_string()
+                    ).into(),
+                ),
+                (
+                    "source",
+                    fmt_source(builder, &path).unwrap_or_else(|e| {
+                        builder.diagnostics.warn(Category::Build, e);
+                        Html::p(builder.config.tr("Unable to read source"))
+                    }),
+                ),
+            ],
+        )
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        format!(
+            "Source code of {} in {}",
+            self.file.path,
+            builder.config.project.name
+        )
+    }
 }
 
 impl<'e> Entry<'e> for File {
@@ -33,7 +182,11 @@ impl<'e> Entry<'e> for File {
     }
 
     fn build(&self, builder: &Builder<'e>) -> BuildResult {
-        builder.create_output_for(self)
+        let mut handles = builder.create_output_for(self)?;
+        if builder.config.docs.source_pages {
+            handles.extend(builder.create_output_for(&SourceView { file: self })?);
+        }
+        Ok(handles)
     }
 
     fn nav(&self) -> NavItem {
@@ -43,21 +196,25 @@ impl<'e> Entry<'e> for File {
 
 impl<'e> OutputEntry<'e> for File {
     fn output(&self, builder: &'e Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
-        let matcher = |entry: &dyn ASTEntry<'e>| -> bool {
-            entry.entity().get_location()
+        let full_path = self.full_path(builder.config.clone());
+        let is_here = |entity: &Entity| -> bool {
+            entity.get_location()
                 .and_then(|file| file.get_file_location().file)
-                .is_some_and(|file|
-                    file.get_path() == builder.config.input_dir.join(
-                        self.source.dir.join(&self.path).to_raw_string()
-                    )
-                )
+                .is_some_and(|file| file.get_path() == full_path)
         };
+        let matcher = |entry: &dyn ASTEntry<'e>| -> bool { is_here(entry.entity()) };
+        let (includes, included_by, include_graph) = fmt_includes(&self.include_path(), builder);
 
-        (
+        let (template, mut vars): (Arc<String>, Vec<(&'static str, Html)>) = (
             builder.config.templates.file.clone(),
             vec![
                 ("name", HtmlText::new(self.name()).into()),
-                ("description", Html::p("")),
+                (
+                    "description",
+                    JSDocComment::parse_file(&full_path, builder)
+                        .map(|c| c.to_html(true))
+                        .unwrap_or(Html::Raw(String::new())),
+                ),
                 (
                     "file_url",
                     HtmlText::new(
@@ -77,10 +234,26 @@ impl<'e> OutputEntry<'e> for File {
                     "file_path",
                     HtmlText::new(self.source.dir.join(&self.path).to_raw_string()).into(),
                 ),
+                (
+                    "source_link",
+                    if builder.config.docs.source_pages {
+                        HtmlElement::new("a")
+                            .with_attr("href", self.url().join(UrlPath::part("source")).to_absolute(builder.config.clone()))
+                            .with_class("source-link")
+                            .with_child(Html::feather("code"))
+                            .with_text(builder.config.tr("View source"))
+                            .into()
+                    } else {
+                        Html::Raw(String::new())
+                    },
+                ),
+                ("includes", includes),
+                ("included_by", included_by),
+                ("include_graph", include_graph),
                 (
                     "functions",
                     fmt_section(
-                        "Functions",
+                        builder.config.tr("Functions"),
                         builder.root
                             .get(&|entry| 
                                 matches!(
@@ -96,7 +269,7 @@ impl<'e> OutputEntry<'e> for File {
                 (
                     "classes",
                     fmt_section(
-                        "Classes",
+                        builder.config.tr("Classes"),
                         builder.root
                             .get(&|entry| 
                                 matches!(
@@ -112,7 +285,7 @@ impl<'e> OutputEntry<'e> for File {
                 (
                     "structs",
                     fmt_section(
-                        "Structs",
+                        builder.config.tr("Structs"),
                         builder.root
                             .get(&|entry| 
                                 matches!(
@@ -126,7 +299,64 @@ impl<'e> OutputEntry<'e> for File {
                     ),
                 ),
             ],
-        )
+        );
+        if !builder.config.analysis.c {
+            return (template, vars);
+        }
+
+        // C headers are the API's modules, so everything declared in them is 
+        // listed on their page
+        let declared = |kind| builder.root.declared(kind).into_iter().filter(is_here).collect::<Vec<_>>();
+        vars.extend([
+            (
+                "enums",
+                fmt_section(
+                    builder.config.tr("Enums"),
+                    declared(EntityKind::EnumDecl).iter().map(|e| fmt_enum_decl(e, builder)).collect(),
+                ),
+            ),
+            (
+                "typedefs",
+                fmt_section(
+                    builder.config.tr("Typedefs"),
+                    declared(EntityKind::TypedefDecl)
+                        .iter()
+                        // `typedef struct Foo Foo;` only makes the struct 
+                        // usable without writing `struct`
+                        .filter(|t| t.get_typedef_underlying_type()
+                            .and_then(|u| u.get_declaration())
+                            .and_then(|d| d.get_name()) != t.get_name()
+                        )
+                        .map(|t| fmt_typedef(t, builder))
+                        .collect(),
+                ),
+            ),
+            (
+                "macros",
+                fmt_section(
+                    builder.config.tr("Macros"),
+                    declared(EntityKind::MacroDefinition)
+                        .iter()
+                        .filter(|m| !is_include_guard(m))
+                        .map(|m| fmt_macro(m, builder))
+                        .collect(),
+                ),
+            ),
+            (
+                "constants",
+                fmt_section(
+                    builder.config.tr("Constants"),
+                    declared(EntityKind::VarDecl)
+                        .iter()
+                        // Mutable globals are state, not part of the API's 
+                        // constants
+                        .filter(|v| v.get_type().is_some_and(|t| t.is_const_qualified()))
+                        .map(|v| fmt_field(v, builder))
+                        .collect(),
+                ),
+            ),
+        ]);
+        (builder.config.templates.c_file.clone(), vars)
     }
     
     fn description(&self, builder: &'e Builder<'e>) -> String {
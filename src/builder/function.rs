@@ -4,18 +4,39 @@ use crate::{html::Html, url::UrlPath};
 use clang::Entity;
 
 use super::{
-    traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem, OutputEntry},
+    traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem, OutputEntry, SubItem},
     builder::Builder,
-    shared::output_entity,
+    shared::{fmt_fun_decl, fmt_section, member_fun_link, output_entity},
+    signature::signature,
 };
 
+/// A free function along with all of its overloads, which share one page
 pub struct Function<'e> {
     entity: Entity<'e>,
+    overloads: Vec<Entity<'e>>,
 }
 
 impl<'e> Function<'e> {
     pub fn new(entity: Entity<'e>) -> Self {
-        Self { entity }
+        Self { entity, overloads: Vec::new() }
+    }
+
+    pub fn add_overload(&mut self, overload: Entity<'e>) {
+        if overload != self.entity && !self.overloads.contains(&overload) {
+            self.overloads.push(overload);
+        }
+    }
+
+    pub fn merge(&mut self, other: Function<'e>) {
+        self.add_overload(other.entity);
+        for overload in other.overloads {
+            self.add_overload(overload);
+        }
+    }
+
+    /// Get every overload of this function, including the first one
+    pub fn overloads(&self) -> Vec<Entity<'e>> {
+        std::iter::once(self.entity).chain(self.overloads.iter().copied()).collect()
     }
 }
 
@@ -27,7 +48,7 @@ impl<'e> Entry<'e> for Function<'e> {
     }
 
     fn url(&self) -> UrlPath {
-        self.entity.rel_docs_url().expect("Unable to get function URL")
+        self.entity.page_url()
     }
 
     fn build(&self, builder: &Builder<'e>) -> BuildResult {
@@ -35,7 +56,20 @@ impl<'e> Entry<'e> for Function<'e> {
     }
 
     fn nav(&self) -> NavItem {
-        NavItem::new_link(&self.name(), self.url(), Some(("code", true)), Vec::new())
+        // Link to each overload separately if there are multiple
+        let overloads = if self.overloads.is_empty() {
+            Vec::new()
+        } else {
+            self.overloads()
+                .iter()
+                .filter_map(|e| Some(SubItem {
+                    title: e.get_display_name()?,
+                    heading: member_fun_link(e)?,
+                    icon: Some((String::from("code"), true)),
+                }))
+                .collect()
+        };
+        NavItem::new_link(&self.name(), self.url(), Some(("code", true)), overloads)
     }
 }
 
@@ -51,13 +85,26 @@ impl<'e> ASTEntry<'e> for Function<'e> {
 
 impl<'e> OutputEntry<'e> for Function<'e> {
     fn output(&self, builder: &Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
-        (
-            builder.config.templates.function.clone(),
-            output_entity(self, builder),
-        )
+        let mut vars = output_entity(self, builder);
+        vars.push((
+            "overloads",
+            if self.overloads.is_empty() {
+                Html::Raw(String::new())
+            } else {
+                fmt_section(
+                    builder.config.tr("Overloads"),
+                    self.overloads().iter().map(|e| fmt_fun_decl(e, builder)).collect(),
+                )
+            },
+        ));
+        (builder.config.templates.function.clone(), vars)
     }
 
     fn description(&self, builder: &'e Builder<'e>) -> String {
         self.output_description(builder)
     }
+
+    fn signatures(&self) -> Vec<String> {
+        self.overloads().iter().filter_map(signature).collect()
+    }
 }
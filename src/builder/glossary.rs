@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    html::{escape::{escape_attr, escape_text}, Html, HtmlElement, HtmlText},
+    url::UrlPath,
+};
+
+use super::{
+    anchors::{heading_id, mark_page_term, page_terms, reserve_id},
+    builder::Builder,
+    markdown::fmt_markdown,
+    traits::{BuildResult, Entry, NavItem, OutputEntry},
+};
+
+/// The anchor of a term on the glossary page, like `term-translation-unit`
+pub fn term_id(term: &str) -> String {
+    format!(
+        "term-{}",
+        term.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    )
+}
+
+/// Render the markdown of a definition for a tooltip. This doesn't go
+/// through `fmt_markdown` since that would look for glossary terms in it
+fn fmt_definition(definition: &str) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, pulldown_cmark::Parser::new(definition));
+    html
+}
+
+/// Find the first glossary term in a piece of text that hasn't been used
+/// yet, as the byte range of the match and the index of the term. Terms are
+/// matched as whole words ignoring case
+pub fn find_term(config: &Config, text: &str, used: &[usize]) -> Option<(usize, usize, usize)> {
+    let lower = text.to_lowercase();
+    // Lowercasing can change byte lengths, in which case the matches
+    // wouldn't line up with the original text
+    if lower.len() != text.len() {
+        return None;
+    }
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    config.glossary_terms.iter()
+        .enumerate()
+        .filter(|(ix, _)| !used.contains(ix))
+        .filter_map(|(ix, (term, _))| {
+            let term = term.to_lowercase();
+            lower.match_indices(&term)
+                .find(|(start, m)|
+                    !lower[..*start].ends_with(is_word) && !lower[start + m.len()..].starts_with(is_word)
+                )
+                .map(|(start, m)| (start, start + m.len(), ix))
+        })
+        .min()
+}
+
+/// Wrap the first use of every glossary term in some text in a link to the
+/// glossary with the definition as a tooltip, returning HTML. Terms in
+/// `used` were already wrapped earlier in the same markdown and are skipped,
+/// as are terms wrapped in other descriptions on the page being built
+pub fn fmt_glossary_terms(builder: &Builder, text: &str, used: &mut Vec<usize>) -> Option<String> {
+    let mut res = String::new();
+    let mut rest = text;
+    let mut skip = page_terms();
+    skip.extend(used.iter());
+    while let Some((start, end, ix)) = find_term(&builder.config, rest, &skip) {
+        skip.push(ix);
+        used.push(ix);
+        mark_page_term(ix);
+        let (term, definition) = &builder.config.glossary_terms[ix];
+        let url = UrlPath::part("glossary").to_absolute(builder.config.clone());
+        let tooltip = heading_id(&format!("tooltip-{}", term_id(term)));
+        res += &escape_text(&rest[..start]);
+        res += &format!(
+            "<span class=\"glossary-term\" tabindex=\"0\">\
+                <a href=\"{}#{}\" aria-describedby=\"{tooltip}\">{}</a>\
+                <span class=\"glossary-tooltip\" role=\"tooltip\" id=\"{tooltip}\">{}</span>\
+            </span>",
+            escape_attr(&url.to_string()),
+            term_id(term),
+            escape_text(&rest[start..end]),
+            fmt_definition(definition),
+        );
+        rest = &rest[end..];
+    }
+    (!res.is_empty()).then(|| res + &escape_text(rest))
+}
+
+/// A page listing every term in the glossary with its definition
+pub struct Glossary {
+    /// The title in the configured language
+    name: String,
+}
+
+impl Glossary {
+    /// Create the page if the project has a glossary
+    pub fn new(config: Arc<Config>) -> Option<Self> {
+        (!config.glossary_terms.is_empty()).then(|| Self { name: config.tr("Glossary").to_owned() })
+    }
+}
+
+impl<'e> Entry<'e> for Glossary {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn url(&self) -> UrlPath {
+        UrlPath::part("glossary")
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        NavItem::new_link(&self.name, self.url(), Some(("bookmark", false)), Vec::new())
+    }
+}
+
+impl<'e> OutputEntry<'e> for Glossary {
+    fn output(&self, builder: &'e Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        let mut terms = builder.config.glossary_terms.iter().collect::<Vec<_>>();
+        terms.sort_by_key(|(term, _)| term.to_lowercase());
+        (
+            builder.config.templates.glossary.clone(),
+            vec![
+                ("name", HtmlText::new(&self.name).into()),
+                (
+                    "content",
+                    HtmlElement::new("dl")
+                        .with_class("glossary")
+                        .with_children(terms.into_iter()
+                            .flat_map(|(term, definition)| {
+                                reserve_id(&term_id(term));
+                                [
+                                    HtmlElement::new("dt")
+                                        .with_attr("id", term_id(term))
+                                        .with_text(term)
+                                        .into(),
+                                    HtmlElement::new("dd")
+                                        .with_child(fmt_markdown(builder, definition, None::<fn(_) -> _>, None))
+                                        .into(),
+                                ]
+                            })
+                            .collect()
+                        )
+                        .into(),
+                ),
+            ],
+        )
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        builder.config.tr("Glossary of the terms used in {name}").replace("{name}", &builder.config.project.name)
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use clang::Entity;
+use glob::Pattern;
+
+use crate::{
+    config::Config,
+    html::{Html, HtmlText},
+    url::UrlPath,
+};
+
+use super::{
+    attributes::attribute_categories,
+    builder::Builder,
+    comment::find_groups,
+    markdown::fmt_comment_markdown,
+    namespace::{CppItem, CppItemKind, Namespace},
+    shared::{fmt_classlike_decl, fmt_fun_decl, fmt_section},
+    traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem, OutputEntry},
+};
+
+/// A logical module ("Audio", "Networking", etc.) that groups entities
+/// regardless of which namespace they are in. Entities are added to a group
+/// either with `@ingroup` or through the glob rules in the config
+pub struct Group<'e> {
+    name: String,
+    description: Option<String>,
+    entities: Vec<Entity<'e>>,
+}
+
+impl<'e> Group<'e> {
+    fn new(name: String, description: Option<String>) -> Self {
+        Self { name, description, entities: Vec::new() }
+    }
+
+    pub fn from_config(config: Arc<Config>, root: &Namespace<'e>) -> Result<Vec<Self>, String> {
+        fn collect<'e>(ns: &Namespace<'e>, out: &mut Vec<Entity<'e>>) {
+            for entry in ns.entries.values() {
+                match entry {
+                    CppItem::Namespace(ns) => collect(ns, out),
+                    other => out.push(*other.entity()),
+                }
+            }
+        }
+
+        let mut groups = Vec::new();
+        let mut rules = Vec::new();
+        for def in &config.groups {
+            groups.push(Self::new(def.name.clone(), def.description.clone()));
+            rules.push(
+                def.include.iter()
+                    .map(|p| Pattern::new(p).map_err(|e| format!(
+                        "Invalid glob pattern '{p}' for group {}: {e}", def.name
+                    )))
+                    .collect::<Result<Vec<_>, _>>()?
+            );
+        }
+
+        let mut entities = Vec::new();
+        collect(root, &mut entities);
+        entities.sort_by_key(|e| e.full_name());
+
+        for entity in entities {
+            let header = entity.header(config.clone());
+            for (group, patterns) in groups.iter_mut().zip(&rules) {
+                if header.as_ref().is_some_and(|h| patterns.iter().any(|p| p.matches_path(h))) {
+                    group.entities.push(entity);
+                }
+            }
+            let names = entity.get_comment()
+                .map(|c| find_groups(&c))
+                .unwrap_or_default()
+                .into_iter()
+                .chain(attribute_categories(&entity));
+            for name in names {
+                let group = match groups.iter().position(|g| g.name == name) {
+                    Some(ix) => &mut groups[ix],
+                    None => {
+                        groups.push(Self::new(name, None));
+                        groups.last_mut().unwrap()
+                    }
+                };
+                if !group.entities.contains(&entity) {
+                    group.entities.push(entity);
+                }
+            }
+        }
+
+        Ok(groups)
+    }
+
+    fn fmt_entities(&self, kind: CppItemKind, builder: &Builder) -> Vec<Html> {
+        self.entities.iter()
+            .filter(|e| CppItemKind::from(e) == Some(kind))
+            .map(|e| match kind {
+                CppItemKind::Function => fmt_fun_decl(e, builder),
+                CppItemKind::Struct => fmt_classlike_decl(e, "struct", builder),
+                _ => fmt_classlike_decl(e, "class", builder),
+            })
+            .collect()
+    }
+}
+
+impl<'e> Entry<'e> for Group<'e> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn url(&self) -> UrlPath {
+        UrlPath::part("modules").join(UrlPath::part(&self.name.to_lowercase().replace(' ', "-")))
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        NavItem::new_link(&self.name, self.url(), Some(("package", false)), Vec::new())
+    }
+}
+
+impl<'e> OutputEntry<'e> for Group<'e> {
+    fn output(&self, builder: &'e Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        (
+            builder.config.templates.group.clone(),
+            vec![
+                ("name", HtmlText::new(&self.name).into()),
+                (
+                    "description",
+                    self.description.as_ref()
+                        .map(|d| fmt_comment_markdown(builder, d))
+                        .unwrap_or(Html::Raw(String::new())),
+                ),
+                ("classes", fmt_section(builder.config.tr("Classes"), self.fmt_entities(CppItemKind::Class, builder))),
+                ("structs", fmt_section(builder.config.tr("Structs"), self.fmt_entities(CppItemKind::Struct, builder))),
+                ("functions", fmt_section(builder.config.tr("Functions"), self.fmt_entities(CppItemKind::Function, builder))),
+            ],
+        )
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        format!(
+            "Documentation for the {} module in {}",
+            self.name,
+            builder.config.project.name
+        )
+    }
+}
@@ -0,0 +1,112 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    config::Config,
+    html::{Html, HtmlElement, HtmlList, HtmlText},
+    url::UrlPath,
+};
+
+use super::{
+    builder::Builder,
+    comment::JSDocComment,
+    files::{Dir, Root},
+    shared::fmt_section,
+    traits::{BuildResult, Entry, NavItem, OutputEntry},
+};
+
+/// Include path, page URL and full path of a header
+type HeaderItem = (String, String, PathBuf);
+
+/// A landing page listing every header with the brief description from its
+/// `@file` comment, since many libraries are organized by header rather than
+/// by namespace
+pub struct HeaderList {
+    /// The title in the configured language
+    name: String,
+    /// The headers of each source, by the source's name
+    roots: Vec<(String, Vec<HeaderItem>)>,
+}
+
+fn collect_headers(dir: &Dir, config: Arc<Config>, out: &mut Vec<HeaderItem>) {
+    for sub in dir.dirs.values() {
+        collect_headers(sub, config.clone(), out);
+    }
+    for file in dir.files.values() {
+        out.push((
+            file.include_path(),
+            Entry::url(file).to_absolute(config.clone()).to_string(),
+            file.full_path(config.clone()),
+        ));
+    }
+}
+
+impl HeaderList {
+    /// Create the page if there are any headers to list
+    pub fn new(config: Arc<Config>, file_roots: &[Root]) -> Option<Self> {
+        let roots = file_roots.iter()
+            .map(|root| {
+                let mut headers = Vec::new();
+                collect_headers(&root.dir, config.clone(), &mut headers);
+                headers.sort();
+                (root.source.name.clone(), headers)
+            })
+            .filter(|(_, headers)| !headers.is_empty())
+            .collect::<Vec<_>>();
+        (!roots.is_empty()).then(|| Self { name: config.tr("Headers").to_owned(), roots })
+    }
+}
+
+impl<'e> Entry<'e> for HeaderList {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn url(&self) -> UrlPath {
+        UrlPath::part("headers")
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        NavItem::new_link(&self.name, self.url(), Some(("layers", false)), Vec::new())
+    }
+}
+
+impl<'e> OutputEntry<'e> for HeaderList {
+    fn output(&self, builder: &'e Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        let fmt_header = |(include, url, path): &HeaderItem| -> Html {
+            HtmlElement::new("div")
+                .with_class("header-item")
+                .with_child(
+                    HtmlElement::new("a")
+                        .with_attr("href", url)
+                        .with_child(HtmlElement::new("code").with_text(include))
+                )
+                .with_child(
+                    JSDocComment::parse_file(path, builder)
+                        .and_then(|c| c.brief_html())
+                        .unwrap_or(Html::span(&["no-desc"], builder.config.tr("No description provided")))
+                )
+                .into()
+        };
+        (
+            builder.config.templates.headers.clone(),
+            vec![
+                ("name", HtmlText::new(&self.name).into()),
+                (
+                    "content",
+                    HtmlList::new(self.roots.iter()
+                        .map(|(name, headers)| fmt_section(name, headers.iter().map(fmt_header).collect()))
+                        .collect()
+                    ).into(),
+                ),
+            ],
+        )
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        builder.config.tr("Headers of {name}").replace("{name}", &builder.config.project.name)
+    }
+}
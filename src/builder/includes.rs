@@ -0,0 +1,220 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use crate::{
+    config::Config,
+    html::{Html, HtmlElement},
+    url::UrlPath,
+};
+
+use super::{
+    builder::Builder,
+    files::{Dir, Root},
+    shared::fmt_section,
+    traits::Entry,
+};
+
+/// A documented header, by the path it's included with
+struct Header {
+    url: UrlPath,
+    /// What the header includes as written between the `<>` or `""`
+    includes: Vec<String>,
+}
+
+/// Which headers each documented header includes and is included by. The 
+/// includes are read from the files themselves, so this works the same no 
+/// matter how the headers were parsed
+pub struct IncludeGraph {
+    headers: HashMap<String, Header>,
+    /// The documented headers that include each header, sorted
+    included_by: HashMap<String, Vec<String>>,
+}
+
+/// Get the path of every `#include` in a file
+fn read_includes(path: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    content.lines()
+        .filter_map(|line| {
+            let rest = line.trim_start().strip_prefix('#')?.trim_start().strip_prefix("include")?.trim_start();
+            let (open, close) = match rest.chars().next()? {
+                '<' => ('<', '>'),
+                '"' => ('"', '"'),
+                _ => return None,
+            };
+            let rest = rest.strip_prefix(open)?;
+            Some(rest[..rest.find(close)?].to_owned())
+        })
+        .collect()
+}
+
+/// Resolve `.` and `..` in a path with forward slashes
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn collect(dir: &Dir, config: Arc<Config>, out: &mut HashMap<String, Header>) {
+    for sub in dir.dirs.values() {
+        collect(sub, config.clone(), out);
+    }
+    for file in dir.files.values() {
+        out.insert(file.include_path(), Header {
+            url: Entry::url(file),
+            includes: read_includes(&file.full_path(config.clone())),
+        });
+    }
+}
+
+impl IncludeGraph {
+    pub fn new(config: Arc<Config>, roots: &[Root]) -> Self {
+        let mut headers = HashMap::new();
+        for root in roots {
+            collect(&root.dir, config.clone(), &mut headers);
+        }
+        let mut graph = Self { headers, included_by: HashMap::new() };
+        let mut included_by: HashMap<String, Vec<String>> = HashMap::new();
+        for (key, header) in &graph.headers {
+            for include in &header.includes {
+                if let Some(target) = graph.resolve(key, include) {
+                    included_by.entry(target.to_owned()).or_default().push(key.clone());
+                }
+            }
+        }
+        for by in included_by.values_mut() {
+            by.sort();
+            by.dedup();
+        }
+        graph.included_by = included_by;
+        graph
+    }
+
+    /// Find the documented header an include in `from` refers to. Includes 
+    /// are tried relative to the including file first, then as include paths 
+    /// and finally as the end of one
+    fn resolve(&self, from: &str, include: &str) -> Option<&str> {
+        let dir = from.rsplit_once('/').map(|(d, _)| d).unwrap_or_default();
+        let rel = normalize(&format!("{dir}/{include}"));
+        let include = normalize(include);
+        [rel.as_str(), include.as_str()].into_iter()
+            .find_map(|p| self.headers.get_key_value(p).map(|(k, _)| k.as_str()))
+            .or_else(|| {
+                let suffix = format!("/{include}");
+                let mut matches = self.headers.keys().filter(|k| k.ends_with(&suffix));
+                match (matches.next(), matches.next()) {
+                    (Some(key), None) => Some(key.as_str()),
+                    _ => None,
+                }
+            })
+    }
+
+    /// What a header includes, with the documented headers resolved
+    pub fn includes(&self, header: &str) -> Vec<(String, Option<&str>)> {
+        self.headers.get(header)
+            .map(|h| h.includes.iter().map(|i| (i.clone(), self.resolve(header, i))).collect())
+            .unwrap_or_default()
+    }
+
+    /// The documented headers that include a header
+    pub fn included_by(&self, header: &str) -> Vec<&str> {
+        self.included_by.get(header)
+            .map(|by| by.iter().map(|h| h.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    fn url(&self, header: &str, config: Arc<Config>) -> Option<String> {
+        Some(self.headers.get(header)?.url.to_absolute(config).to_string())
+    }
+}
+
+fn fmt_header(graph: &IncludeGraph, include: &str, header: Option<&str>, config: Arc<Config>) -> Html {
+    HtmlElement::new("div")
+        .with_class("index-item")
+        .with_child(HtmlElement::new("a")
+            .with_attr_opt("href", header.and_then(|h| graph.url(h, config)))
+            .with_child(HtmlElement::new("code").with_text(include))
+        )
+        .into()
+}
+
+/// SVG with the including headers on the left, the header in the middle and 
+/// the headers it includes on the right
+fn fmt_graph(header: &str, includes: &[&str], included_by: &[&str]) -> Html {
+    const ROW: usize = 24;
+    const COL: usize = 260;
+    let rows = includes.len().max(included_by.len()).max(1);
+    let height = rows * ROW;
+    let middle = height / 2;
+    let y = |i: usize, len: usize| (height - len * ROW) / 2 + i * ROW + ROW / 2;
+    let name = |h: &str| h.rsplit('/').next().unwrap_or(h).to_owned();
+
+    let mut svg = HtmlElement::new("svg")
+        .with_class("include-graph")
+        .with_attr("viewBox", format!("0 0 {} {height}", COL * 3))
+        .with_attr("width", COL * 3)
+        .with_attr("height", height);
+    let mut text = |x: usize, y: usize, anchor: &str, label: String| {
+        svg.add_child(HtmlElement::new("text")
+            .with_attr("x", x)
+            .with_attr("y", y)
+            .with_attr("text-anchor", anchor)
+            .with_attr("dominant-baseline", "middle")
+            .with_text(label)
+        );
+    };
+    text(COL * 3 / 2, middle, "middle", name(header));
+    for (i, h) in included_by.iter().enumerate() {
+        text(COL - 10, y(i, included_by.len()), "end", name(h));
+    }
+    for (i, h) in includes.iter().enumerate() {
+        text(COL * 2 + 10, y(i, includes.len()), "start", name(h));
+    }
+    let lines = included_by.iter().enumerate()
+        .map(|(i, _)| (COL, y(i, included_by.len()), COL + 60, middle))
+        .chain(includes.iter().enumerate().map(|(i, _)| (COL * 2 - 60, middle, COL * 2, y(i, includes.len()))))
+        .collect::<Vec<_>>();
+    for (x1, y1, x2, y2) in lines {
+        svg.add_child(HtmlElement::new("line")
+            .with_attr("x1", x1)
+            .with_attr("y1", y1)
+            .with_attr("x2", x2)
+            .with_attr("y2", y2)
+        );
+    }
+    svg.into()
+}
+
+/// Format the "Includes" and "Included by" sections of a file page, plus the 
+/// graph of them if `docs.include-graph` is enabled
+pub fn fmt_includes(header: &str, builder: &Builder) -> (Html, Html, Html) {
+    let config = builder.config.clone();
+    let graph = builder.include_graph();
+    let includes = graph.includes(header);
+    let included_by = graph.included_by(header);
+    let svg = if config.docs.include_graph && !(includes.is_empty() && included_by.is_empty()) {
+        let documented = includes.iter().filter_map(|(_, h)| *h).collect::<Vec<_>>();
+        fmt_graph(header, &documented, &included_by)
+    } else {
+        Html::Raw(String::new())
+    };
+    (
+        fmt_section(
+            config.tr("Includes"),
+            includes.iter().map(|(i, h)| fmt_header(graph, i, *h, config.clone())).collect(),
+        ),
+        fmt_section(
+            config.tr("Included by"),
+            included_by.iter().map(|h| fmt_header(graph, h, Some(*h), config.clone())).collect(),
+        ),
+        svg,
+    )
+}
@@ -0,0 +1,221 @@
+use std::sync::Arc;
+
+use clang::Entity;
+
+use crate::{
+    config::Config,
+    html::{Html, HtmlElement, HtmlList, HtmlText},
+    url::UrlPath,
+};
+
+use super::{
+    builder::Builder,
+    files::{Dir, Root},
+    namespace::{CppItem, Namespace},
+    shared::{fmt_section, index_letter, is_member_shown, member_fun_link, section_id},
+    traits::{get_member_functions, ASTEntry, Access, BuildResult, EntityMethods, Entry, Include, NavItem, OutputEntry},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum IndexKind {
+    Classes,
+    Functions,
+    Files,
+}
+
+/// Name, URL and qualifying scope (or directory) of something listed in an
+/// index
+type IndexItem = (String, String, String);
+
+/// An alphabetical list of every class, function or file like Doxygen's
+/// indexes, for finding something by name without knowing its namespace
+pub struct Index {
+    kind: IndexKind,
+    /// The title in the configured language
+    name: String,
+    /// Files are collected up front since they aren't part of the AST
+    files: Vec<IndexItem>,
+}
+
+fn entity_item(entity: &Entity, builder: &Builder) -> Option<IndexItem> {
+    let mut name = entity.full_name();
+    let last = name.pop()?;
+    Some((last, entity.abs_docs_url(builder.config.clone())?.to_string(), name.join("::")))
+}
+
+fn collect(item: &CppItem, kind: IndexKind, builder: &Builder, out: &mut Vec<IndexItem>) {
+    match item {
+        CppItem::Namespace(ns) => {
+            for entry in ns.entries.values() {
+                collect(entry, kind, builder, out);
+            }
+        }
+        CppItem::Class(_) | CppItem::Struct(_) => {
+            if kind == IndexKind::Classes {
+                out.extend(entity_item(item.entity(), builder));
+            }
+            else if let Some(url) = item.entity().abs_docs_url(builder.config.clone()) {
+                let scope = item.entity().full_name().join("::");
+                for fun in get_member_functions(item.entity(), Access::All, Include::All) {
+                    if !is_member_shown(&fun, builder) {
+                        continue;
+                    }
+                    if let Some(name) = fun.get_name() && let Some(link) = member_fun_link(&fun) {
+                        out.push((name, format!("{url}#{link}"), scope.clone()));
+                    }
+                }
+            }
+            for nested in item.nested() {
+                collect(nested, kind, builder, out);
+            }
+        }
+        CppItem::Function(_) if kind == IndexKind::Functions => {
+            out.extend(entity_item(item.entity(), builder));
+        }
+        _ => {}
+    }
+}
+
+fn collect_files(dir: &Dir, root_name: &str, config: Arc<Config>, out: &mut Vec<IndexItem>) {
+    for sub in dir.dirs.values() {
+        collect_files(sub, root_name, config.clone(), out);
+    }
+    for file in dir.files.values() {
+        let dir = file.path.to_raw_string();
+        let dir = dir.rsplit_once('/').map(|(d, _)| d).unwrap_or_default();
+        out.push((
+            Entry::name(file),
+            Entry::url(file).to_absolute(config.clone()).to_string(),
+            format!("{root_name}/{dir}").trim_end_matches('/').to_owned(),
+        ));
+    }
+}
+
+impl Index {
+    /// Create the indexes for every kind of item. The files index is only
+    /// created if there are any files
+    pub fn all(config: Arc<Config>, file_roots: &[Root]) -> Vec<Self> {
+        let mut files = Vec::new();
+        for root in file_roots {
+            collect_files(&root.dir, &root.source.name, config.clone(), &mut files);
+        }
+        let index = |kind, files| {
+            let mut res = Self { kind, name: String::new(), files };
+            res.name = config.tr(if kind == IndexKind::Classes && config.analysis.c {
+                "All structs"
+            } else {
+                res.title()
+            }).to_owned();
+            res
+        };
+        let mut res = vec![
+            index(IndexKind::Classes, Vec::new()),
+            index(IndexKind::Functions, Vec::new()),
+        ];
+        if !files.is_empty() {
+            res.push(index(IndexKind::Files, files));
+        }
+        res
+    }
+
+    fn title(&self) -> &'static str {
+        match self.kind {
+            IndexKind::Classes => "All classes",
+            IndexKind::Functions => "All functions",
+            IndexKind::Files => "All files",
+        }
+    }
+
+    fn items(&self, root: &Namespace, builder: &Builder) -> Vec<IndexItem> {
+        let mut items = if self.kind == IndexKind::Files {
+            self.files.clone()
+        } else {
+            let mut items = Vec::new();
+            for entry in root.entries.values() {
+                collect(entry, self.kind, builder, &mut items);
+            }
+            items
+        };
+        items.sort_by_key(|(name, _, scope)| (index_letter(name), name.to_lowercase(), scope.clone()));
+        items.dedup();
+        items
+    }
+}
+
+impl<'e> Entry<'e> for Index {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn url(&self) -> UrlPath {
+        UrlPath::part("all").join(UrlPath::part(match self.kind {
+            IndexKind::Classes => "classes",
+            IndexKind::Functions => "functions",
+            IndexKind::Files => "files",
+        }))
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        NavItem::new_link(&self.name, self.url(), Some(("list", false)), Vec::new())
+    }
+}
+
+impl<'e> OutputEntry<'e> for Index {
+    fn output(&self, builder: &'e Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        let mut letters: Vec<(String, Vec<Html>)> = Vec::new();
+        for (name, url, scope) in self.items(&builder.root, builder) {
+            let item = HtmlElement::new("div")
+                .with_class("index-item")
+                .with_child(HtmlElement::new("a").with_attr("href", url).with_text(&name))
+                .with_child_opt((!scope.is_empty()).then(||
+                    Html::span(&["index-scope", "space-before"], &scope)
+                ))
+                .into();
+            let letter = index_letter(&name);
+            match letters.last_mut() {
+                Some((last, items)) if *last == letter => items.push(item),
+                _ => letters.push((letter, vec![item])),
+            }
+        }
+
+        (
+            builder.config.templates.entity_index.clone(),
+            vec![
+                ("name", HtmlText::new(&self.name).into()),
+                (
+                    "letters",
+                    HtmlElement::new("nav")
+                        .with_class("index-letters")
+                        .with_children(letters.iter()
+                            .map(|(letter, _)| HtmlElement::new("a")
+                                .with_attr("href", format!("#{}", section_id(letter)))
+                                .with_text(letter)
+                                .into()
+                            )
+                            .collect()
+                        )
+                        .into(),
+                ),
+                (
+                    "content",
+                    HtmlList::new(letters.into_iter()
+                        .map(|(letter, items)| fmt_section(&letter, items))
+                        .collect()
+                    ).into(),
+                ),
+            ],
+        )
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        format!(
+            "Alphabetical list of {} in {}",
+            self.title().trim_start_matches("All "),
+            builder.config.project.name
+        )
+    }
+}
@@ -0,0 +1,177 @@
+use clang::{Accessibility, Entity, EntityKind};
+use serde_json::{json, Value};
+
+use super::{
+    builder::Builder,
+    comment::JSDocComment,
+    namespace::CppItem,
+    shared::{field_initializer, field_qualifiers, is_member_variable},
+    traits::{ASTEntry, Entry, EntityMethods},
+};
+
+fn fmt_access(entity: &Entity) -> Option<&'static str> {
+    entity.get_accessibility().map(|a| match a {
+        Accessibility::Public => "public",
+        Accessibility::Protected => "protected",
+        Accessibility::Private => "private",
+    })
+}
+
+fn fmt_location(entity: &Entity) -> Value {
+    let Some(loc) = entity.get_location().map(|l| l.get_file_location()) else {
+        return Value::Null;
+    };
+    json!({
+        "file": loc.file.map(|f| f.get_path().to_string_lossy().to_string()),
+        "line": loc.line,
+        "column": loc.column,
+    })
+}
+
+fn fmt_comment(entity: &Entity, builder: &Builder) -> Value {
+    JSDocComment::parse_for(entity, builder)
+        .map(|c| c.to_json())
+        .unwrap_or(Value::Null)
+}
+
+fn fmt_common(entity: &Entity, kind: &str, builder: &Builder) -> Value {
+    json!({
+        "kind": kind,
+        "name": entity.get_name(),
+        "full_name": entity.full_name().join("::"),
+        "url": entity.abs_docs_url(builder.config.clone()).map(|u| u.to_string()),
+        "github_url": entity.github_url(builder.config.clone()),
+        "include": entity.include_path(builder.config.clone()).map(|p| p.to_raw_string()),
+        "location": fmt_location(entity),
+        "comment": fmt_comment(entity, builder),
+    })
+}
+
+fn fmt_function(fun: &Entity, builder: &Builder) -> Value {
+    let mut res = fmt_common(fun, "function", builder);
+    let obj = res.as_object_mut().unwrap();
+    obj.insert("access".into(), json!(fmt_access(fun)));
+    obj.insert("signature".into(), json!(fun.get_type().map(|t| t.get_display_name())));
+    obj.insert("return_type".into(), json!(fun.get_result_type().map(|t| t.get_display_name())));
+    obj.insert("params".into(), json!(
+        fun.get_arguments()
+            .unwrap_or_default()
+            .iter()
+            .map(|arg| json!({
+                "name": arg.get_display_name(),
+                "type": arg.get_type().map(|t| t.get_display_name()),
+            }))
+            .collect::<Vec<_>>()
+    ));
+    obj.insert("static".into(), json!(fun.is_static_method()));
+    obj.insert("virtual".into(), json!(fun.is_virtual_method()));
+    obj.insert("pure_virtual".into(), json!(fun.is_pure_virtual_method()));
+    obj.insert("const".into(), json!(fun.is_const_method()));
+    res
+}
+
+fn fmt_field(field: &Entity, builder: &Builder) -> Value {
+    let mut res = fmt_common(field, "field", builder);
+    let obj = res.as_object_mut().unwrap();
+    obj.insert("access".into(), json!(fmt_access(field)));
+    obj.insert("type".into(), json!(field.get_type().map(|t| t.get_display_name())));
+    obj.insert("qualifiers".into(), json!(field_qualifiers(field)));
+    obj.insert("bit_width".into(), json!(field.get_bit_field_width()));
+    obj.insert("initializer".into(), json!(field_initializer(field)));
+    res
+}
+
+fn fmt_classlike(entry: &dyn ASTEntry, builder: &Builder) -> Value {
+    let entity = entry.entity();
+    let mut res = fmt_common(entity, entry.category(), builder);
+    let children = entity.get_children();
+    let obj = res.as_object_mut().unwrap();
+    obj.insert("display_name".into(), json!(entry.name()));
+    obj.insert("bases".into(), json!(
+        children.iter()
+            .filter(|c| c.get_kind() == EntityKind::BaseSpecifier)
+            .map(|base| json!({
+                "access": fmt_access(base),
+                "virtual": base.is_virtual_base(),
+                "type": base.get_type().map(|t| t.get_display_name()),
+                "url": base.get_type()
+                    .and_then(|t| t.get_declaration())
+                    .and_then(|d| d.abs_docs_url(builder.config.clone()))
+                    .map(|u| u.to_string()),
+            }))
+            .collect::<Vec<_>>()
+    ));
+    obj.insert("functions".into(), json!(
+        children.iter()
+            .filter(|c| c.get_kind() == EntityKind::Method)
+            .map(|f| fmt_function(f, builder))
+            .collect::<Vec<_>>()
+    ));
+    obj.insert("fields".into(), json!(
+        children.iter()
+            .filter(|c| is_member_variable(c))
+            .map(|f| fmt_field(f, builder))
+            .collect::<Vec<_>>()
+    ));
+    res
+}
+
+fn fmt_item(item: &CppItem, builder: &Builder) -> Value {
+    match item {
+        CppItem::Namespace(ns) => {
+            let mut entries = ns.entries.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|p| p.0);
+
+            let mut res = fmt_common(ns.entity(), "namespace", builder);
+            res.as_object_mut().unwrap().insert("entries".into(), json!(
+                entries.into_iter().map(|e| fmt_item(e.1, builder)).collect::<Vec<_>>()
+            ));
+            res
+        }
+        CppItem::Class(_) | CppItem::Struct(_) => {
+            let mut res = fmt_classlike(item, builder);
+            res.as_object_mut().unwrap().insert("nested".into(), json!(
+                item.nested().iter().map(|n| fmt_item(n, builder)).collect::<Vec<_>>()
+            ));
+            res
+        }
+        CppItem::Function(fun) => {
+            let mut res = fmt_function(fun.entity(), builder);
+            res.as_object_mut().unwrap().insert("overloads".into(), json!(
+                fun.overloads()
+                    .iter()
+                    .skip(1)
+                    .map(|o| fmt_function(o, builder))
+                    .collect::<Vec<_>>()
+            ));
+            res
+        }
+        CppItem::Concept(concept) => {
+            let entity = concept.entity();
+            let mut res = fmt_common(entity, "concept", builder);
+            let obj = res.as_object_mut().unwrap();
+            obj.insert("name".into(), json!(concept.name()));
+            obj.insert("definition".into(), json!(
+                entity.get_range()
+                    .map(|r| r.tokenize().iter().map(|t| t.get_spelling()).collect::<Vec<_>>().join(" "))
+            ));
+            res
+        }
+    }
+}
+
+/// Create a JSON representation of the whole documentation model for tools
+/// that want to consume Flash's analysis without scraping the HTML output
+pub fn output_json(builder: &Builder) -> Value {
+    let mut entries = builder.root.entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|p| p.0);
+
+    json!({
+        "project": {
+            "name": builder.config.project.name,
+            "version": builder.config.project.version,
+            "repository": builder.config.project.repository,
+        },
+        "entries": entries.into_iter().map(|e| fmt_item(e.1, builder)).collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,133 @@
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}};
+
+use percent_encoding::percent_decode_str;
+
+use crate::log::Category;
+
+use super::builder::Builder;
+
+/// Pages that only repeat the content of other pages
+const SKIPPED: &[&str] = &["content.html", "offline.html", "print.html"];
+
+fn find_pages(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            find_pages(&path, out);
+        }
+        else if path.extension().is_some_and(|e| e == "html")
+            && !path.file_name().is_some_and(|n| SKIPPED.contains(&n.to_string_lossy().as_ref()))
+        {
+            out.push(path);
+        }
+    }
+}
+
+/// Get the values of every `attr` attribute in some HTML. Minified HTML may
+/// have unquoted values
+fn attr_values(html: &str, attr: &str) -> Vec<String> {
+    let pattern = format!("{attr}=");
+    let mut res = Vec::new();
+    let mut rest = html;
+    while let Some(ix) = rest.find(&pattern) {
+        let before = rest[..ix].chars().last();
+        rest = &rest[ix + pattern.len()..];
+        // Skip things like data-href= and text that just happens to match
+        if !before.is_some_and(|c| c.is_whitespace() || c == '"' || c == '\'') {
+            continue;
+        }
+        let value = match rest.chars().next() {
+            Some(q @ ('"' | '\'')) => rest[1..].split(q).next().unwrap_or_default(),
+            _ => rest.split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or_default(),
+        };
+        res.push(value.replace("&amp;", "&"));
+    }
+    res
+}
+
+fn is_external(href: &str) -> bool {
+    href.starts_with("//") || href.split_once(':').is_some_and(
+        |(scheme, _)| scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    )
+}
+
+/// Find the file an internal link points to. Returns `None` for links that
+/// can't be checked, like ones to other sites or outside of the output
+fn resolve(builder: &Builder, page: &Path, path: &str) -> Option<Result<PathBuf, ()>> {
+    let config = &builder.config;
+    let path = percent_decode_str(path.split('?').next().unwrap_or_default())
+        .decode_utf8_lossy()
+        .to_string();
+    let (base, rel) = if path.is_empty() {
+        return Some(Ok(page.to_path_buf()));
+    } else if path.starts_with('/') {
+        let root = config.output_url.clone().unwrap_or_default().to_string();
+        let rel = path.strip_prefix(&root)?;
+        if !rel.is_empty() && !rel.starts_with('/') {
+            return None;
+        }
+        (config.output_dir.clone(), rel.trim_start_matches('/').to_owned())
+    } else {
+        (page.parent()?.to_path_buf(), path)
+    };
+    let target = base.join(&rel);
+    [target.clone(), target.join("index.html"), base.join(format!("{rel}.html"))]
+        .into_iter()
+        .find(|c| c.is_file())
+        .map(Ok)
+        .or(Some(Err(())))
+}
+
+/// Check that every internal link in the generated pages points to a page
+/// that exists and an anchor on it, if it has one. Dangling links are added
+/// to the documentation warnings so they fail the build in strict mode.
+/// Returns the number of links checked
+pub fn check_links(builder: &Builder) -> Result<usize, String> {
+    let config = builder.config.clone();
+    let mut pages = Vec::new();
+    find_pages(&config.output_dir, &mut pages);
+    pages.sort();
+
+    let mut ids = HashMap::<PathBuf, HashSet<String>>::new();
+    let mut get_ids = |file: &Path| -> HashSet<String> {
+        ids.entry(file.to_path_buf())
+            .or_insert_with(|| {
+                let html = fs::read_to_string(file).unwrap_or_default();
+                attr_values(&html, "id").into_iter().chain(attr_values(&html, "name")).collect()
+            })
+            .clone()
+    };
+
+    let mut checked = 0;
+    for page in pages {
+        let html = fs::read_to_string(&page)
+            .map_err(|e| format!("Unable to read {} for checking links: {e}", page.to_string_lossy()))?;
+        let name = page.strip_prefix(&config.output_dir).unwrap_or(&page).to_string_lossy().to_string();
+        let mut seen = HashSet::new();
+        for href in attr_values(&html, "href") {
+            if is_external(&href) || !seen.insert(href.clone()) {
+                continue;
+            }
+            let (path, frag) = href.split_once('#').unwrap_or((href.as_str(), ""));
+            let Some(target) = resolve(builder, &page, path) else {
+                continue;
+            };
+            checked += 1;
+            let Ok(target) = target else {
+                builder.diagnostics.warn(Category::Links, format!("Dangling link to '{href}' in {name}"));
+                continue;
+            };
+            if !frag.is_empty() {
+                let ids = get_ids(&target);
+                let decoded = percent_decode_str(frag).decode_utf8_lossy();
+                if !ids.contains(frag) && !ids.contains(decoded.as_ref()) {
+                    builder.diagnostics.warn(Category::Links, format!("Dangling anchor in link to '{href}' in {name}"));
+                }
+            }
+        }
+    }
+    Ok(checked)
+}
@@ -1,12 +1,20 @@
 
+use super::anchors::{heading_id, reserve_id, unique_in};
 use super::builder::Builder;
-use super::shared::fmt_emoji;
+use super::glossary::fmt_glossary_terms;
+use super::previews::preview_key;
+use super::shared::{fmt_emoji, fmt_toc, resolve_ref, TocItem};
+use super::snippet::load_snippet;
 use super::traits::Entry;
-use crate::html::{Html, HtmlElement, HtmlText};
+use crate::html::{escape::escape_attr, GenHtml, Html, HtmlElement, HtmlText};
+use crate::log::Category;
 use crate::lookahead::{CreateCachedLookahead, CachedLookahead};
+use crate::normalize::Normalize;
 use crate::url::UrlPath;
-use pulldown_cmark::{CowStr, Event, Tag, LinkType};
+use percent_encoding::percent_decode_str;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, Tag, LinkType};
 use serde::{Deserialize, Deserializer};
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}};
 
 #[derive(Clone, PartialEq, Default)]
 pub enum Style {
@@ -78,8 +86,30 @@ struct MDStream<'i, 'c, 'b, 'e, const SIZE: usize, F: Fn(UrlPath) -> Option<UrlP
     url_fixer: Option<F>,
     builder: &'b Builder<'e>,
     metadata: Option<Metadata>,
+    /// Directory of the markdown file, for resolving relative images and 
+    /// links
+    source_dir: Option<PathBuf>,
     insert_para_stage: InsertP,
     inside_code_block: bool,
+    inside_link: bool,
+    inside_image: bool,
+    /// Whether the current link points to an entity and was written as HTML 
+    /// to give it a preview card
+    inside_preview_link: bool,
+    inside_math: bool,
+    inside_heading: bool,
+    /// Names of the footnotes in the order they're numbered in, with the ids
+    /// of their first reference and their definition. Several descriptions
+    /// on a page can have footnotes with the same name, so the ids are made
+    /// unique on the page
+    footnotes: Vec<(String, String, String)>,
+    /// Glossary terms that already have a tooltip in this markdown, since
+    /// only the first use of each gets one. While a page is being built the
+    /// terms used on the whole page are also skipped
+    glossary_used: Vec<usize>,
+    /// How many levels headings are moved down and the level they're at 
+    /// least, for markdown in doc comments
+    heading_levels: Option<(usize, usize)>,
 }
 
 impl<
@@ -92,16 +122,366 @@ impl<
         url_fixer: Option<F>,
         builder: &'b Builder<'e>,
         metadata: Option<Metadata>,
+        source_dir: Option<PathBuf>,
     ) -> MDStream<'i, 'c, 'b, 'e, SIZE, F> {
         MDStream {
             iter: iter.lookahead_cached::<SIZE>(),
             url_fixer,
             builder,
             metadata,
+            source_dir,
             insert_para_stage: InsertP::Dont,
             inside_code_block: false,
+            inside_link: false,
+            inside_image: false,
+            inside_preview_link: false,
+            inside_math: false,
+            inside_heading: false,
+            footnotes: Vec::new(),
+            glossary_used: Vec::new(),
+            heading_levels: None,
         }
     }
+
+    fn fix_heading_level(&self, lvl: HeadingLevel) -> HeadingLevel {
+        let Some((shift, min)) = self.heading_levels else {
+            return lvl;
+        };
+        HeadingLevel::try_from((lvl as usize + shift).max(min).min(6)).unwrap_or(lvl)
+    }
+
+    /// Find the file a local image or link points to. Paths starting with 
+    /// `/` are relative to the tutorials directory or the project root, 
+    /// others to the markdown file
+    fn resolve_local(&self, dest: &str) -> Result<PathBuf, String> {
+        let config = &self.builder.config;
+        let path = percent_decode_str(dest.split(['#', '?']).next().unwrap_or_default())
+            .decode_utf8_lossy()
+            .to_string();
+        let candidates = if let Some(path) = path.strip_prefix('/') {
+            config.tutorials.as_ref()
+                .map(|t| config.input_dir.join(&t.dir).join(path))
+                .into_iter()
+                .chain([config.input_dir.join(path)])
+                .collect::<Vec<_>>()
+        } else {
+            vec![self.source_dir.clone().unwrap_or_default().join(&path).normalize()]
+        };
+        candidates.iter()
+            .find(|c| c.exists())
+            .cloned()
+            .ok_or(format!("'{dest}' doesn't exist"))
+    }
+
+    /// Copy a local image to the output, returning its new URL
+    fn copy_image(&self, dest: &str) -> Option<String> {
+        let config = &self.builder.config;
+        let file = self.resolve_local(dest)
+            .inspect_err(|e| self.builder.diagnostics.warn(Category::Links, format!("Image {e}")))
+            .ok()?;
+        let Ok(rel) = file.strip_prefix(&config.input_dir) else {
+            self.builder.diagnostics.warn(Category::Links, format!(
+                "Image '{dest}' is outside of the project and can't be copied"
+            ));
+            return None;
+        };
+        // Same placement as tutorials.assets, so docs/images/a.png is 
+        // served from /images/a.png
+        let rel = config.tutorials.as_ref()
+            .and_then(|t| rel.strip_prefix(&t.dir).ok())
+            .unwrap_or(rel);
+        let output = config.output_dir.join(rel);
+        if let Err(e) = output.parent().map(fs::create_dir_all).transpose()
+            .and_then(|_| fs::copy(&file, &output))
+        {
+            self.builder.diagnostics.warn(Category::Build, format!("Unable to copy image '{dest}': {e}"));
+            return None;
+        }
+        let url = UrlPath::try_from(&rel.to_path_buf()).ok()?;
+        Some(url.to_absolute_unstyled(config.clone()).to_string())
+    }
+
+    /// Check that a link to another markdown file points to an existing 
+    /// file, and point relative links to tutorials at their page
+    fn fix_md_link(&self, dest: &str) -> Option<String> {
+        let config = &self.builder.config;
+        let file = self.resolve_local(dest)
+            .inspect_err(|e| self.builder.diagnostics.warn(Category::Links, format!("Broken link: {e}")))
+            .ok()?;
+        if dest.starts_with('/') {
+            return None;
+        }
+        let tutorials = config.input_dir.join(&config.tutorials.as_ref()?.dir);
+        let url = UrlPath::try_from(&file.strip_prefix(tutorials).ok()?.to_path_buf()).ok()?;
+        let frag = dest.split_once('#').map(|(_, f)| format!("#{f}")).unwrap_or_default();
+        Some(format!(
+            "{}{frag}",
+            url.remove_extension(".md").to_absolute(config.clone())
+        ))
+    }
+    /// Fix the destination of a link or image to point to the site root, 
+    /// copying local images to the output along the way
+    fn fix_dest(&self, ty: LinkType, dest: &str, is_image: bool) -> String {
+        let mut new_dest;
+        if ty == LinkType::Inline 
+            && dest.starts_with("/")
+            && let Some(ref url_fixer) = self.url_fixer
+        {
+            let url = UrlPath::new_with_path(
+                dest.split("/").map(|s| s.to_string()).collect()
+            );
+            if let Some(url) = url_fixer(url) {
+                new_dest = url.to_string();
+            }
+            else {
+                new_dest = dest.to_string();
+            }
+        }
+        else {
+            new_dest = dest.to_string();
+        }
+
+        // make the url absolute in any case if it starts with /
+        if dest.starts_with("/") && let Ok(dest) = UrlPath::parse(&new_dest) {
+            new_dest = dest
+                .to_absolute_unstyled(self.builder.config.clone())
+                .to_string();
+        }
+
+        // copy local images to the output and check links to 
+        // other markdown files
+        if self.source_dir.is_some() && !is_external(dest) {
+            let path = dest.split(['#', '?']).next().unwrap_or_default();
+            if is_image {
+                if let Some(url) = self.copy_image(dest) {
+                    new_dest = url;
+                }
+            }
+            else if path.ends_with(".md") && let Some(url) = self.fix_md_link(dest) {
+                new_dest = url;
+            }
+        }
+
+        new_dest
+    }
+
+    /// Consume an image marked with `#dark` or `#light` up to its end
+    fn take_image_variant(&mut self, ty: LinkType, dest: &str, title: &str) -> ImageVariant {
+        let (dest, dark) = image_variant(dest).unwrap_or((dest, false));
+        let mut alt = String::new();
+        for event in self.iter.by_ref() {
+            match event {
+                Event::Text(t) | Event::Code(t) => alt.push_str(&t),
+                Event::End(Tag::Image(_, _, _)) => break,
+                _ => {}
+            }
+        }
+        ImageVariant {
+            dark,
+            src: self.fix_dest(ty, dest, true),
+            alt,
+            title: title.to_owned(),
+        }
+    }
+
+    /// Consume a code block up to its end, returning its code
+    fn take_code_block(&mut self) -> String {
+        let mut code = String::new();
+        for event in self.iter.by_ref() {
+            match event {
+                Event::Text(t) => code.push_str(&t),
+                Event::End(Tag::CodeBlock(_)) => break,
+                _ => {}
+            }
+        }
+        code
+    }
+
+    /// Group a code block with a `tab=Name` and every one right after it 
+    /// into a tab widget
+    fn fmt_code_tabs(&mut self, info: String) -> Event<'i> {
+        let mut tabs = vec![(info, self.take_code_block())];
+        while let Some(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))) = self.iter.peek()
+            && code_tab(info).is_some()
+        {
+            let info = info.to_string();
+            self.iter.next();
+            tabs.push((info, self.take_code_block()));
+        }
+
+        let mut buttons = HtmlElement::new("div").with_class("tab-buttons");
+        let mut panes = Vec::new();
+        for (i, (info, code)) in tabs.iter().enumerate() {
+            let (lang, name) = code_tab(info).unwrap_or_default();
+            buttons.add_child(HtmlElement::new("button")
+                .with_class_opt((i == 0).then_some("selected"))
+                .with_attr("data-tab", &name)
+                .with_text(&name)
+            );
+            panes.push(HtmlElement::new("div")
+                .with_class("tab")
+                .with_class_opt((i == 0).then_some("selected"))
+                .with_attr("data-tab", &name)
+                .with_child_opt(code_meta(info).0.map(|t| HtmlElement::new("div").with_class("code-title").with_text(t)))
+                .with_child(fmt_code_pre(&lang, info, code))
+                .into()
+            );
+        }
+        Event::Html(CowStr::Boxed(Box::from(
+            HtmlElement::new("div")
+                .with_class("code-tabs")
+                .with_child(buttons)
+                .with_children(panes)
+                .gen_html()
+                .as_str()
+        )))
+    }
+
+    /// Format a code block with a title or highlighted lines in its info 
+    /// string, with the title as a caption above the code
+    fn fmt_code_block(&mut self, info: String) -> Event<'i> {
+        let code = self.take_code_block();
+        let lang = info.split_whitespace()
+            .next()
+            .filter(|l| !l.contains('=') && !l.starts_with('{'))
+            .unwrap_or_default();
+        let (title, _) = code_meta(&info);
+        Event::Html(CowStr::Boxed(Box::from(
+            HtmlElement::new("div")
+                .with_class("code-block")
+                .with_child_opt(title.map(|t| HtmlElement::new("div").with_class("code-title").with_text(t)))
+                .with_child(fmt_code_pre(lang, &info, &code))
+                .gen_html()
+                .as_str()
+        )))
+    }
+
+    /// Get the number of a footnote, the ids of its first reference and its
+    /// definition, and whether this is the first time it's used. Footnotes
+    /// are numbered in the order they're first referenced
+    fn footnote(&mut self, name: &str) -> (usize, String, String, bool) {
+        match self.footnotes.iter().position(|f| f.0 == name) {
+            Some(ix) => {
+                let (_, ref_id, def_id) = &self.footnotes[ix];
+                (ix + 1, ref_id.clone(), def_id.clone(), false)
+            }
+            None => {
+                let ref_id = heading_id(&format!("fnref-{name}"));
+                let def_id = heading_id(&format!("fn-{name}"));
+                self.footnotes.push((name.to_owned(), ref_id.clone(), def_id.clone()));
+                (self.footnotes.len(), ref_id, def_id, true)
+            }
+        }
+    }
+
+    /// Combine an image marked with `#dark` or `#light` and the variant for 
+    /// the other theme right after it into a `<picture>` that follows the 
+    /// reader's preferred color scheme
+    fn fmt_picture(&mut self, ty: LinkType, dest: &str, title: &str) -> Event<'i> {
+        let first = self.take_image_variant(ty, dest, title);
+
+        // The other variant may be separated by a space or a line break
+        let skip = match self.iter.peek() {
+            Some(Event::Text(t)) if t.trim().is_empty() => 1,
+            Some(Event::SoftBreak) => 1,
+            _ => 0,
+        };
+        let next = match &self.iter.lookahead()[skip] {
+            Some(Event::Start(Tag::Image(ty, dest, title)))
+                if image_variant(dest).is_some_and(|(_, dark)| dark != first.dark)
+                => Some((*ty, dest.to_string(), title.to_string())),
+            _ => None,
+        };
+        let second = next.map(|(ty, dest, title)| {
+            for _ in 0..=skip {
+                self.iter.next();
+            }
+            self.take_image_variant(ty, &dest, &title)
+        });
+
+        let img = |img: &ImageVariant, alt: &str| format!(
+            "<img src=\"{}\" alt=\"{}\"{}>",
+            escape_attr(&img.src),
+            escape_attr(alt),
+            (!img.title.is_empty())
+                .then(|| format!(" title=\"{}\"", escape_attr(&img.title)))
+                .unwrap_or_default(),
+        );
+        let html = match second {
+            Some(second) => {
+                let (dark, light) = if first.dark { (first, second) } else { (second, first) };
+                let alt = if light.alt.is_empty() { &dark.alt } else { &light.alt };
+                format!(
+                    "<picture><source media=\"(prefers-color-scheme: dark)\" srcset=\"{}\">{}</picture>",
+                    escape_attr(&dark.src),
+                    img(&light, alt),
+                )
+            }
+            // A lone variant is shown as a normal image
+            None => img(&first, &first.alt),
+        };
+        Event::Html(CowStr::Boxed(Box::from(html)))
+    }
+}
+
+/// Get the `title="..."` caption and `{3-5}` highlighted lines of a code 
+/// block from its info string, like `cpp title="main.cpp" {3-5,8}`
+fn code_meta(info: &str) -> (Option<String>, Option<String>) {
+    let title = info.split_once("title=").and_then(|(_, title)| match title.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next(),
+        None => title.split_whitespace().next(),
+    });
+    let lines = info.split_once('{')
+        .and_then(|(_, lines)| lines.split_once('}'))
+        .map(|(lines, _)| lines.replace(' ', ""))
+        .filter(|l| !l.is_empty() && l.chars().all(|c| c.is_ascii_digit() || c == ',' || c == '-'));
+    (title.filter(|t| !t.is_empty()).map(|t| t.to_owned()), lines)
+}
+
+/// Format a code block as a `<pre>`, with the lines from the info string 
+/// highlighted by Prism's line highlight plugin
+fn fmt_code_pre(lang: &str, info: &str, code: &str) -> HtmlElement {
+    HtmlElement::new("pre")
+        .with_attr_opt("data-line", code_meta(info).1)
+        .with_child(
+            HtmlElement::new("code")
+                .with_class(&format!("language-{lang}"))
+                .with_text(code)
+        )
+}
+
+/// Get the language and tab name of a code block from its info string, like 
+/// `cpp tab=Windows` or `sh tab="Mac OS"`
+fn code_tab(info: &str) -> Option<(String, String)> {
+    let lang = info.split_whitespace().next().filter(|l| !l.contains('='))?;
+    let (_, tab) = info.split_once("tab=")?;
+    let name = match tab.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next()?,
+        None => tab.split_whitespace().next()?,
+    };
+    (!name.is_empty()).then(|| (lang.to_owned(), name.to_owned()))
+}
+
+/// An image meant for only dark or light themes
+struct ImageVariant {
+    dark: bool,
+    src: String,
+    alt: String,
+    title: String,
+}
+
+/// Check if an image is marked as the variant for dark (`img.png#dark`) or 
+/// light (`img.png#light`) themes, returning the destination without the mark
+fn image_variant(dest: &str) -> Option<(&str, bool)> {
+    dest.strip_suffix("#dark").map(|d| (d, true))
+        .or_else(|| dest.strip_suffix("#light").map(|d| (d, false)))
+}
+
+/// Whether a link destination points to another site or is just an anchor
+fn is_external(dest: &str) -> bool {
+    dest.starts_with('#') || dest.starts_with("//") || dest.split_once(':').is_some_and(
+        |(scheme, _)| scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    )
 }
 
 impl<
@@ -127,91 +507,180 @@ impl<
         let Some(event) = self.iter.next() else {
             return None;
         };
+        // Light and dark variants of an image are combined into a <picture>
+        if let Event::Start(Tag::Image(ty, ref dest, ref title)) = event
+            && image_variant(dest).is_some()
+        {
+            return Some(self.fmt_picture(ty, dest, title));
+        }
+        // Footnotes link to their definition and the definition links back to 
+        // where it was first referenced
+        match event {
+            Event::FootnoteReference(ref name) => {
+                let (num, ref_id, def_id, first) = self.footnote(name);
+                return Some(Event::Html(CowStr::Boxed(Box::from(format!(
+                    "<sup class=\"footnote-reference\"{}><a href=\"#{}\">{num}</a></sup>",
+                    first.then(|| format!(" id=\"{}\"", escape_attr(&ref_id))).unwrap_or_default(),
+                    escape_attr(&def_id),
+                )))));
+            }
+            Event::Start(Tag::FootnoteDefinition(ref name)) => {
+                let (num, _, def_id, _) = self.footnote(name);
+                return Some(Event::Html(CowStr::Boxed(Box::from(format!(
+                    "<div class=\"footnote-definition\" id=\"{}\"><sup class=\"footnote-definition-label\">{num}</sup>",
+                    escape_attr(&def_id),
+                )))));
+            }
+            Event::End(Tag::FootnoteDefinition(ref name)) => {
+                let (_, ref_id, _, _) = self.footnote(name);
+                return Some(Event::Html(CowStr::Boxed(Box::from(format!(
+                    "<a href=\"#{}\" class=\"footnote-backref\">↩</a></div>",
+                    escape_attr(&ref_id),
+                )))));
+            }
+            Event::TaskListMarker(checked) => {
+                return Some(Event::Html(CowStr::Boxed(Box::from(format!(
+                    "<input type=\"checkbox\" class=\"task-list-marker\" disabled{}>",
+                    if checked { " checked" } else { "" },
+                )))));
+            }
+            _ => {}
+        }
+        // Diagrams are rendered client-side from their source, with the
+        // title as a caption like other code blocks
+        if let Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) = event
+            && info.split_whitespace().next() == Some("mermaid")
+        {
+            let (title, _) = code_meta(info);
+            let diagram = HtmlElement::new("pre")
+                .with_class("mermaid")
+                .with_text(self.take_code_block());
+            return Some(Event::Html(CowStr::Boxed(Box::from(
+                match title {
+                    Some(title) => HtmlElement::new("div")
+                        .with_class("code-block")
+                        .with_child(HtmlElement::new("div").with_class("code-title").with_text(title))
+                        .with_child(diagram),
+                    None => diagram,
+                }
+                .gen_html()
+                .as_str()
+            ))));
+        }
+        // Consecutive code blocks with tab names are grouped into tabs
+        if let Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) = event
+            && code_tab(info).is_some()
+        {
+            return Some(self.fmt_code_tabs(info.to_string()));
+        }
+        if let Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) = event
+            && code_meta(info) != (None, None)
+        {
+            return Some(self.fmt_code_block(info.to_string()));
+        }
         Some(match event {
-            // Don't format emojis inside code blocks lol
-            Event::Text(t) => if self.inside_code_block {
+            // Don't format emojis inside code blocks or math lol
+            Event::Text(t) => if self.inside_code_block || self.inside_math {
                 Event::Text(t)
             } else {
-                Event::Text(CowStr::Boxed(Box::from(
-                    fmt_emoji(&t).as_str()
+                let text = fmt_emoji(&t);
+                // Glossary terms get a tooltip, except in headings, links and
+                // the alt text of images where a link inside would break them
+                match (!self.inside_link && !self.inside_image && !self.inside_heading)
+                    .then(|| fmt_glossary_terms(self.builder, &text, &mut self.glossary_used))
+                    .flatten()
+                {
+                    Some(html) => Event::Html(CowStr::Boxed(Box::from(html.as_str()))),
+                    None => Event::Text(CowStr::Boxed(Box::from(text.as_str()))),
+                }
+            }
+            // Link inline code that names an entity, like `Class` or 
+            // `ns::func()`
+            Event::Code(code) => if !self.inside_link
+                && let Some(url) = resolve_code_link(self.builder, &code)
+            {
+                Event::Html(CowStr::Boxed(Box::from(
+                    HtmlElement::new("a")
+                        .with_attr_opt("data-preview", preview_key(self.builder, &url))
+                        .with_attr("href", url)
+                        .with_class("code-link")
+                        .with_child(HtmlElement::new("code").with_text(&*code))
+                        .gen_html()
+                        .as_str()
                 )))
+            } else {
+                Event::Code(code)
+            }
+            // Fix urls to point to root. Links to entities are written as 
+            // HTML since pulldown-cmark can't add the attribute for their 
+            // preview card
+            Event::Start(Tag::Link(ty, dest, title)) => {
+                self.inside_link = true;
+                let new_dest = self.fix_dest(ty, &dest, false);
+                match preview_key(self.builder, &new_dest) {
+                    Some(key) => {
+                        self.inside_preview_link = true;
+                        let title = (!title.is_empty())
+                            .then(|| format!(" title=\"{}\"", escape_attr(&title)))
+                            .unwrap_or_default();
+                        Event::Html(CowStr::Boxed(Box::from(format!(
+                            "<a href=\"{}\"{title} data-preview=\"{}\">",
+                            escape_attr(&new_dest), escape_attr(&key)
+                        ).as_str())))
+                    }
+                    None => Event::Start(Tag::Link(ty, CowStr::Boxed(Box::from(new_dest)), title)),
+                }
+            }
+            Event::End(Tag::Link(_, _, _)) if self.inside_preview_link => {
+                self.inside_link = false;
+                self.inside_preview_link = false;
+                Event::Html(CowStr::Borrowed("</a>"))
             }
             Event::Start(tag) => Event::Start(match tag {
-                // Fix urls to point to root
-                Tag::Link(ty, ref dest, ref title) | Tag::Image(ty, ref dest, ref title) => {
-                    let mut new_dest;
-                    if ty == LinkType::Inline 
-                        && dest.starts_with("/")
-                        && let Some(ref url_fixer) = self.url_fixer
-                    {
-                        let url = UrlPath::new_with_path(
-                            dest.split("/").map(|s| s.to_string()).collect()
-                        );
-                        if let Some(url) = url_fixer(url) {
-                            new_dest = url.to_string();
-                        }
-                        else {
-                            new_dest = dest.to_string();
+                // Fix image urls to point to root
+                Tag::Image(ty, ref dest, ref title) => {
+                    self.inside_image = true;
+                    // The alt text is everything up to the end of the image
+                    let mut has_alt = false;
+                    for t in self.iter.lookahead() {
+                        match t {
+                            Some(Event::End(Tag::Image(_, _, _))) | None => break,
+                            Some(Event::Text(t)) if !t.trim().is_empty() => has_alt = true,
+                            _ => {}
                         }
                     }
-                    else {
-                        new_dest = dest.to_string();
-                    }
-
-                    // make the url absolute in any case if it starts with /
-                    if dest.starts_with("/") && let Ok(dest) = UrlPath::parse(&new_dest) {
-                        new_dest = dest
-                            .to_absolute(self.builder.config.clone())
-                            .to_string();
-                    }
-
-                    // return fixed url
-                    if matches!(tag, Tag::Link(_, _, _)) {
-                        Tag::Link(
-                            ty,
-                            CowStr::Boxed(Box::from(new_dest)),
-                            title.to_owned()
-                        )
-                    }
-                    else {
-                        Tag::Image(
-                            ty,
-                            CowStr::Boxed(Box::from(new_dest)),
-                            title.to_owned()
-                        )
+                    if !has_alt {
+                        self.builder.diagnostics.warn(Category::Parse, format!(
+                            "Image '{dest}' has no alt text, describe it like ![Description]({dest})"
+                        ));
                     }
+                    let new_dest = self.fix_dest(ty, dest, true);
+                    Tag::Image(
+                        ty,
+                        CowStr::Boxed(Box::from(new_dest)),
+                        title.to_owned()
+                    )
                 }
                 // Add id to heading so they can be navigated to with url#header
                 Tag::Heading(lvl, mut frag, mut classes) => {
+                    self.inside_heading = true;
+                    let lvl = self.fix_heading_level(lvl);
                     if frag.is_none() && (lvl as usize) < 4 {
-                        let mut buf = String::new();
+                        let mut texts = Vec::new();
                         for t in self.iter.lookahead() {
                             match t {
-                                Some(Event::Text(t)) => {
-                                    if !buf.is_empty() {
-                                        buf += " ";
-                                    }
-                                    // all text must be lowercase
-                                    buf += &t.to_string()
-                                        .chars()
-                                        // no punctuation
-                                        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
-                                        .collect::<String>()
-                                        .to_lowercase();
-                                },
+                                Some(Event::Text(t)) => texts.push(t.to_string()),
                                 Some(Event::End(Tag::Heading(_, _, _))) => break,
                                 // non-text is removed
                                 _ => {},
                             }
                         }
-                        // replace spaces with single hyphens
-                        buf = buf.trim()
-                            .split_whitespace()
-                            .collect::<Vec<_>>()
-                            .join("-");
-                        
+                        let buf = heading_id(&heading_fragment(&texts));
                         frag = Some(CowStr::Boxed(Box::from(buf)));
                     }
+                    else if let Some(ref frag) = frag {
+                        reserve_id(frag);
+                    }
                     if let Some(ref meta) = self.metadata
                         && meta.style == Style::QnA
                         && (lvl as usize) < 3
@@ -227,7 +696,17 @@ impl<
                 _ => tag
             }),
             Event::End(tag) => Event::End(match tag {
+                Tag::Link(ty, dest, title) => {
+                    self.inside_link = false;
+                    Tag::Link(ty, dest, title)
+                }
+                Tag::Image(ty, dest, title) => {
+                    self.inside_image = false;
+                    Tag::Image(ty, dest, title)
+                }
                 Tag::Heading(lvl, frag, classes) => {
+                    self.inside_heading = false;
+                    let lvl = self.fix_heading_level(lvl);
                     if let Some(ref meta) = self.metadata
                         && meta.style == Style::QnA
                         && (lvl as usize) == 2
@@ -242,32 +721,389 @@ impl<
                 }
                 _ => tag
             }),
+            Event::Html(html) => {
+                if html.starts_with("<span class=\"math") {
+                    self.inside_math = true;
+                }
+                else if self.inside_math && html.starts_with("</span>") {
+                    self.inside_math = false;
+                }
+                Event::Html(html)
+            }
             _ => event,
         })
     }
 }
 
+/// Replace lines with `@snippet path tag` or `@include[lines=10-42] path` 
+/// with code blocks of the code from the project's files
+fn expand_snippets(builder: &Builder, text: &str) -> String {
+    let mut in_fence = false;
+    text.lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+            }
+            if in_fence {
+                return line.to_owned();
+            }
+            let Some((cmd, rest)) = ["@snippet", "@include"].into_iter()
+                .find_map(|cmd| Some((cmd, trimmed.strip_prefix(cmd)?)))
+            else {
+                return line.to_owned();
+            };
+            let (attrs, args) = rest.strip_prefix('[')
+                .and_then(|r| r.split_once(']'))
+                .unwrap_or(("", rest));
+            if !args.starts_with(char::is_whitespace) {
+                return line.to_owned();
+            }
+            let attrs = attrs.split(',')
+                .filter_map(|a| a.split_once('='))
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .collect::<HashMap<_, _>>();
+            let mut args = args.split_whitespace();
+            let Some(path) = args.next() else {
+                return line.to_owned();
+            };
+            let tag = args.next().filter(|_| cmd == "@snippet");
+            match load_snippet(builder, path, tag, attrs.get("lines").copied()) {
+                Ok((code, lang)) => {
+                    let lang = attrs.get("lang").map_or(lang, |l| l.to_string());
+                    // The fence has to be longer than any backticks in the code
+                    let ticks = code.split(|c: char| c != '`').map(str::len).max().unwrap_or(0);
+                    let fence = "`".repeat((ticks + 1).max(3));
+                    format!("{fence}{lang}\n{code}\n{fence}")
+                }
+                Err(e) => {
+                    builder.diagnostics.warn(Category::Build, e);
+                    line.to_owned()
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Get the blockquote class and icon for a kind of admonition, like `note` 
+/// or `warning`
+fn admonition_style(kind: &str) -> Option<(&'static str, &'static str)> {
+    match kind.to_lowercase().as_str() {
+        "note" | "info" | "tip" | "hint" | "important" => Some(("info", "ℹ")),
+        "warning" | "warn" | "caution" | "danger" | "attention" => Some(("warning", "⚠️")),
+        _ => None,
+    }
+}
+
+/// Convert admonitions written as `:::note Title` ... `:::` or GitHub-style 
+/// as `> [!NOTE]` quotes into the same blockquotes that `@note` and 
+/// `@warning` create in doc comments
+fn expand_admonitions(text: &str) -> String {
+    let mut res = Vec::new();
+    let mut lines = text.lines().peekable();
+    let mut in_fence = false;
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let (kind, title) = if in_fence {
+            res.push(line.to_owned());
+            continue;
+        } else if let Some(head) = trimmed.strip_prefix(":::") {
+            head.trim().split_once(char::is_whitespace).unwrap_or((head.trim(), ""))
+        } else if let Some(kind) = trimmed.strip_prefix("> [!").and_then(|k| k.strip_suffix(']')) {
+            (kind, "")
+        } else {
+            res.push(line.to_owned());
+            continue;
+        };
+        let Some((class, icon)) = admonition_style(kind) else {
+            res.push(line.to_owned());
+            continue;
+        };
+
+        let mut body = Vec::new();
+        if trimmed.starts_with(":::") {
+            for line in lines.by_ref() {
+                if line.trim() == ":::" {
+                    break;
+                }
+                body.push(line);
+            }
+        } else {
+            while let Some(line) = lines.next_if(|l| l.trim_start().starts_with('>')) {
+                let line = line.trim_start().trim_start_matches('>');
+                body.push(line.strip_prefix(' ').unwrap_or(line));
+            }
+        }
+
+        // Default to the kind as the title, like GitHub does
+        let title = if title.is_empty() {
+            kind[..1].to_uppercase() + &kind[1..].to_lowercase()
+        } else {
+            title.trim().to_owned()
+        };
+        res.push(format!("<blockquote class=\"{class}\">\n\n{icon} **{title}**\n"));
+        res.extend(body.into_iter().map(|l| l.to_owned()));
+        res.push("\n</blockquote>\n".into());
+    }
+    res.join("\n")
+}
+
+/// Convert definition lists written like
+/// ```md
+/// Term
+/// : Definition
+/// ```
+/// into `<dl>`s, since pulldown_cmark doesn't support them. Definitions can 
+/// continue on lines indented by four spaces or a tab, and the blank lines 
+/// around the terms and definitions make them be parsed as markdown
+fn expand_definition_lists(text: &str) -> String {
+    parse_definition_lists(text).into_iter()
+        .map(|line| match line {
+            DefinitionLine::Line(line) => line.to_owned(),
+            DefinitionLine::Term(term, definitions) => format!(
+                "<dl>\n<dt>\n\n{term}\n\n</dt>\n{}\n</dl>\n",
+                definitions.iter()
+                    .map(|def| format!("<dd>\n\n{def}\n\n</dd>"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A line of markdown, or a term of a definition list with its definitions
+pub enum DefinitionLine<'t> {
+    Line(&'t str),
+    Term(&'t str, Vec<String>),
+}
+
+/// Find the definition lists in markdown, which are terms on their own line
+/// followed by `: Definition`. Definitions can continue on lines indented by
+/// four spaces or a tab. Code blocks are left alone
+pub fn parse_definition_lists(text: &str) -> Vec<DefinitionLine<'_>> {
+    let is_definition = |l: &&str| l.starts_with(": ");
+    let is_continuation = |l: &&str| l.starts_with("    ") || l.starts_with('\t');
+    let mut res = Vec::new();
+    let mut lines = text.lines().peekable();
+    let mut in_fence = false;
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        // Fences are never terms, including the one closing a code block
+        let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        if is_fence {
+            in_fence = !in_fence;
+        }
+        if is_fence || in_fence || trimmed.is_empty() || !lines.peek().is_some_and(is_definition) {
+            res.push(DefinitionLine::Line(line));
+            continue;
+        }
+        let mut definitions = Vec::new();
+        while let Some(def) = lines.next_if(is_definition) {
+            let mut def = def[2..].to_owned();
+            while let Some(cont) = lines.next_if(is_continuation) {
+                def += "\n";
+                def += cont.trim_start();
+            }
+            definitions.push(def);
+        }
+        res.push(DefinitionLine::Term(trimmed, definitions));
+    }
+    res
+}
+
+/// Opening and closing delimiters of math, and whether it's display math
+const MATH_DELIMITERS: &[(&str, &str, bool)] = &[
+    ("$$", "$$", true),
+    ("\\f[", "\\f]", true),
+    ("\\f$", "\\f$", false),
+    ("$", "$", false),
+];
+
+/// Find where math started with a delimiter ends. Inline `$` math has to 
+/// end on the same line and can't start or end with a space or a digit 
+/// after (so prices like $5 and $10 aren't math)
+fn math_end(body: &str, open: &str, close: &str) -> Option<usize> {
+    if open != "$" {
+        return body.find(close);
+    }
+    if body.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let line = &body[..body.find('\n').unwrap_or(body.len())];
+    line.match_indices('$').map(|(i, _)| i).find(|&i| i > 0
+        && !line[..i].ends_with(char::is_whitespace)
+        && !line[..i].ends_with('\\')
+        && !line[i + 1..].starts_with(|c: char| c.is_ascii_digit())
+    )
+}
+
+/// Replace math in `$...$`, `$$...$$`, `\f$...\f$` or `\f[...\f]` with 
+/// spans for the client-side renderer. The TeX source is encoded as entities 
+/// so markdown doesn't parse things like `_` and `*` inside it. Code is left 
+/// alone, including indented code blocks
+fn escape_math(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut in_fence = false;
+    let mut line_start = true;
+    // Indented code blocks can't interrupt a paragraph, and lines indented
+    // in a list are part of the list item instead
+    let mut prev_blank = true;
+    let mut in_indented = false;
+    let mut in_list = false;
+    while let Some(c) = rest.chars().next() {
+        // Copy fenced and indented code blocks as-is
+        if line_start {
+            line_start = false;
+            let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+            let trimmed = rest.trim_start_matches([' ', '\t']);
+            let blank = line.trim().is_empty();
+            let indented = !blank && (line.starts_with("    ") || line.starts_with('\t'));
+            let fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+            if fence {
+                in_fence = !in_fence;
+            }
+            if !in_fence && !fence {
+                in_indented = (indented && !in_list && (in_indented || prev_blank))
+                    || (blank && in_indented);
+                if !blank && !indented {
+                    let head = line.trim_start();
+                    in_list = (head.starts_with(['-', '*', '+']) && head[1..].starts_with(' '))
+                        || head.split_once(['.', ')']).is_some_and(|(num, rest)|
+                            !num.is_empty() && num.chars().all(|c| c.is_ascii_digit()) && rest.starts_with(' ')
+                        );
+                }
+            }
+            prev_blank = blank;
+            if fence || in_fence || in_indented {
+                let end = rest.find('\n').map_or(rest.len(), |i| i + 1);
+                res.push_str(&rest[..end]);
+                rest = &rest[end..];
+                line_start = true;
+                continue;
+            }
+        }
+        // Copy inline code as-is
+        if c == '`' {
+            let ticks = &rest[..rest.len() - rest.trim_start_matches('`').len()];
+            let end = rest[ticks.len()..]
+                .find(ticks)
+                .map_or(ticks.len(), |i| i + ticks.len() * 2);
+            res.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("\\$") {
+            res.push_str("\\$");
+            rest = after;
+            continue;
+        }
+        if let Some((open, close, display)) = MATH_DELIMITERS.iter().find(|d| rest.starts_with(d.0))
+            && let Some(end) = math_end(&rest[open.len()..], open, close)
+        {
+            let body = &rest[open.len()..];
+            let tex = body[..end].trim().chars()
+                .map(|c| match c {
+                    c if c.is_ascii_punctuation() => format!("&#{};", c as u32),
+                    c if c.is_whitespace() => " ".into(),
+                    c => c.to_string(),
+                })
+                .collect::<String>();
+            res.push_str(&format!(
+                "<span class=\"{}\">{tex}</span>",
+                if *display { "math display" } else { "math" }
+            ));
+            rest = &body[end + close.len()..];
+            continue;
+        }
+        res.push(c);
+        rest = &rest[c.len_utf8()..];
+        line_start = c == '\n';
+    }
+    res
+}
+
+/// Create the anchor for a heading from its text parts
+fn heading_fragment(texts: &[String]) -> String {
+    texts.iter()
+        .map(|t| t.chars()
+            // no punctuation
+            .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+            .collect::<String>()
+            // all text must be lowercase
+            .to_lowercase()
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+        // replace spaces with single hyphens
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Resolve the entity an inline code span refers to, ignoring any call 
+/// parentheses so both `Class::method` and `Class::method()` work
+fn resolve_code_link(builder: &Builder, code: &str) -> Option<String> {
+    let name = code.split('(').next()?.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':') {
+        return None;
+    }
+    // Like autolinks, skip all-lowercase names so code like `get` or `data`
+    // isn't linked, unless it's qualified
+    if name.chars().all(|c| c.is_lowercase()) {
+        return None;
+    }
+    resolve_ref(builder, name)
+}
+
 #[allow(clippy::ptr_arg)]
 pub fn fmt_markdown<F: Fn(UrlPath) -> Option<UrlPath>>(
-    builder: &Builder, text: &str, url_fixer: Option<F>
+    builder: &Builder, text: &str, url_fixer: Option<F>, source_dir: Option<&Path>
+) -> Html {
+    render_markdown(builder, text, url_fixer, source_dir, None)
+}
+
+/// Format the markdown of a description from a doc comment. Its headings are 
+/// moved down by `docs.heading-shift` levels and to at least 
+/// `docs.min-heading`, so a `# Heading` doesn't end up above the page's own 
+/// headings
+pub fn fmt_comment_markdown(builder: &Builder, text: &str) -> Html {
+    let docs = &builder.config.docs;
+    render_markdown(builder, text, None::<fn(_) -> _>, None, Some((docs.heading_shift, docs.min_heading)))
+}
+
+fn render_markdown<F: Fn(UrlPath) -> Option<UrlPath>>(
+    builder: &Builder,
+    text: &str,
+    url_fixer: Option<F>,
+    source_dir: Option<&Path>,
+    heading_levels: Option<(usize, usize)>,
 ) -> Html {
     // skip metadata
     let (text, meta) = parse_markdown_metadata(text);
+    let text = escape_math(&expand_definition_lists(&expand_admonitions(&expand_snippets(builder, text))));
 
     // pulldown_cmark doesn't automatically generate header links for me, and I 
     // need those to be able to have docs links. Unfortunately the mechanism it 
     // provides for adding header links takes a &str and not an owned String, so 
     // I have to do this to have Strings with the same lifetime as the input text
 
-    let parser = MDStream::<5, F>::new(
+    let mut parser = MDStream::<5, F>::new(
         pulldown_cmark::Parser::new_ext(
-            text,
+            &text,
             pulldown_cmark::Options::all()
         ),
         url_fixer,
         builder,
         meta,
+        source_dir.map(|d| d.to_path_buf()),
     );
+    parser.heading_levels = heading_levels;
 
     let mut content = String::new();
     pulldown_cmark::html::push_html(&mut content, parser);
@@ -329,13 +1165,64 @@ pub fn extract_metadata_from_md(text: &String, default_title: Option<String>) ->
     }
 }
 
+/// Collect the level 2 and 3 headings of a document for its table of 
+/// contents. The ids are made unique the same way as when the document is 
+/// rendered
+fn markdown_toc(text: &str) -> Vec<TocItem> {
+    let (text, _) = parse_markdown_metadata(text);
+    let mut parser = pulldown_cmark::Parser::new_ext(text, pulldown_cmark::Options::all());
+
+    let mut res: Vec<TocItem> = Vec::new();
+    let mut used = HashSet::new();
+    while let Some(event) = parser.next() {
+        let Event::Start(Tag::Heading(lvl, frag, _)) = event else { continue };
+        let lvl = lvl as usize;
+        let mut texts = Vec::new();
+        let mut title = String::new();
+        for ev in parser.by_ref() {
+            match ev {
+                Event::Text(t) => {
+                    texts.push(t.to_string());
+                    title.push_str(&t);
+                }
+                Event::Code(t) => title.push_str(&t),
+                Event::End(Tag::Heading(_, _, _)) => break,
+                _ => {}
+            }
+        }
+        // Ids are counted the same way as when the headings are rendered,
+        // so custom ids are reserved at every level and generated ids are
+        // only given to headings of level 1 to 3
+        let id = match frag {
+            Some(frag) => {
+                used.insert(frag.to_string());
+                frag.to_string()
+            }
+            None if lvl < 4 => unique_in(&mut used, &heading_fragment(&texts)),
+            None => continue,
+        };
+        if !(2..4).contains(&lvl) {
+            continue;
+        }
+        let item = TocItem::new(title.trim(), &id);
+        match res.last_mut() {
+            Some(parent) if lvl == 3 => parent.children.push(item),
+            _ => res.push(item),
+        }
+    }
+    res
+}
+
 pub fn output_tutorial<'e, T: Entry<'e>>(
     entry: &T,
     builder: &Builder,
     content: &str,
+    source_dir: &Path,
     links: Html,
+    breadcrumbs: Html,
 ) -> Vec<(&'static str, Html)> {
     vec![
+        ("breadcrumbs", breadcrumbs),
         ("title", HtmlText::new(entry.name()).into()),
         (
             "content",
@@ -343,10 +1230,17 @@ pub fn output_tutorial<'e, T: Entry<'e>>(
                 builder,
                 &content,
                 Some(|url: UrlPath| {
-                    Some(url.remove_extension(".md"))
+                    // Links to other tutorials are page links
+                    if url.raw_file_name().is_some_and(|n| n.ends_with(".md")) {
+                        Some(url.remove_extension(".md").styled(builder.config.clone()))
+                    } else {
+                        Some(url)
+                    }
                 }),
+                Some(source_dir),
             ),
         ),
         ("links", links),
+        ("page_toc", fmt_toc(&markdown_toc(content), &builder.config)),
     ]
 }
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use crate::{config::Config, html::{Html, HtmlElement, HtmlText}, url::UrlPath};
+use clang::{Entity, EntityKind};
+
+use super::{
+    traits::{get_member_functions, Access, ASTEntry, BuildResult, EntityMethods, Entry, Include, NavItem, OutputEntry},
+    builder::Builder,
+    shared::{fmt_fun_decl_open, fmt_section, is_member_shown, output_entity},
+    signature::signature,
+};
+
+/// A member function along with all of its overloads, which share one page 
+/// under their class' page when `docs.member-pages` is enabled
+pub struct MemberFunction<'e> {
+    class: Entity<'e>,
+    overloads: Vec<Entity<'e>>,
+}
+
+impl<'e> MemberFunction<'e> {
+    /// Group the shown member functions of a class by name
+    pub fn for_class(class: &Entity<'e>, builder: &Builder<'e>) -> Vec<Self> {
+        let mut res: Vec<Self> = Vec::new();
+        for fun in get_member_functions(class, Access::All, Include::All) {
+            if !is_member_shown(&fun, builder) {
+                continue;
+            }
+            match res.iter_mut().find(|m| m.overloads[0].get_name() == fun.get_name()) {
+                Some(member) => member.overloads.push(fun),
+                None => res.push(Self { class: *class, overloads: vec![fun] }),
+            }
+        }
+        res
+    }
+}
+
+/// Get the URL of a member function's own page, if it has one
+pub fn member_page_url(fun: &Entity, config: Arc<Config>) -> Option<UrlPath> {
+    if !config.docs.member_pages || fun.get_kind() != EntityKind::Method {
+        return None;
+    }
+    Some(
+        fun.get_semantic_parent()?
+            .abs_docs_url(config)?
+            .join(UrlPath::part(&fun.get_name()?))
+    )
+}
+
+/// Build the pages of every member function of a class
+pub fn build_member_pages<'e>(class: &Entity<'e>, builder: &Builder<'e>) -> BuildResult {
+    let mut handles = Vec::new();
+    if builder.config.docs.member_pages {
+        for member in MemberFunction::for_class(class, builder) {
+            handles.extend(member.build(builder)?);
+        }
+    }
+    Ok(handles)
+}
+
+impl<'e> Entry<'e> for MemberFunction<'e> {
+    fn name(&self) -> String {
+        self.overloads[0]
+            .get_name()
+            .unwrap_or("`Anonymous function`".into())
+    }
+
+    fn url(&self) -> UrlPath {
+        self.class.page_url()
+            .join(UrlPath::part(&self.name()))
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        // Member functions are listed under their class in the navbar
+        NavItem::new_link(&self.name(), self.url(), Some(("code", true)), Vec::new())
+    }
+}
+
+impl<'e> ASTEntry<'e> for MemberFunction<'e> {
+    fn entity(&self) -> &Entity<'e> {
+        &self.overloads[0]
+    }
+
+    fn category(&self) -> &'static str {
+        "member function"
+    }
+}
+
+impl<'e> OutputEntry<'e> for MemberFunction<'e> {
+    fn output(&self, builder: &Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        let class_name = self.class.get_name().unwrap_or("_anon".into());
+        let mut vars = output_entity(self, builder);
+        vars.extend([
+            ("class_name", HtmlText::new(&class_name).into()),
+            (
+                "back_link",
+                HtmlElement::new("a")
+                    .with_class("back-link")
+                    .with_attr_opt("href", self.class.abs_docs_url(builder.config.clone()))
+                    .with_text(builder.config.tr("Back to {name}").replace("{name}", &class_name))
+                    .into(),
+            ),
+            (
+                "overloads",
+                fmt_section(
+                    builder.config.tr(if self.overloads.len() > 1 { "Overloads" } else { "Declaration" }),
+                    self.overloads.iter().map(|f| fmt_fun_decl_open(f, builder)).collect(),
+                ),
+            ),
+        ]);
+        (builder.config.templates.member.clone(), vars)
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        format!(
+            "Documentation for the {}::{} member function in {}",
+            self.class.get_name().unwrap_or_default(),
+            self.name(),
+            builder.config.project.name
+        )
+    }
+
+    fn signatures(&self) -> Vec<String> {
+        self.overloads.iter().filter_map(signature).collect()
+    }
+}
@@ -1,12 +1,39 @@
+pub mod anchors;
+pub mod attributes;
 #[allow(clippy::module_inception)]
 pub mod builder;
+pub mod bundle;
+pub mod changelog;
 pub mod class;
 pub mod comment;
+pub mod concept;
+pub mod coverage;
+pub mod diagnostics;
+pub mod doctest;
+pub mod feed;
 pub mod files;
 pub mod function;
+pub mod glossary;
+pub mod group;
+pub mod headers;
+pub mod includes;
+pub mod index;
+pub mod json;
+pub mod linkcheck;
 pub mod namespace;
+pub mod objc;
+pub mod page;
+pub mod permalinks;
+pub mod previews;
+pub mod print;
+pub mod references;
 pub mod shared;
+pub mod signature;
+pub mod snippet;
+pub mod stats;
 pub mod struct_;
+pub mod symbols;
 pub mod tutorial;
 pub mod traits;
 pub mod markdown;
+pub mod member;
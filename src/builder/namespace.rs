@@ -1,31 +1,41 @@
-use std::collections::HashMap;
+use std::{collections::{BTreeMap, HashMap, HashSet}, sync::Arc};
 
 use clang::{Entity, EntityKind};
 
-use crate::url::UrlPath;
+use crate::{config::Config, html::Html, url::UrlPath};
 
 use super::{
-    traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem},
+    traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem, OutputEntry},
     builder::Builder,
+    shared::{fmt_classlike_decl, fmt_enum_decl, fmt_field, fmt_fun_decl, fmt_section, fmt_section_grouped, index_letter, is_member_visible, output_entity},
+    signature::signature,
     class::Class,
+    attributes::has_hide_attribute,
+    comment::is_hidden_comment,
+    concept::{concept_name, Concept},
     function::Function,
     struct_::Struct,
 };
 
+#[derive(Clone, Copy, PartialEq)]
 pub enum CppItemKind {
     Namespace,
     Class,
     Struct,
     Function,
+    Concept,
 }
 
 impl CppItemKind {
     pub fn from<'e>(entity: &Entity<'e>) -> Option<Self> {
         match entity.get_kind() {
             EntityKind::StructDecl => Some(Self::Struct),
-            EntityKind::ClassDecl | EntityKind::ClassTemplate => Some(Self::Class),
+            EntityKind::ClassDecl
+            | EntityKind::ClassTemplate
+            | EntityKind::ClassTemplatePartialSpecialization => Some(Self::Class),
             EntityKind::FunctionDecl => Some(Self::Function),
             EntityKind::Namespace => Some(Self::Namespace),
+            EntityKind::UnexposedDecl if concept_name(entity).is_some() => Some(Self::Concept),
             _ => None,
         }
     }
@@ -36,18 +46,55 @@ impl CppItemKind {
             Self::Class => "classes",
             Self::Struct => "classes",
             Self::Function => "functions",
+            Self::Concept => "concepts",
         })
     }
 }
 
+/// Whether an entity is a partial or full specialization of a class template
+fn is_specialization(entity: &Entity) -> bool {
+    entity.get_kind() == EntityKind::ClassTemplatePartialSpecialization
+        || (entity.get_kind() != EntityKind::ClassTemplate && entity.get_template().is_some())
+}
+
+/// Load the classes and structs defined inside a class body. They get their 
+/// own pages, nested under the parent class' URL. Like members, private and
+/// hidden ones are only loaded with `docs.include-private`
+pub fn load_nested<'e>(entity: &Entity<'e>, config: Arc<Config>) -> Vec<CppItem<'e>> {
+    entity.get_children()
+        .into_iter()
+        .filter(|c|
+            c.get_name().is_some()
+                && c.is_definition()
+                && !is_specialization(c)
+                && is_member_visible(c, &config)
+        )
+        .filter_map(|c| match CppItemKind::from(&c)? {
+            CppItemKind::Class => Some(CppItem::Class(Class::new(c, config.clone()))),
+            CppItemKind::Struct => Some(CppItem::Struct(Struct::new(c, config.clone()))),
+            _ => None,
+        })
+        .collect()
+}
+
 pub enum CppItem<'e> {
     Namespace(Namespace<'e>),
     Class(Class<'e>),
     Struct(Struct<'e>),
     Function(Function<'e>),
+    Concept(Concept<'e>),
 }
 
 impl<'e> CppItem<'e> {
+    /// Types nested inside this item, if it is a class or struct
+    pub fn nested(&self) -> &[CppItem<'e>] {
+        match self {
+            CppItem::Class(cls) => cls.nested(),
+            CppItem::Struct(st) => st.nested(),
+            _ => &[],
+        }
+    }
+
     fn get(&'e self, matcher: &dyn Fn(&dyn ASTEntry<'e>) -> bool, out: &mut Vec<&'e dyn ASTEntry<'e>>) {
         match self {
             CppItem::Namespace(ns) => {
@@ -62,17 +109,28 @@ impl<'e> CppItem<'e> {
                 if matcher(cls) {
                     out.push(cls);
                 }
+                for nested in cls.nested() {
+                    nested.get(&matcher, out);
+                }
             },
             CppItem::Struct(cls) => {
                 if matcher(cls) {
                     out.push(cls);
                 }
+                for nested in cls.nested() {
+                    nested.get(&matcher, out);
+                }
             },
             CppItem::Function(fun) => {
                 if matcher(fun) {
                     out.push(fun);
                 }
             },
+            CppItem::Concept(concept) => {
+                if matcher(concept) {
+                    out.push(concept);
+                }
+            },
         }
     }
 }
@@ -84,6 +142,7 @@ impl<'e> Entry<'e> for CppItem<'e> {
             CppItem::Class(cs) => cs.name(),
             CppItem::Struct(st) => st.name(),
             CppItem::Function(st) => st.name(),
+            CppItem::Concept(cc) => cc.name(),
         }
     }
 
@@ -93,6 +152,7 @@ impl<'e> Entry<'e> for CppItem<'e> {
             CppItem::Class(cs) => cs.url(),
             CppItem::Struct(st) => st.url(),
             CppItem::Function(st) => st.url(),
+            CppItem::Concept(cc) => cc.url(),
         }
     }
 
@@ -102,6 +162,7 @@ impl<'e> Entry<'e> for CppItem<'e> {
             CppItem::Class(cs) => cs.build(builder),
             CppItem::Struct(st) => st.build(builder),
             CppItem::Function(st) => st.build(builder),
+            CppItem::Concept(cc) => cc.build(builder),
         }
     }
 
@@ -111,6 +172,7 @@ impl<'e> Entry<'e> for CppItem<'e> {
             CppItem::Class(cs) => cs.nav(),
             CppItem::Struct(st) => st.nav(),
             CppItem::Function(st) => st.nav(),
+            CppItem::Concept(cc) => cc.nav(),
         }
     }
 }
@@ -122,6 +184,7 @@ impl<'e> ASTEntry<'e> for CppItem<'e> {
             CppItem::Function(c) => c.entity(),
             CppItem::Namespace(c) => c.entity(),
             CppItem::Struct(c) => c.entity(),
+            CppItem::Concept(c) => c.entity(),
         }
     }
 
@@ -131,79 +194,269 @@ impl<'e> ASTEntry<'e> for CppItem<'e> {
             CppItem::Class(cs) => cs.category(),
             CppItem::Struct(st) => st.category(),
             CppItem::Function(st) => st.category(),
+            CppItem::Concept(cc) => cc.category(),
         }
     }
 }
 
+/// Values of the `annotate` attributes on an entity, like 
+/// `[[clang::annotate("internal")]]`
+fn annotations(entity: &Entity) -> Vec<String> {
+    entity.get_children()
+        .into_iter()
+        .filter(|c| c.get_kind() == EntityKind::AnnotateAttr)
+        .filter_map(|c| c.get_display_name())
+        .collect()
+}
+
 pub struct Namespace<'e> {
     entity: Entity<'e>,
     is_root: bool,
     pub entries: HashMap<String, CppItem<'e>>,
+    /// Names of entries hidden with `@internal` or `@hide`, so references to 
+    /// them can be ignored instead of reported as broken
+    pub hidden: HashSet<String>,
+    /// Other blocks of the same namespace that have been merged into this one
+    blocks: Vec<Entity<'e>>,
+    /// Template specializations whose primary template hasn't been found yet
+    specializations: Vec<Entity<'e>>,
+    /// Warnings about entries that have the same name as another entry
+    collisions: Vec<String>,
+    /// Namespaces with more entries than this are split into alphabetical 
+    /// groups, from `docs.group-above`
+    group_above: usize,
 }
 
 impl<'e> Namespace<'e> {
-    pub fn new(entity: Entity<'e>) -> Self {
+    pub fn new(entity: Entity<'e>, config: Arc<Config>) -> Self {
         let mut ret = Self {
             entity,
             is_root: false,
             entries: HashMap::new(),
+            hidden: HashSet::new(),
+            blocks: Vec::new(),
+            specializations: Vec::new(),
+            collisions: Vec::new(),
+            group_above: config.docs.group_above,
         };
-        ret.load_entries();
+        ret.load_entries(config);
         ret
     }
 
-    pub fn new_root(entity: Entity<'e>) -> Self {
+    pub fn new_root(entity: Entity<'e>, config: Arc<Config>) -> Self {
         let mut ret = Self {
             entity,
             is_root: true,
             entries: HashMap::new(),
+            hidden: HashSet::new(),
+            blocks: Vec::new(),
+            specializations: Vec::new(),
+            collisions: Vec::new(),
+            group_above: config.docs.group_above,
         };
-        ret.load_entries();
+        ret.load_entries(config);
         ret
     }
 
-    fn load_entries(&mut self) {
+    fn load_entries(&mut self, config: Arc<Config>) {
         for child in &self.entity.get_children() {
-            if child.is_in_system_header() || child.get_name().is_none() {
+            // Concepts don't have a name according to LibClang
+            if child.is_in_system_header()
+                || (child.get_name().is_none() && concept_name(child).is_none())
+            {
                 continue;
             }
             if let Some(kind) = CppItemKind::from(child) {
+                // Skip entities filtered out by their source's config
+                if let Some(src) = child.config_source(config.clone())
+                    && !src.includes_entity(
+                        &child.full_name().join("::"),
+                        &annotations(child),
+                        kind == CppItemKind::Namespace,
+                    )
+                {
+                    continue;
+                }
+                if !config.docs.include_private
+                    && (child.get_comment().is_some_and(|c| is_hidden_comment(&c))
+                        || has_hide_attribute(child))
+                {
+                    if let Some(name) = child.get_name().or(concept_name(child)) {
+                        self.hidden.insert(name);
+                    }
+                    continue;
+                }
                 match kind {
                     CppItemKind::Namespace => {
-                        let entry = Namespace::new(*child);
-                        // Merge existing entries of namespace
-                        if let Some(key) = self.entries.get_mut(&entry.name()) {
-                            if let CppItem::Namespace(ns) = key {
-                                ns.entries.extend(entry.entries);
-                            }
-                        }
-                        // Insert new namespace
-                        else {
-                            self.entries.insert(entry.name(), CppItem::Namespace(entry));
+                        let entry = Namespace::new(*child, config.clone());
+                        self.insert_entry(entry.name(), CppItem::Namespace(entry));
+                    }
+
+                    CppItemKind::Struct | CppItemKind::Class if is_specialization(child) => {
+                        if child.is_definition() {
+                            self.specializations.push(*child);
                         }
                     }
 
                     CppItemKind::Struct => {
                         if child.is_definition() {
-                            let entry = Struct::new(*child);
-                            self.entries.insert(entry.name(), CppItem::Struct(entry));
+                            let entry = Struct::new(*child, config.clone());
+                            self.insert_entry(entry.name(), CppItem::Struct(entry));
                         }
                     }
 
                     CppItemKind::Class => {
                         if child.is_definition() {
-                            let entry = Class::new(*child);
-                            self.entries.insert(entry.name(), CppItem::Class(entry));
+                            let entry = Class::new(*child, config.clone());
+                            self.insert_entry(entry.name(), CppItem::Class(entry));
                         }
                     }
 
                     CppItemKind::Function => {
                         let entry = Function::new(*child);
-                        self.entries.insert(entry.name(), CppItem::Function(entry));
+                        self.insert_entry(entry.name(), CppItem::Function(entry));
                     }
+
+                    CppItemKind::Concept => {
+                        let entry = Concept::new(*child);
+                        self.insert_entry(entry.name(), CppItem::Concept(entry));
+                    }
+                }
+            }
+        }
+        self.attach_specializations();
+    }
+
+    /// Add an entry. Functions with the same name are merged into one 
+    /// overload set and namespaces with the same name into one namespace, but 
+    /// any other entry with the same name as an existing one (like a class and 
+    /// a function called `stat`) is kept under a key qualified by its kind 
+    /// instead of replacing the existing entry
+    fn insert_entry(&mut self, name: String, entry: CppItem<'e>) {
+        let Some(existing) = self.entries.get_mut(&name) else {
+            self.entries.insert(name, entry);
+            return;
+        };
+        match (existing, entry) {
+            (CppItem::Function(existing), CppItem::Function(fun)) => existing.merge(fun),
+            (CppItem::Namespace(existing), CppItem::Namespace(ns)) => existing.merge(ns),
+            (existing, entry) if existing.entity() == entry.entity() => {}
+            (existing, entry) => {
+                let key = format!("{name}@{}", entry.category());
+                self.collisions.push(format!(
+                    "{} {} has the same name as {} {}, so references to '{name}' link to the {}",
+                    entry.category(),
+                    entry.entity().full_name().join("::"),
+                    existing.category(),
+                    existing.entity().full_name().join("::"),
+                    existing.category(),
+                ));
+                self.entries.insert(key, entry);
+            }
+        }
+    }
+
+    /// Warnings about entries in this namespace and its nested namespaces 
+    /// that have the same name as another entry
+    pub fn collisions(&self) -> Vec<String> {
+        let mut res = self.collisions.clone();
+        for entry in self.entries.values() {
+            if let CppItem::Namespace(ns) = entry {
+                res.extend(ns.collisions());
+            }
+        }
+        res
+    }
+
+    /// Merge the entries of another block of the same namespace into this one
+    fn merge(&mut self, other: Namespace<'e>) {
+        self.hidden.extend(other.hidden);
+        self.collisions.extend(other.collisions);
+        for (name, entry) in other.entries {
+            self.insert_entry(name, entry);
+        }
+        self.blocks.push(other.entity);
+        self.blocks.extend(other.blocks);
+        self.specializations.extend(other.specializations);
+        self.attach_specializations();
+    }
+
+    /// Entities of the given kind declared directly in any block of this 
+    /// namespace that aren't loaded as entries, like enums and constants
+    pub fn declared(&self, kind: EntityKind) -> Vec<Entity<'e>> {
+        let mut res = [self.entity].iter()
+            .chain(&self.blocks)
+            .flat_map(|b| b.get_children())
+            .filter(|c|
+                c.get_kind() == kind
+                    && c.get_name().is_some()
+                    && !c.is_in_system_header()
+                    && (kind != EntityKind::EnumDecl || c.is_definition())
+            )
+            .collect::<Vec<_>>();
+        res.sort_by_key(|e| e.get_name());
+        res.dedup_by_key(|e| e.get_name());
+        res
+    }
+
+    /// Format the entries of a kind with their names, so large sections can 
+    /// be grouped by letter
+    fn fmt_entries(&self, kind: CppItemKind, builder: &Builder) -> Vec<(String, Html)> {
+        let mut entries = self.entries.iter()
+            .filter(|p| CppItemKind::from(p.1.entity()) == Some(kind))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|p| p.0);
+        entries.into_iter()
+            .flat_map(|(_, entry)| {
+                let name = entry.name();
+                match entry {
+                    CppItem::Namespace(ns) => vec![fmt_classlike_decl(&ns.entity, "namespace", builder)],
+                    CppItem::Class(cls) => vec![fmt_classlike_decl(cls.entity(), "class", builder)],
+                    CppItem::Struct(st) => vec![fmt_classlike_decl(st.entity(), "struct", builder)],
+                    CppItem::Function(fun) => fun.overloads()
+                        .iter()
+                        .map(|f| fmt_fun_decl(f, builder))
+                        .collect(),
+                    CppItem::Concept(_) => Vec::new(),
                 }
+                .into_iter()
+                .map(move |html| (name.clone(), html))
+            })
+            .collect()
+    }
+
+    fn fmt_entries_section(&self, title: &str, kind: CppItemKind, builder: &Builder) -> Html {
+        fmt_section_grouped(builder.config.tr(title), self.fmt_entries(kind, builder), self.group_above)
+    }
+
+    /// Move template specializations onto the page of their primary template
+    fn attach_specializations(&mut self) {
+        let mut unattached = Vec::new();
+        for spec in std::mem::take(&mut self.specializations) {
+            let primary = spec.get_template().map(|t| t.get_canonical_entity());
+            let target = self.entries.values_mut().find(|e|
+                primary.is_some_and(|p| e.entity().get_canonical_entity() == p)
+            );
+            match target {
+                Some(CppItem::Class(cls)) => cls.add_specialization(spec),
+                Some(CppItem::Struct(st)) => st.add_specialization(spec),
+                _ => unattached.push(spec),
+            }
+        }
+        self.specializations = unattached;
+    }
+
+    /// Get all concepts in this namespace and its nested namespaces
+    pub fn concepts(&self) -> Vec<&Concept<'e>> {
+        let mut res = Vec::new();
+        for entry in self.entries.values() {
+            match entry {
+                CppItem::Namespace(ns) => res.extend(ns.concepts()),
+                CppItem::Concept(concept) => res.push(concept),
+                _ => {}
             }
         }
+        res
     }
 
     // so apparently if you make this a <M: Fn(&dyn ASTEntry<'e>) -> bool> 
@@ -219,7 +472,12 @@ impl<'e> Namespace<'e> {
 
 impl<'e> Entry<'e> for Namespace<'e> {
     fn build(&self, builder: &Builder<'e>) -> BuildResult {
-        let mut handles = Vec::new();
+        // The root namespace is the index page, which is built separately
+        let mut handles = if self.is_root {
+            Vec::new()
+        } else {
+            builder.create_output_for(self)?
+        };
         for entry in self.entries.values() {
             handles.extend(entry.build(builder)?);
         }
@@ -227,19 +485,32 @@ impl<'e> Entry<'e> for Namespace<'e> {
     }
 
     fn nav(&self) -> NavItem {
-        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        // Concepts are listed in their own category in the navbar
+        let mut entries = self.entries.iter()
+            .filter(|p| !matches!(p.1, CppItem::Concept(_)))
+            .collect::<Vec<_>>();
 
         // Namespaces first in sorted order, everything else after in sorted order
         entries.sort_by_key(|p| (!matches!(p.1, CppItem::Namespace(_)), p.0));
 
+        // Namespaces with lots of entries have them in a folder for each 
+        // letter so the navbar stays usable
+        let count = entries.iter().filter(|e| !matches!(e.1, CppItem::Namespace(_))).count();
+        let mut items: Vec<NavItem> = Vec::new();
+        let mut letters: BTreeMap<String, Vec<NavItem>> = BTreeMap::new();
+        for (name, entry) in entries {
+            if self.group_above == 0 || count <= self.group_above || matches!(entry, CppItem::Namespace(_)) {
+                items.push(entry.nav());
+            } else {
+                letters.entry(index_letter(name)).or_default().push(entry.nav());
+            }
+        }
+        items.extend(letters.into_iter().map(|(letter, group)| NavItem::new_dir(&letter, group, None)));
+
         if self.is_root {
-            NavItem::new_root(None, entries.iter().map(|e| e.1.nav()).collect())
+            NavItem::new_root(None, items)
         } else {
-            NavItem::new_dir(
-                &self.name(),
-                entries.iter().map(|e| e.1.nav()).collect(),
-                None,
-            )
+            NavItem::new_dir(&self.name(), items, None)
         }
     }
 
@@ -254,7 +525,7 @@ impl<'e> Entry<'e> for Namespace<'e> {
             UrlPath::new()
         }
         else {
-            self.entity.rel_docs_url().expect("Unable to get namespace URL")
+            self.entity.page_url()
         }
     }
 }
@@ -268,3 +539,44 @@ impl<'e> ASTEntry<'e> for Namespace<'e> {
         "namespace"
     }
 }
+
+impl<'e> OutputEntry<'e> for Namespace<'e> {
+    fn output(&self, builder: &Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        let mut vars = output_entity(self, builder);
+        vars.extend([
+            ("namespaces", self.fmt_entries_section("Namespaces", CppItemKind::Namespace, builder)),
+            ("classes", self.fmt_entries_section("Classes", CppItemKind::Class, builder)),
+            ("structs", self.fmt_entries_section("Structs", CppItemKind::Struct, builder)),
+            ("functions", self.fmt_entries_section("Functions", CppItemKind::Function, builder)),
+            (
+                "enums",
+                fmt_section(
+                    builder.config.tr("Enums"),
+                    self.declared(EntityKind::EnumDecl)
+                        .iter()
+                        .map(|e| fmt_enum_decl(e, builder))
+                        .collect(),
+                ),
+            ),
+            (
+                "constants",
+                fmt_section(
+                    builder.config.tr("Constants"),
+                    self.declared(EntityKind::VarDecl)
+                        .iter()
+                        .map(|e| fmt_field(e, builder))
+                        .collect(),
+                ),
+            ),
+        ]);
+        (builder.config.templates.namespace.clone(), vars)
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        self.output_description(builder)
+    }
+
+    fn signatures(&self) -> Vec<String> {
+        signature(self.entity()).into_iter().collect()
+    }
+}
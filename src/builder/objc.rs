@@ -0,0 +1,301 @@
+use std::{collections::HashSet, sync::Arc};
+
+use clang::{Entity, EntityKind};
+
+use crate::{
+    config::Config,
+    html::{Html, HtmlElement, HtmlList, HtmlText},
+    url::UrlPath,
+};
+
+use super::{
+    attributes::has_hide_attribute,
+    builder::Builder,
+    comment::is_hidden_comment,
+    shared::{fmt_entity_comment, fmt_section, fmt_type, output_entity},
+    traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem, OutputEntry},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ObjCKind {
+    Interface,
+    Protocol,
+    Category,
+}
+
+impl ObjCKind {
+    pub fn from(entity: &Entity) -> Option<Self> {
+        match entity.get_kind() {
+            EntityKind::ObjCInterfaceDecl => Some(Self::Interface),
+            EntityKind::ObjCProtocolDecl => Some(Self::Protocol),
+            EntityKind::ObjCCategoryDecl => Some(Self::Category),
+            _ => None,
+        }
+    }
+
+    fn keyword(&self) -> &'static str {
+        match self {
+            Self::Interface => "@interface",
+            Self::Protocol => "@protocol",
+            Self::Category => "@interface",
+        }
+    }
+}
+
+/// The class a category extends
+fn extended_class(category: &Entity) -> Option<String> {
+    category.get_children()
+        .into_iter()
+        .find(|c| c.get_kind() == EntityKind::ObjCClassRef)
+        .and_then(|c| c.get_name())
+}
+
+/// Get the page of an Objective-C interface, protocol or category, like
+/// `objc/NSString+Additions` for a category. Members link to an anchor on
+/// the page of what they're declared in
+pub fn objc_docs_url(entity: &Entity) -> Option<UrlPath> {
+    let kind = ObjCKind::from(entity)?;
+    let name = entity.get_name()?;
+    Some(UrlPath::part("objc").join(UrlPath::part(&match kind {
+        ObjCKind::Category => format!("{}+{name}", extended_class(entity)?),
+        _ => name,
+    })))
+}
+
+/// An `@interface`, `@protocol` or category of one. Objective-C declarations
+/// can't be in namespaces, so these are all at the top level
+pub struct ObjCItem<'e> {
+    entity: Entity<'e>,
+    kind: ObjCKind,
+}
+
+impl<'e> ObjCItem<'e> {
+    /// Load the Objective-C declarations in the project's headers if
+    /// `analysis.objc` is enabled
+    pub fn from_config(config: Arc<Config>, root: &Entity<'e>) -> Vec<Self> {
+        if !config.analysis.objc {
+            return Vec::new();
+        }
+        let mut seen = HashSet::new();
+        let mut res = root.get_children()
+            .into_iter()
+            .filter(|c|
+                c.is_definition()
+                    && c.config_source(config.clone()).is_some()
+                    && objc_docs_url(c).is_some()
+            )
+            .filter(|c| config.docs.include_private || !(
+                c.get_comment().is_some_and(|c| is_hidden_comment(&c)) || has_hide_attribute(c)
+            ))
+            .filter_map(|c| Some(Self { entity: c, kind: ObjCKind::from(&c)? }))
+            .filter(|item| seen.insert((item.category(), item.name())))
+            .collect::<Vec<_>>();
+        res.sort_by_key(|item| item.name());
+        res
+    }
+
+    fn members(&self, kind: EntityKind) -> Vec<Entity<'e>> {
+        self.entity.get_children()
+            .into_iter()
+            .filter(|c| c.get_kind() == kind)
+            .collect()
+    }
+
+    /// The declaration line, like `@interface Foo : NSObject <Bar, Baz>`
+    fn fmt_declaration(&self, builder: &Builder) -> Html {
+        let link = |c: &Entity| -> Html {
+            HtmlElement::new("a")
+                .with_class("name")
+                .with_attr_opt("href", c.get_reference().and_then(|r| r.abs_docs_url(builder.config.clone())))
+                .with_text(c.get_name().unwrap_or_default())
+                .into()
+        };
+        let children = self.entity.get_children();
+        let superclass = children.iter().find(|c| c.get_kind() == EntityKind::ObjCSuperClassRef);
+        let protocols = children.iter()
+            .filter(|c| c.get_kind() == EntityKind::ObjCProtocolRef)
+            .map(link)
+            .collect::<Vec<_>>();
+
+        HtmlElement::new("div")
+            .with_classes(&["entity", "class"])
+            .with_child(Html::span(&["keyword", "space-after"], self.kind.keyword()))
+            .with_child(Html::span(&["name"], &match self.kind {
+                ObjCKind::Category => extended_class(&self.entity).unwrap_or_default(),
+                _ => self.entity.get_name().unwrap_or_default(),
+            }))
+            .with_child_opt((self.kind == ObjCKind::Category).then(||
+                Html::span(&["space-before"], &format!("({})", self.entity.get_name().unwrap_or_default()))
+            ))
+            .with_child_opt(superclass.map(|s| HtmlList::new(vec![
+                Html::span(&["space-before", "space-after"], ":"),
+                link(s),
+            ])))
+            .with_child_opt((!protocols.is_empty()).then(|| HtmlList::new(
+                [Html::span(&["space-before"], "<")].into_iter()
+                    .chain(protocols.into_iter().intersperse_with(|| Html::span(&["space-after"], ",")))
+                    .chain([HtmlText::new(">").into()])
+                    .collect()
+            )))
+            .into()
+    }
+}
+
+/// The attributes of a property as written, like `nonatomic, readonly`
+fn property_attributes(property: &Entity) -> Option<String> {
+    let tokens = property.get_range()?
+        .tokenize()
+        .into_iter()
+        .map(|t| t.get_spelling())
+        .skip_while(|t| t != "property" && t != "@property")
+        .skip(1)
+        .collect::<Vec<_>>();
+    if tokens.first()? != "(" {
+        return None;
+    }
+    let end = tokens.iter().position(|t| t == ")")?;
+    Some(tokens[1..end].join(" ").replace(" ,", ",").replace(" = ", "="))
+}
+
+fn fmt_property(property: &Entity, builder: &Builder) -> Html {
+    HtmlElement::new("details")
+        .with_class("entity-desc")
+        .with_attr_opt("id", property.get_name())
+        .with_child(
+            HtmlElement::new("summary")
+                .with_classes(&["entity", "var"])
+                .with_child(Html::span(&["keyword", "space-after"], "@property"))
+                .with_child_opt(property_attributes(property).map(|a|
+                    Html::span(&["keyword", "space-after"], &format!("({a})"))
+                ))
+                .with_child_opt(property.get_type().map(|t| fmt_type(&t, builder)))
+                .with_child(Html::span(&["name", "space-before"], &property.get_name().unwrap_or_default()))
+                .with_child(HtmlText::new(";")),
+        )
+        .with_child(HtmlElement::new("div").with_child(fmt_entity_comment(property, builder, true)))
+        .into()
+}
+
+/// Format a method like `- (void)insert:(id)object atIndex:(NSUInteger)index`,
+/// with each part of the selector before the parameter it names
+fn fmt_method(method: &Entity, builder: &Builder) -> Html {
+    let selector = method.get_name().unwrap_or_default();
+    let args = method.get_arguments().unwrap_or_default();
+    let mut parts = Vec::new();
+    if args.is_empty() {
+        parts.push(Html::span(&["name"], &selector));
+    } else {
+        for (i, (part, arg)) in selector.split(':').zip(&args).enumerate() {
+            parts.push(HtmlElement::new("span")
+                .with_class_opt((i > 0).then_some("space-before"))
+                .with_child(Html::span(&["name"], &format!("{part}:")))
+                .with_child(HtmlText::new("("))
+                .with_child_opt(arg.get_type().map(|t| fmt_type(&t, builder)))
+                .with_child(HtmlText::new(")"))
+                .with_text(arg.get_name().unwrap_or_default())
+                .into()
+            );
+        }
+    }
+    let sign = if method.get_kind() == EntityKind::ObjCClassMethodDecl { "+" } else { "-" };
+    HtmlElement::new("details")
+        .with_class("entity-desc")
+        .with_attr("id", &selector)
+        .with_child(
+            HtmlElement::new("summary")
+                .with_classes(&["entity", "fun"])
+                .with_child(Html::span(&["keyword", "space-after"], sign))
+                .with_child(HtmlText::new("("))
+                .with_child_opt(method.get_result_type().map(|t| fmt_type(&t, builder)))
+                .with_child(Html::span(&["space-after"], ")"))
+                .with_children(parts)
+                .with_child(HtmlText::new(";")),
+        )
+        .with_child(HtmlElement::new("div").with_child(fmt_entity_comment(method, builder, true)))
+        .into()
+}
+
+impl<'e> Entry<'e> for ObjCItem<'e> {
+    fn name(&self) -> String {
+        match self.kind {
+            ObjCKind::Category => format!(
+                "{} ({})",
+                extended_class(&self.entity).unwrap_or_default(),
+                self.entity.get_name().unwrap_or_default()
+            ),
+            _ => self.entity.get_name().unwrap_or_default(),
+        }
+    }
+
+    fn url(&self) -> UrlPath {
+        self.entity.page_url()
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        let icon = match self.kind {
+            ObjCKind::Interface => "box",
+            ObjCKind::Protocol => "link",
+            ObjCKind::Category => "plus-square",
+        };
+        NavItem::new_link(&self.name(), self.url(), Some((icon, false)), Vec::new())
+    }
+}
+
+impl<'e> ASTEntry<'e> for ObjCItem<'e> {
+    fn entity(&self) -> &Entity<'e> {
+        &self.entity
+    }
+
+    fn category(&self) -> &'static str {
+        match self.kind {
+            ObjCKind::Interface => "interface",
+            ObjCKind::Protocol => "protocol",
+            ObjCKind::Category => "category",
+        }
+    }
+}
+
+impl<'e> OutputEntry<'e> for ObjCItem<'e> {
+    fn output(&self, builder: &'e Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        let config = &builder.config;
+        let mut vars = output_entity(self, builder);
+        vars.extend([
+            ("kind", HtmlText::new(config.tr(match self.kind {
+                ObjCKind::Interface => "Interface",
+                ObjCKind::Protocol => "Protocol",
+                ObjCKind::Category => "Category",
+            })).into()),
+            ("declaration", self.fmt_declaration(builder)),
+            (
+                "properties",
+                fmt_section(
+                    config.tr("Properties"),
+                    self.members(EntityKind::ObjCPropertyDecl).iter().map(|p| fmt_property(p, builder)).collect(),
+                ),
+            ),
+            (
+                "class_methods",
+                fmt_section(
+                    config.tr("Class methods"),
+                    self.members(EntityKind::ObjCClassMethodDecl).iter().map(|m| fmt_method(m, builder)).collect(),
+                ),
+            ),
+            (
+                "instance_methods",
+                fmt_section(
+                    config.tr("Instance methods"),
+                    self.members(EntityKind::ObjCInstanceMethodDecl).iter().map(|m| fmt_method(m, builder)).collect(),
+                ),
+            ),
+        ]);
+        (config.templates.objc_item.clone(), vars)
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        self.output_description(builder)
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    config::{Config, PageDef},
+    html::Html,
+    url::UrlPath,
+};
+use std::{fs, path::PathBuf, sync::Arc};
+
+use super::{
+    traits::{BuildResult, Entry, NavItem, OutputEntry},
+    builder::Builder,
+    markdown::{extract_metadata_from_md, output_tutorial, Metadata},
+};
+
+/// A standalone markdown page (changelog, FAQ, etc.) that lives outside of
+/// the tutorials folder
+pub struct Page {
+    path: PathBuf,
+    url: UrlPath,
+    metadata: Metadata,
+    unparsed_content: String,
+}
+
+impl Page {
+    pub fn new(config: Arc<Config>, def: &PageDef) -> Result<Self, String> {
+        let unparsed_content = fs::read_to_string(config.input_dir.join(&def.file))
+            .map_err(|e| format!("Unable to read page {}: {e}", def.file.to_string_lossy()))?;
+
+        let stem = def.file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Ok(Self {
+            metadata: extract_metadata_from_md(&unparsed_content, Some(stem.clone()))
+                .unwrap_or(Metadata::new_with_title(stem.clone())),
+            url: def.url.clone().unwrap_or(UrlPath::part(&stem.to_lowercase())),
+            path: def.file.clone(),
+            unparsed_content,
+        })
+    }
+
+    pub fn from_config(config: Arc<Config>) -> Result<Vec<Self>, String> {
+        config.pages
+            .iter()
+            .map(|def| Self::new(config.clone(), def))
+            .collect()
+    }
+}
+
+impl<'e> Entry<'e> for Page {
+    fn name(&self) -> String {
+        self.metadata.title.clone()
+            .unwrap_or(self.path.to_string_lossy().to_string())
+    }
+
+    fn url(&self) -> UrlPath {
+        self.url.clone()
+    }
+
+    fn build(&self, builder: &Builder<'e>) -> BuildResult {
+        builder.create_output_for(self)
+    }
+
+    fn nav(&self) -> NavItem {
+        NavItem::new_link(
+            &self.name(),
+            self.url(),
+            Some(
+                self.metadata.icon.as_ref()
+                    .map(|i| (i.as_str(), false))
+                    .unwrap_or(("file-text", false))
+            ),
+            Vec::new(),
+        )
+    }
+}
+
+impl<'e> OutputEntry<'e> for Page {
+    fn output(&self, builder: &Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
+        (
+            builder.config.templates.tutorial.clone(),
+            output_tutorial(
+                self,
+                builder,
+                &self.unparsed_content,
+                builder.config.input_dir.join(&self.path).parent().unwrap_or(builder.config.input_dir.as_path()),
+                Html::Raw(String::new()),
+                Html::Raw(String::new()),
+            )
+        )
+    }
+
+    fn description(&self, builder: &'e Builder<'e>) -> String {
+        self.metadata.description.clone().unwrap_or(format!(
+            "{} - {}",
+            self.name(),
+            builder.config.project.name
+        ))
+    }
+}
@@ -0,0 +1,115 @@
+use std::{collections::BTreeMap, fs};
+
+use clang::Entity;
+
+use crate::{
+    html::{Html, HtmlElement},
+    log::Category,
+    url::UrlPath,
+};
+
+use super::{builder::Builder, shared::stable_hash, symbols::all_symbols};
+
+/// Full name, kind and URL of an entity in `docs.permalink-history`
+type Permalink = (String, String, String);
+
+/// Get the permanent id of an entity, which is a hash of its USR so it 
+/// stays the same between builds
+pub fn permalink_id(entity: &Entity) -> Option<String> {
+    Some(format!("{:016x}", stable_hash(entity.get_usr()?.0.as_bytes())))
+}
+
+/// Get the URL that redirects to an entity's page wherever it ends up
+pub fn permalink_url(entity: &Entity, builder: &Builder) -> Option<String> {
+    Some(format!(
+        "{}/id/#{}",
+        builder.config.output_url.as_ref().unwrap_or(&UrlPath::new()),
+        permalink_id(entity)?
+    ))
+}
+
+pub fn fmt_permalink(entity: &Entity, builder: &Builder) -> Html {
+    match permalink_url(entity, builder) {
+        Some(url) => HtmlElement::new("a")
+            .with_class("permalink")
+            .with_attr("href", url)
+            .with_attr("title", builder.config.tr("Permanent link"))
+            .with_child(Html::feather("link"))
+            .into(),
+        None => Html::Raw(String::new()),
+    }
+}
+
+fn last_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// Point the ids of entities that no longer exist at an entity with the same 
+/// name and kind, if there's exactly one. Since USRs include the namespace, 
+/// this keeps links to entities that were moved to another namespace working
+fn carry_over(history: BTreeMap<String, Permalink>, current: &mut BTreeMap<String, Permalink>) {
+    for (id, old) in history {
+        if current.contains_key(&id) {
+            continue;
+        }
+        let mut matches = current.values()
+            .filter(|(name, kind, _)| *kind == old.1 && last_name(name) == last_name(&old.0));
+        let moved = match (matches.next(), matches.next()) {
+            (Some(new), None) => new.clone(),
+            _ => old,
+        };
+        current.insert(id, moved);
+    }
+}
+
+/// Write the ids of every entity and the page that redirects them, plus the 
+/// history of ids to `docs.permalink-history` if it's set
+pub fn build_permalinks(builder: &Builder) -> Result<(), String> {
+    let config = &builder.config;
+    let mut links = BTreeMap::new();
+    for (entity, (name, url, kind)) in all_symbols(builder) {
+        if let Some(id) = permalink_id(&entity) {
+            links.insert(id, (name, kind.to_owned(), url));
+        }
+    }
+
+    if let Some(ref path) = config.docs.permalink_history {
+        let path = config.input_dir.join(path);
+        if path.exists() {
+            let history = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()));
+            match history {
+                Ok(history) => carry_over(history, &mut links),
+                Err(e) => builder.diagnostics.warn(Category::Config, format!(
+                    "Unable to read permalink history {}: {e}", path.to_string_lossy()
+                )),
+            }
+        }
+        fs::write(
+            &path,
+            serde_json::to_string_pretty(&links).map_err(|e| format!("Unable to serialize permalinks: {e}"))?,
+        ).map_err(|e| format!("Unable to save permalink history: {e}"))?;
+    }
+
+    let urls = links.into_iter().map(|(id, (_, _, url))| (id, url)).collect::<BTreeMap<_, _>>();
+    fs::write(
+        config.output_dir.join("permalinks.json"),
+        serde_json::to_string(&urls).map_err(|e| format!("Unable to serialize permalinks: {e}"))?,
+    ).map_err(|e| format!("Unable to save permalinks.json: {e}"))?;
+
+    fs::create_dir_all(config.output_dir.join("id"))
+        .map_err(|e| format!("Unable to create permalink page: {e}"))?;
+    fs::write(
+        config.output_dir.join("id").join("index.html"),
+        format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><script>\
+            fetch(\"{root}/permalinks.json\")\
+                .then(res => res.json())\
+                .then(links => location.replace(links[location.hash.slice(1)] ?? \"{root}/\"))\
+                .catch(() => location.replace(\"{root}/\"));\
+            </script></head></html>",
+            root = config.output_url.as_ref().unwrap_or(&UrlPath::new()),
+        ),
+    ).map_err(|e| format!("Unable to save permalink page: {e}"))
+}
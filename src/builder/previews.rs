@@ -0,0 +1,83 @@
+use std::{collections::HashMap, fs};
+
+use serde_json::{json, Map};
+
+use crate::{html::GenHtml, url::UrlPath};
+
+use super::{
+    builder::Builder,
+    comment::{fmt_brief, JSDocComment},
+    signature::signature,
+    symbols::all_symbols,
+};
+
+/// The key of the preview of what a link points to, which is its URL without
+/// the output root so it stays the same when links are made relative
+fn key_for(builder: &Builder, url: &str) -> String {
+    let root = builder.config.output_url.as_ref().unwrap_or(&UrlPath::new()).to_string();
+    url.strip_prefix(&root).unwrap_or(url).trim_start_matches('/').to_owned()
+}
+
+/// What the preview card of an entity shows
+pub struct Preview {
+    kind: &'static str,
+    signature: String,
+    /// The brief description as markdown. It's only formatted when the
+    /// previews are saved, since formatting it looks up the previews of the
+    /// entities it links to
+    brief: Option<String>,
+    /// Where the comment is, for warnings about the brief description
+    location: Option<String>,
+}
+
+/// The previews of every entity that gets a card by their key. Each doc
+/// comment is parsed once here and reused for saving `previews.json`
+pub fn collect_previews(builder: &Builder) -> HashMap<String, Preview> {
+    let mut previews = HashMap::new();
+    if !builder.config.output.link_previews {
+        return previews;
+    }
+    for (entity, (name, url, kind)) in all_symbols(builder) {
+        // Overloads share their URL, so the first one is shown
+        previews.entry(key_for(builder, &url)).or_insert_with(|| {
+            let comment = JSDocComment::parse_for(&entity, builder);
+            Preview {
+                kind,
+                signature: signature(&entity).unwrap_or(name),
+                brief: comment.as_ref().and_then(|c| c.brief().map(str::to_owned)),
+                location: comment.as_ref().and_then(|c| c.location()),
+            }
+        });
+    }
+    previews
+}
+
+/// Get the value for the `data-preview` attribute of a link to `url`, if it
+/// points to an entity with a preview card
+pub fn preview_key(builder: &Builder, url: &str) -> Option<String> {
+    let key = key_for(builder, url);
+    builder.previews().contains_key(&key).then_some(key)
+}
+
+/// The preview cards of every entity by their key, with the kind, the full
+/// signature and the brief description as HTML
+pub fn previews_json(builder: &Builder) -> Result<String, String> {
+    let previews = builder.previews()
+        .iter()
+        .map(|(key, preview)| (key.clone(), json!({
+            "kind": preview.kind,
+            "signature": preview.signature,
+            "brief": preview.brief.as_ref().map(|b| fmt_brief(builder, b, preview.location.as_deref()).gen_html()),
+        })))
+        .collect::<Map<_, _>>();
+    serde_json::to_string(&previews)
+        .map_err(|e| format!("Unable to serialize previews: {e}"))
+}
+
+pub fn build_previews(builder: &Builder) -> Result<(), String> {
+    if !builder.config.output.link_previews {
+        return Ok(());
+    }
+    fs::write(builder.config.output_dir.join("previews.json"), previews_json(builder)?)
+        .map_err(|e| format!("Unable to save previews.json: {e}"))
+}
@@ -0,0 +1,105 @@
+use regex::{Captures, Regex};
+use std::{collections::HashSet, fs, process::Command};
+
+use crate::{
+    html::{escape::escape_attr, process::minify_html},
+    url::UrlPath,
+};
+
+use super::{
+    builder::Builder,
+    bundle::{inline_head, inline_icons, read_page},
+};
+
+/// Pages to export in navbar order. Pages that aren't in the navbar are
+/// placed at the end
+fn export_order(builder: &Builder, pages: &[UrlPath]) -> Vec<UrlPath> {
+    let selected = |url: &UrlPath| builder.config.export.as_ref()
+        .and_then(|e| e.pages.as_ref())
+        .map(|prefixes| prefixes.iter().any(|p|
+            UrlPath::parse(p).is_ok_and(|p| url.starts_with(p))
+        ))
+        .unwrap_or(true);
+
+    let order = builder.nav_order();
+    let built = pages.iter().collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    order.iter()
+        .filter(|url| built.contains(url))
+        .chain(pages)
+        .filter(|url| selected(url) && seen.insert(*url))
+        .cloned()
+        .collect()
+}
+
+/// Prefix the ids in the content of a page and the links and references to
+/// them within the page, since the pages are combined into one document
+/// where the same ids would otherwise appear several times
+fn prefix_ids(content: &str, url: &UrlPath) -> String {
+    let prefix = format!(
+        "page-{}",
+        url.to_string().trim_matches('/').replace(|c: char| !c.is_alphanumeric(), "-")
+    );
+    let re = Regex::new(
+        r##"(\s)(id|for|aria-describedby|aria-labelledby|aria-controls)="([^"]+)"|(\s)href="#([^"]+)""##
+    ).unwrap();
+    re.replace_all(content, |c: &Captures| match c.get(5) {
+        Some(id) => format!("{}href=\"#{prefix}-{}\"", &c[4], id.as_str()),
+        None => format!("{}{}=\"{prefix}-{}\"", &c[1], &c[2], &c[3]),
+    }).into_owned()
+}
+
+/// Render the selected pages into a single `print.html` with each page
+/// starting on a new sheet, and convert it to `print.pdf` if a PDF command
+/// is configured
+pub fn build_print(builder: &Builder, pages: &[UrlPath]) -> Result<(), String> {
+    let config = builder.config.clone();
+    let (head, _) = inline_head(builder, &format!("{} Docs", config.project.name))
+        .map_err(|e| format!("Unable to build print.html: {e}"))?;
+
+    let mut sections = String::new();
+    for url in export_order(builder, pages) {
+        let (content, _) = read_page(builder, &url)
+            .map_err(|e| format!("Unable to build print.html: {e}"))?;
+        sections += &format!(
+            "<section class=\"print-page\" data-url=\"{}\">{}</section>",
+            escape_attr(&url.to_string()),
+            prefix_ids(&content, &url),
+        );
+    }
+
+    // The navigation script expects the navbar, so only run the icon
+    // replacement that the page content needs
+    let page = format!(
+        "<!DOCTYPE html><html lang=\"en\"><head>{head}</head>\
+        <body class=\"flash-theme-{} line-numbers flash-print\"><main>{sections}</main>\
+        <script>feather.replace();</script></body></html>",
+        escape_attr(&config.theme.default),
+    );
+    let path = config.output_dir.join("print.html");
+    fs::write(&path, minify_html(&config, inline_icons(builder, page))?)
+        .map_err(|e| format!("Unable to save print.html: {e}"))?;
+
+    if let Some(cmd) = config.export.as_ref().and_then(|e| e.pdf_command.as_ref()) {
+        let pdf = config.output_dir.join("print.pdf");
+        let args = shlex::split(cmd)
+            .ok_or(format!("Unable to parse PDF command `{cmd}`"))?
+            .into_iter()
+            .map(|arg| arg
+                .replace("{input}", &path.to_string_lossy())
+                .replace("{output}", &pdf.to_string_lossy())
+            )
+            .collect::<Vec<_>>();
+        let exit = Command::new(args.first().ok_or("PDF command is empty")?)
+            .args(&args[1..])
+            .spawn()
+            .map_err(|e| format!("Unable to execute PDF command `{cmd}`: {e}"))?
+            .wait()
+            .map_err(|e| format!("Unable to wait for PDF command `{cmd}`: {e}"))?;
+        if !exit.success() {
+            return Err(format!("PDF command `{cmd}` failed"));
+        }
+    }
+
+    Ok(())
+}
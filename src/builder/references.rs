@@ -0,0 +1,105 @@
+use std::collections::{HashMap, HashSet};
+
+use clang::{Entity, EntityKind};
+
+use crate::html::{Html, HtmlElement};
+
+use super::{builder::Builder, shared::fmt_section, symbols::all_symbols, traits::EntityMethods};
+
+/// Name and URL of an entity that references another
+type Referrer = (String, String);
+
+/// Which documented entities refer to each entity in their signatures or 
+/// bodies, by the full name of the referenced entity so all overloads of a 
+/// function share their references
+#[derive(Default)]
+pub struct References {
+    by: HashMap<String, Vec<Referrer>>,
+}
+
+/// Whether a child of a class is documented as its own symbol, so its 
+/// references shouldn't be counted for the class too
+fn is_own_symbol(entity: &Entity) -> bool {
+    matches!(
+        entity.get_kind(),
+        EntityKind::Method
+            | EntityKind::Constructor
+            | EntityKind::Destructor
+            | EntityKind::ConversionFunction
+            | EntityKind::FunctionTemplate
+            | EntityKind::ClassDecl
+            | EntityKind::StructDecl
+            | EntityKind::ClassTemplate
+    )
+}
+
+/// Collect the full names of everything an entity refers to in its types, 
+/// base classes and (for inline functions) body
+fn collect(entity: &Entity, top: bool, out: &mut HashSet<String>) {
+    for child in entity.get_children() {
+        if top && matches!(entity.get_kind(), EntityKind::ClassDecl | EntityKind::StructDecl | EntityKind::ClassTemplate)
+            && is_own_symbol(&child)
+        {
+            continue;
+        }
+        if matches!(
+            child.get_kind(),
+            EntityKind::TypeRef
+                | EntityKind::TemplateRef
+                | EntityKind::DeclRefExpr
+                | EntityKind::MemberRefExpr
+                | EntityKind::OverloadedDeclRef
+        ) && let Some(target) = child.get_reference()
+        {
+            out.insert(target.get_canonical_entity().full_name().join("::"));
+        }
+        collect(&child, false, out);
+    }
+}
+
+impl References {
+    /// Go through every documented entity and record what it refers to
+    pub fn build(builder: &Builder) -> Self {
+        let mut by: HashMap<String, Vec<Referrer>> = HashMap::new();
+        for (entity, (name, url, _)) in all_symbols(builder) {
+            let mut targets = HashSet::new();
+            collect(&entity, true, &mut targets);
+            targets.remove(&name);
+            for target in targets {
+                by.entry(target).or_default().push((name.clone(), url.clone()));
+            }
+        }
+        for referrers in by.values_mut() {
+            referrers.sort();
+            referrers.dedup();
+        }
+        Self { by }
+    }
+
+    pub fn referenced_by(&self, entity: &Entity) -> &[Referrer] {
+        self.by.get(&entity.full_name().join("::")).map(|r| r.as_slice()).unwrap_or_default()
+    }
+}
+
+/// Format the "Referenced by" section of an entity's page, which is empty if 
+/// nothing refers to it or `docs.referenced-by` is disabled
+pub fn fmt_referenced_by(entity: &Entity, builder: &Builder) -> Html {
+    let referrers = if builder.config.docs.referenced_by {
+        builder.references().referenced_by(entity)
+    } else {
+        &[]
+    };
+    fmt_section(
+        builder.config.tr("Referenced by"),
+        referrers.iter()
+            .map(|(name, url)| HtmlElement::new("div")
+                .with_class("index-item")
+                .with_child(HtmlElement::new("a")
+                    .with_attr("href", url)
+                    .with_child(HtmlElement::new("code").with_text(name))
+                )
+                .into()
+            )
+            .collect(),
+    )
+}
@@ -1,15 +1,28 @@
+use super::anchors::heading_id;
+use super::attributes::{attribute_badges, cpp_attributes, has_hide_attribute};
 use super::builder::Builder;
 use super::traits::{ASTEntry, EntityMethods, Entry, get_member_functions, Include, Access};
-use super::comment::JSDocComment;
-use super::namespace::CppItem;
+use super::comment::{has_hidden_comment, JSDocComment};
+use super::member::member_page_url;
+use super::permalinks::fmt_permalink;
+use super::previews::preview_key;
+use super::references::fmt_referenced_by;
+use super::signature::{function_qualifiers, function_specifiers, is_final_class, signature, virt_specifiers};
+use super::namespace::{CppItem, CppItemKind, Namespace};
 use crate::annotation::Annotations;
 use crate::config::Config;
+use crate::log::Category;
 use crate::html::{Html, HtmlElement, HtmlList, HtmlText};
-use clang::{Accessibility, Entity, EntityKind, Type, TypeKind};
+use crate::url::UrlPath;
+use clang::{
+    token::{Token, TokenKind},
+    Accessibility, Entity, EntityKind, Type, TypeKind,
+};
 use multipeek::{IteratorExt, MultiPeek};
 use pulldown_cmark::CowStr;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::Chars;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 
 trait Surround<T> {
     fn surround(self, start: T, end: T) -> Self;
@@ -42,14 +55,50 @@ impl<T, Sep: Fn() -> T> InsertBetween<T, Sep> for Vec<T> {
     }
 }
 
-fn fmt_type(entity: &Type, builder: &Builder) -> Html {
+/// Get the docs of the declaration of a type. Instantiations of templates 
+/// link to the template
+fn type_docs_url(decl: &Entity, builder: &Builder) -> Option<UrlPath> {
+    decl.abs_docs_url(builder.config.clone())
+        .or_else(|| decl.get_template()?.abs_docs_url(builder.config.clone()))
+}
+
+/// Whether a type or any of its template arguments is documented
+fn mentions_documented(ty: &Type, builder: &Builder) -> bool {
+    let base = ty.get_pointee_type().unwrap_or(ty.to_owned());
+    base.get_declaration().is_some_and(|decl| type_docs_url(&decl, builder).is_some())
+        || base.get_template_argument_types().unwrap_or_default()
+            .iter()
+            .flatten()
+            .any(|t| mentions_documented(t, builder))
+}
+
+pub fn fmt_type(entity: &Type, builder: &Builder) -> Html {
     let base = entity.get_pointee_type().unwrap_or(entity.to_owned());
-    let decl = base.get_declaration();
-    let link = decl.and_then(|decl| decl.abs_docs_url(builder.config.clone()));
+    // Sugar like elaborated types doesn't always have a declaration, but 
+    // what it stands for does
+    let decl = base.get_declaration().or_else(|| base.get_canonical_type().get_declaration());
+    let external = decl.and_then(|decl| decl.external_docs_url(builder.config.clone()));
+    let link = if external.is_some() {
+        None
+    } else {
+        decl.and_then(|decl| type_docs_url(&decl, builder))
+    };
     let kind = decl
         .map(|decl| decl.get_kind())
         .unwrap_or(EntityKind::UnexposedDecl);
 
+    // Aliases that aren't documented themselves are shown as what they 
+    // alias if that has documented types in it, so those can be linked
+    let canonical = entity.get_canonical_type();
+    if matches!(kind, EntityKind::TypeAliasDecl | EntityKind::TypedefDecl)
+        && link.is_none()
+        && external.is_none()
+        && canonical != *entity
+        && mentions_documented(&canonical, builder)
+    {
+        return fmt_type(&canonical, builder);
+    }
+
     let name: Html = decl
         .map(|decl| {
             HtmlList::new(
@@ -108,34 +157,33 @@ fn fmt_type(entity: &Type, builder: &Builder) -> Html {
         .with_class("entity")
         .with_class("type")
         .with_class_opt(entity.is_pod().then_some("keyword"))
-        .with_class_opt(link.is_none().then_some("disabled"))
-        .with_attr_opt("href", link.clone())
-        .with_attr_opt(
-            "onclick",
-            link.map(|link| format!("return navigate('{link}')")),
-        )
+        .with_class_opt((link.is_none() && external.is_none()).then_some("disabled"))
+        .with_attr_opt("href", external.or(link.as_ref().map(|l| l.to_string())))
+        .with_attr_opt("data-preview", link.as_ref().and_then(|l| preview_key(builder, &l.to_string())))
         .with_child(name)
         .with_child_opt(match kind {
             EntityKind::TypeAliasDecl | EntityKind::TypedefDecl => None,
-            _ => base.get_template_argument_types().map(|types| {
-                HtmlList::new(
-                    types
-                        .iter()
-                        .map(|t| {
-                            t.map(|t| fmt_type(&t, builder))
-                                .unwrap_or(HtmlText::new("_unk").into())
-                        })
-                        .collect::<Vec<_>>()
-                        .insert_between(|| {
-                            HtmlElement::new("span")
-                                .with_class("comma")
-                                .with_class("space-after")
-                                .with_child(HtmlText::new(","))
-                                .into()
-                        })
-                        .surround(HtmlText::new("<").into(), HtmlText::new(">").into()),
-                )
-            }),
+            _ => base.get_template_argument_types()
+                .or_else(|| base.get_canonical_type().get_template_argument_types())
+                .map(|types| {
+                    HtmlList::new(
+                        types
+                            .iter()
+                            .map(|t| {
+                                t.map(|t| fmt_type(&t, builder))
+                                    .unwrap_or(HtmlText::new("_unk").into())
+                            })
+                            .collect::<Vec<_>>()
+                            .insert_between(|| {
+                                HtmlElement::new("span")
+                                    .with_class("comma")
+                                    .with_class("space-after")
+                                    .with_child(HtmlText::new(","))
+                                    .into()
+                            })
+                            .surround(HtmlText::new("<").into(), HtmlText::new(">").into()),
+                    )
+                }),
         })
         .with_child_opt(
             base.is_const_qualified()
@@ -182,33 +230,218 @@ fn fmt_template_args(entity: &Entity, _builder: &Builder) -> Option<Html> {
     ).into())
 }
 
+/// Find the closest method that an override overrides which has a doc 
+/// comment, searching each base before the bases of the bases
+fn documented_overridden<'e>(method: &Entity<'e>) -> Option<Entity<'e>> {
+    let mut queue = method.get_overridden_methods().unwrap_or_default();
+    while !queue.is_empty() {
+        let base = queue.remove(0);
+        if base.get_comment().is_some() {
+            return Some(base);
+        }
+        queue.extend(base.get_overridden_methods().unwrap_or_default());
+    }
+    None
+}
+
+/// Format the doc comment of an entity, or a placeholder if it has none. 
+/// Overrides without a comment get the documentation of the method they 
+/// override
+pub fn fmt_entity_comment(entity: &Entity, builder: &Builder, include_examples: bool) -> Html {
+    let (mut comment, documented) = match JSDocComment::parse_for(entity, builder) {
+        Some(comment) => (comment, true),
+        None => match documented_overridden(entity)
+            .and_then(|base| Some((JSDocComment::parse_for(&base, builder)?, base)))
+        {
+            Some((mut comment, base)) => {
+                comment.inherit_from(&base, entity);
+                (comment, false)
+            }
+            // Undocumented entities still get their parameters and the
+            // release that added them
+            None => {
+                builder.diagnostics.missing_docs(entity);
+                (JSDocComment::new(builder), false)
+            }
+        },
+    };
+    comment.infer_since(entity);
+    if matches!(
+        entity.get_kind(),
+        EntityKind::FunctionDecl
+            | EntityKind::Method
+            | EntityKind::Constructor
+            | EntityKind::Destructor
+            | EntityKind::FunctionTemplate
+    ) {
+        // Inherited comments document the base's parameters, and
+        // undocumented entities were already reported
+        if documented {
+            comment.check_params(entity);
+        }
+        comment.merge_params(entity);
+        comment.merge_exception_spec(entity);
+    }
+    comment.to_html(include_examples)
+}
+
+/// Whether a class member should be listed on its class' page. Private 
+/// members and members marked `@internal` are only listed if 
+/// `docs.include-private` is enabled
+pub fn is_member_shown(member: &Entity, builder: &Builder) -> bool {
+    is_member_visible(member, &builder.config)
+}
+
+/// [`is_member_shown`] for while the entities are loaded, before there's a
+/// builder
+pub fn is_member_visible(member: &Entity, config: &Config) -> bool {
+    config.docs.include_private || (
+        member.get_accessibility() != Some(Accessibility::Private)
+            && !has_hidden_comment(member)
+            && !has_hide_attribute(member)
+    )
+}
+
+fn fmt_visibility_badge(member: &Entity) -> Option<Html> {
+    let vis = match member.get_accessibility()? {
+        Accessibility::Public => return None,
+        Accessibility::Protected => "protected",
+        Accessibility::Private => "private",
+    };
+    Some(Html::span(&["badge", "visibility", vis, "space-before"], vis))
+}
+
+/// Format the C++ attributes of a declaration for its signature
+fn fmt_cpp_attributes(entity: &Entity) -> Vec<Html> {
+    cpp_attributes(entity)
+        .iter()
+        .map(|a| Html::span(&["attribute", "space-after"], a))
+        .collect()
+}
+
+/// Format the custom badges added to an entity with `[[flash::badge("...")]]`
+fn fmt_attribute_badges(entity: &Entity) -> Vec<Html> {
+    attribute_badges(entity)
+        .iter()
+        .map(|b| Html::span(&["badge", "custom", "space-before"], b))
+        .collect()
+}
+
+/// Whether a class member is a member variable, either a regular field or a 
+/// static data member
+pub fn is_member_variable(entity: &Entity) -> bool {
+    matches!(entity.get_kind(), EntityKind::FieldDecl | EntityKind::VarDecl)
+}
+
+/// Join tokens back into source code, only adding spaces where they are 
+/// needed to keep words apart
+pub fn join_tokens(tokens: &[Token]) -> String {
+    let is_word = |t: &Token| matches!(
+        t.get_kind(), TokenKind::Identifier | TokenKind::Keyword | TokenKind::Literal
+    );
+    let mut res = String::new();
+    let mut prev: Option<&Token> = None;
+    for token in tokens {
+        if let Some(prev) = prev
+            && ((is_word(prev) && is_word(token)) || prev.get_spelling() == ",")
+        {
+            res.push(' ');
+        }
+        res += &token.get_spelling();
+        prev = Some(token);
+    }
+    res
+}
+
+/// Declaration specifiers like `static` and `constexpr` written before a 
+/// member variable's name
+pub fn field_qualifiers(field: &Entity) -> Vec<String> {
+    let (Some(name), Some(range)) = (field.get_name(), field.get_range()) else {
+        return Vec::new();
+    };
+    range.tokenize()
+        .into_iter()
+        .map(|t| t.get_spelling())
+        .take_while(|t| *t != name)
+        .filter(|t| matches!(
+            t.as_str(),
+            "static" | "constexpr" | "constinit" | "inline" | "mutable" | "thread_local"
+        ))
+        .collect()
+}
+
+/// Default member initializer of a member variable, like `5` in `int x = 5;`
+/// or `{ 1, 2 }` in `Point p { 1, 2 };`
+pub fn field_initializer(field: &Entity) -> Option<String> {
+    let name = field.get_name()?;
+    let tokens = field.get_range()?.tokenize();
+    let mut rest = tokens.iter()
+        .skip_while(|t| t.get_spelling() != name)
+        .skip(1)
+        // Skip array sizes and bitfield widths
+        .skip_while(|t| t.get_spelling() != "=" && t.get_spelling() != "{")
+        .cloned()
+        .collect::<Vec<_>>();
+    if rest.first()?.get_spelling() == "=" {
+        rest.remove(0);
+    }
+    if rest.last().is_some_and(|t| t.get_spelling() == ";") {
+        rest.pop();
+    }
+    (!rest.is_empty()).then(|| join_tokens(&rest))
+}
+
 pub fn fmt_field(field: &Entity, builder: &Builder) -> Html {
     HtmlElement::new("details")
         .with_class("entity-desc")
+        .with_attr_opt("id", field.get_name())
         .with_child(
             HtmlElement::new("summary")
                 .with_classes(&["entity", "var"])
+                .with_children(fmt_cpp_attributes(field))
+                .with_children(
+                    field_qualifiers(field)
+                        .iter()
+                        .map(|q| Html::span(&["keyword", "space-after"], q))
+                        .collect()
+                )
                 .with_child(fmt_param(field, builder))
-                .with_child(HtmlText::new(";")),
+                .with_child_opt(field.get_bit_field_width().map(|width| HtmlList::new(vec![
+                    Html::span(&["space-before"], ":"),
+                    Html::span(&["space-before", "literal"], &width.to_string()),
+                ])))
+                .with_child_opt(field_initializer(field).map(|init| HtmlList::new(vec![
+                    Html::span(&["space-before"], "="),
+                    Html::span(&["space-before", "literal"], &init),
+                ])))
+                .with_child(HtmlText::new(";"))
+                .with_child_opt(fmt_visibility_badge(field))
+                .with_children(fmt_attribute_badges(field)),
         )
         .with_child(
-            HtmlElement::new("div").with_child(
-                field
-                    .get_comment()
-                    .map(|s| JSDocComment::parse(s, builder).to_html(true))
-                    .unwrap_or(Html::span(&["no-desc"], "No description provided")),
-            ),
+            HtmlElement::new("div").with_child(fmt_entity_comment(field, builder, true)),
         )
         .into()
 }
 
-pub fn fmt_fun_decl(fun: &Entity, builder: &Builder) -> Html {
+/// Format a function declaration. If the function has its own page, the name 
+/// links to it and only the description is shown
+fn fun_decl(fun: &Entity, builder: &Builder, page: Option<UrlPath>) -> HtmlElement {
+    let name = fun.get_name().unwrap_or("_anon".into());
     HtmlElement::new("details")
         .with_class("entity-desc")
         .with_attr_opt("id", member_fun_link(fun))
         .with_child(
             HtmlElement::new("summary")
                 .with_classes(&["entity", "fun"])
+                .with_attr_opt("data-copy", signature(fun))
+                .with_children(fmt_cpp_attributes(fun))
+                .with_children(
+                    function_specifiers(fun)
+                        .iter()
+                        .map(|s| Html::span(&["keyword", "space-after"], s))
+                        .collect()
+                )
                 .with_child_opt(
                     fun.is_static_method()
                         .then_some(Html::span(&["keyword", "space-after"], "static")),
@@ -218,10 +451,14 @@ pub fn fmt_fun_decl(fun: &Entity, builder: &Builder) -> Html {
                         .then_some(Html::span(&["keyword", "space-after"], "virtual")),
                 )
                 .with_child_opt(fun.get_result_type().map(|t| fmt_type(&t, builder)))
-                .with_child(Html::span(
-                    &["name", "space-before"],
-                    &fun.get_name().unwrap_or("_anon".into()),
-                ))
+                .with_child(match page {
+                    Some(ref url) => HtmlElement::new("a")
+                        .with_classes(&["name", "space-before"])
+                        .with_attr("href", url)
+                        .with_text(&name)
+                        .into(),
+                    None => Html::span(&["name", "space-before"], &name),
+                })
                 .with_child_opt(fmt_template_args(fun, builder))
                 .with_child(
                     HtmlElement::new("span").with_class("params").with_children(
@@ -236,9 +473,17 @@ pub fn fmt_fun_decl(fun: &Entity, builder: &Builder) -> Html {
                             .surround(HtmlText::new("(").into(), HtmlText::new(")").into()),
                     ),
                 )
+                // Qualifiers like `const`, `&&` and `noexcept`
                 .with_child_opt(
-                    fun.is_const_method()
-                        .then_some(Html::span(&["keyword", "space-before"], "const")),
+                    Some(function_qualifiers(fun))
+                        .filter(|q| !q.is_empty())
+                        .map(|q| Html::span(&["keyword", "space-before"], &q)),
+                )
+                .with_children(
+                    virt_specifiers(fun)
+                        .iter()
+                        .map(|s| Html::span(&["keyword", "space-before"], s))
+                        .collect()
                 )
                 .with_child_opt(
                     fun.is_pure_virtual_method().then_some::<Html>(
@@ -248,16 +493,31 @@ pub fn fmt_fun_decl(fun: &Entity, builder: &Builder) -> Html {
                         ])
                         .into(),
                     ),
-                ),
-        )
-        .with_child(
-            HtmlElement::new("div").with_child(
-                fun.get_comment()
-                    .map(|s| JSDocComment::parse(s, builder).to_html(true))
-                    .unwrap_or(Html::span(&["no-desc"], "No description provided")),
-            ),
+                )
+                .with_child_opt(fmt_visibility_badge(fun))
+                .with_children(fmt_attribute_badges(fun)),
         )
-        .into()
+        .with_child(match page {
+            Some(url) => HtmlElement::new("div")
+                .with_child(fmt_entity_comment(fun, builder, false))
+                .with_child(
+                    HtmlElement::new("a")
+                        .with_class("member-page")
+                        .with_attr("href", url)
+                        .with_text(builder.config.tr("Full documentation"))
+                ),
+            None => HtmlElement::new("div").with_child(fmt_entity_comment(fun, builder, true)),
+        })
+}
+
+pub fn fmt_fun_decl(fun: &Entity, builder: &Builder) -> Html {
+    fun_decl(fun, builder, member_page_url(fun, builder.config.clone())).into()
+}
+
+/// Format a function declaration with its full documentation expanded, for 
+/// the function's own page
+pub fn fmt_fun_decl_open(fun: &Entity, builder: &Builder) -> Html {
+    fun_decl(fun, builder, None).with_attr("open", "").into()
 }
 
 pub fn fmt_classlike_decl(class: &Entity, keyword: &str, builder: &Builder) -> Html {
@@ -267,60 +527,347 @@ pub fn fmt_classlike_decl(class: &Entity, keyword: &str, builder: &Builder) -> H
             HtmlElement::new("summary")
                 .with_classes(&["entity", keyword])
                 .with_child(Html::span(&["keyword", "space-after"], keyword))
-                .with_child(Html::span(
-                    &["name"],
-                    &class.get_name().unwrap_or("_anon".into()),
-                ))
+                .with_children(fmt_cpp_attributes(class))
+                .with_child(
+                    HtmlElement::new("a")
+                        .with_class("name")
+                        .with_attr_opt("href", class.abs_docs_url(builder.config.clone()))
+                        .with_text(class.get_name().unwrap_or("_anon".into()))
+                )
                 .with_child_opt(fmt_template_args(class, builder))
-                .with_child(HtmlText::new(";")),
+                .with_child_opt(
+                    is_final_class(class)
+                        .then_some(Html::span(&["keyword", "space-before"], "final"))
+                )
+                .with_child(HtmlText::new(";"))
+                .with_children(fmt_attribute_badges(class)),
         )
         .with_child(
-            HtmlElement::new("div").with_child(
-                class.get_comment()
-                    .map(|s| JSDocComment::parse(s, builder).to_html(true))
-                    .unwrap_or(Html::span(&["no-desc"], "No description provided")),
-            ),
+            HtmlElement::new("div").with_child(fmt_entity_comment(class, builder, true)),
+        )
+        .into()
+}
+
+/// Get the anchor of an enumerator on the page its enum is listed on, like 
+/// `Color-Red`
+pub fn enum_value_link(enumerator: &Entity) -> Option<String> {
+    Some(format!("{}-{}", enumerator.get_semantic_parent()?.get_name()?, enumerator.get_name()?))
+}
+
+/// Get the value of an enumerator, whether it was assigned explicitly or 
+/// follows from the previous enumerator
+fn enum_value(enumerator: &Entity, unsigned: bool) -> Option<String> {
+    let (signed, raw) = enumerator.get_enum_constant_value()?;
+    Some(if unsigned { raw.to_string() } else { signed.to_string() })
+}
+
+pub fn fmt_enum_decl(enum_: &Entity, builder: &Builder) -> Html {
+    let unsigned = enum_.get_enum_underlying_type().is_some_and(|t| matches!(
+        t.get_canonical_type().get_kind(),
+        TypeKind::Bool | TypeKind::CharU | TypeKind::UChar | TypeKind::UShort
+            | TypeKind::UInt | TypeKind::ULong | TypeKind::ULongLong | TypeKind::UInt128
+    ));
+    let rows = enum_.get_children()
+        .into_iter()
+        .filter(|c| c.get_kind() == EntityKind::EnumConstantDecl)
+        .map(|c| {
+            // The initializer is the enumerator's only expression child,
+            // other children are things like attributes
+            let implicit = !c.get_children().iter().any(|e| e.is_expression());
+            HtmlElement::new("tr")
+                .with_attr_opt("id", enum_value_link(&c))
+                .with_child(
+                    HtmlElement::new("td")
+                        .with_child(Html::span(&["enumerator"], &c.get_name().unwrap_or("_anon".into())))
+                )
+                .with_child(
+                    HtmlElement::new("td")
+                        .with_child(
+                            HtmlElement::new("code")
+                                .with_class_opt(implicit.then_some("implicit"))
+                                .with_text(enum_value(&c, unsigned).unwrap_or_default())
+                        )
+                )
+                .with_child(
+                    HtmlElement::new("td")
+                        .with_child_opt(JSDocComment::parse_for(&c, builder).map(|c| c.to_html(false)))
+                )
+                .into()
+        })
+        .collect::<Vec<Html>>();
+
+    HtmlElement::new("details")
+        .with_class("entity-desc")
+        .with_attr_opt("id", enum_.get_name())
+        .with_child(
+            HtmlElement::new("summary")
+                .with_classes(&["entity", "enum"])
+                .with_child(Html::span(&["keyword", "space-after"], "enum"))
+                .with_child(
+                    HtmlElement::new("a")
+                        .with_class("name")
+                        .with_text(enum_.get_name().unwrap_or("_anon".into()))
+                )
+                .with_child(HtmlText::new(";"))
+                .with_children(fmt_attribute_badges(enum_)),
         )
+        .with_child(
+            HtmlElement::new("div")
+                .with_child(fmt_entity_comment(enum_, builder, true))
+                .with_child_opt((!rows.is_empty()).then(||
+                    HtmlElement::new("table")
+                        .with_class("enumerators")
+                        .with_children(rows)
+                )),
+        )
+        .into()
+}
+
+/// Format a `typedef` with the type it aliases
+pub fn fmt_typedef(alias: &Entity, builder: &Builder) -> Html {
+    HtmlElement::new("details")
+        .with_class("entity-desc")
+        .with_attr_opt("id", alias.get_name())
+        .with_child(
+            HtmlElement::new("summary")
+                .with_classes(&["entity", "alias"])
+                .with_child(Html::span(&["keyword", "space-after"], "typedef"))
+                .with_child_opt(alias.get_typedef_underlying_type().map(|t| fmt_type(&t, builder)))
+                .with_child(Html::span(&["name", "space-before"], &alias.get_name().unwrap_or_default()))
+                .with_child(HtmlText::new(";"))
+                .with_children(fmt_attribute_badges(alias)),
+        )
+        .with_child(
+            HtmlElement::new("div").with_child(fmt_entity_comment(alias, builder, true)),
+        )
+        .into()
+}
+
+/// The parameters and the replacement of a macro, like `(a, b)` and 
+/// `((a) > (b) ? (a) : (b))`. Object-like macros have no parameters
+fn macro_parts(macro_: &Entity) -> (Option<String>, String) {
+    let tokens = macro_.get_range().map(|r| r.tokenize()).unwrap_or_default();
+    // The first token is the name of the macro
+    let rest = tokens.get(1..).unwrap_or_default();
+    if macro_.is_function_like_macro() && let Some(end) = rest.iter().position(|t| t.get_spelling() == ")") {
+        (Some(join_tokens(&rest[..=end])), join_tokens(&rest[end + 1..]))
+    } else {
+        (None, join_tokens(rest))
+    }
+}
+
+/// Whether a macro is only there to guard a header from being included 
+/// twice, like `#define FOO_H`
+pub fn is_include_guard(macro_: &Entity) -> bool {
+    let name = macro_.get_name().unwrap_or_default();
+    !macro_.is_function_like_macro()
+        && macro_parts(macro_).1.is_empty()
+        && ["_H", "_H_", "_HPP", "_INCLUDED"].iter().any(|s| name.ends_with(s))
+}
+
+/// Format a `#define` with its parameters, and its value if it's 
+/// object-like since those are usually constants
+pub fn fmt_macro(macro_: &Entity, builder: &Builder) -> Html {
+    let (params, value) = macro_parts(macro_);
+    let object_like = params.is_none();
+    HtmlElement::new("details")
+        .with_class("entity-desc")
+        .with_attr_opt("id", macro_.get_name())
+        .with_child(
+            HtmlElement::new("summary")
+                .with_classes(&["entity", "macro"])
+                .with_child(Html::span(&["keyword", "space-after"], "#define"))
+                .with_child(Html::span(&["name"], &macro_.get_name().unwrap_or_default()))
+                .with_child_opt(params.map(HtmlText::new))
+                .with_child_opt((object_like && !value.is_empty()).then(||
+                    Html::span(&["space-before", "literal"], &value)
+                )),
+        )
+        .with_child(
+            HtmlElement::new("div").with_child(fmt_entity_comment(macro_, builder, true)),
+        )
+        .into()
+}
+
+/// Format the classes, structs and enums declared inside a class
+pub fn fmt_nested_types(class: &Entity, builder: &Builder) -> Html {
+    fmt_section(
+        builder.config.tr("Nested types"),
+        class.get_children()
+            .iter()
+            .filter(|c| c.get_name().is_some() && c.is_definition() && is_member_shown(c, builder))
+            .filter_map(|c| match c.get_kind() {
+                EntityKind::EnumDecl => Some(fmt_enum_decl(c, builder)),
+                _ => match CppItemKind::from(c)? {
+                    CppItemKind::Class => Some(fmt_classlike_decl(c, "class", builder)),
+                    CppItemKind::Struct => Some(fmt_classlike_decl(c, "struct", builder)),
+                    _ => None,
+                },
+            })
+            .collect(),
+    )
+}
+
+/// Entry in a page's table of contents
+pub struct TocItem {
+    pub title: String,
+    pub id: String,
+    pub children: Vec<TocItem>,
+}
+
+impl TocItem {
+    pub fn new(title: &str, id: &str) -> Self {
+        Self { title: title.into(), id: id.into(), children: Vec::new() }
+    }
+}
+
+/// Format an in-page table of contents, shown as a sidebar next to the 
+/// page content
+pub fn fmt_toc(items: &[TocItem], config: &Config) -> Html {
+    fn fmt_items(items: &[TocItem]) -> Html {
+        HtmlElement::new("ul")
+            .with_children(items.iter().map(|item| 
+                HtmlElement::new("li")
+                    .with_child(
+                        HtmlElement::new("a")
+                            .with_attr("href", format!("#{}", item.id))
+                            .with_text(&item.title)
+                    )
+                    .with_child_opt(
+                        (!item.children.is_empty()).then(|| fmt_items(&item.children))
+                    )
+                    .into()
+            ).collect())
+            .into()
+    }
+
+    if items.is_empty() {
+        return Html::Raw(String::new());
+    }
+    HtmlElement::new("aside")
+        .with_class("page-toc")
+        .with_attr("aria-label", config.tr("On this page"))
+        .with_child(Html::span(&["title"], config.tr("On this page")))
+        .with_child(fmt_items(items))
         .into()
 }
 
+/// Get the anchor for a section from its title
+pub fn section_id(title: &str) -> String {
+    title.to_lowercase().split_whitespace().collect::<Vec<_>>().join("-")
+}
+
 pub fn fmt_section(title: &str, data: Vec<Html>) -> Html {
+    let count = data.len();
+    fmt_section_with_count(title, count, data)
+}
+
+fn fmt_section_with_count(title: &str, count: usize, data: Vec<Html>) -> Html {
+    fmt_section_with_id(&heading_id(&section_id(title)), title, count, data)
+}
+
+/// Format a section with an id from `heading_id`, for sections that are
+/// also linked to from the table of contents
+fn fmt_section_with_id(id: &str, title: &str, count: usize, data: Vec<Html>) -> Html {
     HtmlElement::new("details")
         .with_attr("open", "")
+        .with_attr("id", id)
         .with_class("section")
         .with_child(
             HtmlElement::new("summary").with_child(
                 HtmlElement::new("span")
                     .with_child(Html::feather("chevron-right"))
                     .with_child(HtmlText::new(title))
-                    .with_child(Html::span(&["badge"], &data.len().to_string())),
+                    .with_child(Html::span(&["badge"], &count.to_string())),
             ),
         )
         .with_child(HtmlElement::new("div").with_child(HtmlList::new(data)))
         .into()
 }
 
+/// The letter something is listed under in alphabetical lists. Names that 
+/// don't start with a letter are listed under `#`
+pub fn index_letter(name: &str) -> String {
+    match name.trim_start_matches(['_', '~']).chars().next() {
+        Some(c) if c.is_alphabetic() => c.to_uppercase().to_string(),
+        _ => "#".into(),
+    }
+}
+
+/// Format a section of named items, splitting it into a heading for each 
+/// letter with links to them at the top if there are more than `above` 
+/// items (and `above` isn't 0)
+pub fn fmt_section_grouped(title: &str, items: Vec<(String, Html)>, above: usize) -> Html {
+    if above == 0 || items.len() <= above {
+        return fmt_section(title, items.into_iter().map(|(_, html)| html).collect());
+    }
+    let count = items.len();
+    let mut letters: BTreeMap<String, Vec<Html>> = BTreeMap::new();
+    for (name, html) in items {
+        letters.entry(index_letter(&name)).or_default().push(html);
+    }
+    let id = |letter: &str| heading_id(&format!(
+        "{}-{}", section_id(title), if letter == "#" { "other" } else { letter }.to_lowercase()
+    ));
+    let ids = letters.keys().map(|l| id(l)).collect::<Vec<_>>();
+    let mut data: Vec<Html> = vec![
+        HtmlElement::new("nav")
+            .with_class("index-letters")
+            .with_children(letters.keys().zip(&ids)
+                .map(|(letter, id)| HtmlElement::new("a")
+                    .with_attr("href", format!("#{id}"))
+                    .with_text(letter)
+                    .into()
+                )
+                .collect()
+            )
+            .into(),
+    ];
+    for ((letter, group), id) in letters.into_iter().zip(ids) {
+        data.push(HtmlElement::new("h3")
+            .with_class("letter-group")
+            .with_attr("id", id)
+            .with_text(letter)
+            .into()
+        );
+        data.extend(group);
+    }
+    fmt_section_with_count(title, count, data)
+}
+
 pub fn fmt_header_link(entity: &Entity, config: Arc<Config>) -> Html {
     if let Some(link) = entity.github_url(config.clone()) &&
         let Some(path) = entity.include_path(config.clone()) &&
-        let Some(src) = entity.config_source(config)
+        let Some(src) = entity.config_source(config.clone())
     {
         let disabled = !src.exists_online;
-        HtmlElement::new("a")
-            .with_attr_opt("href", (!disabled).then_some(link))
-            .with_class("header-link")
-            .with_class_opt(disabled.then_some("disabled"))
-            .with_child(HtmlElement::new("code")
+        HtmlList::new([
+            Some::<Html>(HtmlElement::new("a")
+                .with_attr_opt("href", (!disabled).then_some(link))
                 .with_class("header-link")
-                .with_children(vec![
-                    Html::span(&["keyword"], "#include "),
-                    Html::span(&["url"], &format!("&lt;{}&gt;", path.to_raw_string()))
-                ])
-            )
-            .into()
+                .with_class_opt(disabled.then_some("disabled"))
+                .with_attr("data-copy", format!("#include <{}>", path.to_raw_string()))
+                .with_child(HtmlElement::new("code")
+                    .with_class("header-link")
+                    .with_children(vec![
+                        Html::span(&["keyword"], "#include "),
+                        Html::span(&["url"], &format!("<{}>", path.to_raw_string()))
+                    ])
+                )
+                .into()),
+            entity.source_url(config.clone()).map(|url|
+                HtmlElement::new("a")
+                    .with_attr("href", url)
+                    .with_class("source-link")
+                    .with_child(Html::feather("code"))
+                    .with_text(config.tr("View source"))
+                    .into()
+            ),
+        ].into_iter().flatten().collect())
+        .into()
     }
     else {
-        Html::p("&lt;Not available online&gt;")
+        Html::p("<Not available online>")
     }
 }
 
@@ -334,6 +881,10 @@ pub fn fmt_base_classes<'e, T: ASTEntry<'e>>(entry: &T, kw: &str, builder: &Buil
         .with_classes(&["entity", "class"])
         .with_child(Html::span(&["keyword", "space-after"], kw))
         .with_child(Html::span(&["name"], entry.name().as_str()))
+        .with_child_opt(
+            is_final_class(entry.entity())
+                .then_some(Html::span(&["keyword", "space-before"], "final"))
+        )
         .with_child_opt((!bases.is_empty()).then_some(
             Html::span(&["space-before", "space-after"], ":")
         ))
@@ -361,33 +912,147 @@ pub fn fmt_base_classes<'e, T: ASTEntry<'e>>(entry: &T, kw: &str, builder: &Buil
         .into()
 }
 
+/// Get the classes a class publicly inherits from, with the template of 
+/// each base that's an instantiation since those don't have their members
+fn public_bases<'e>(class: &Entity<'e>) -> Vec<Entity<'e>> {
+    class.get_children()
+        .into_iter()
+        .filter(|c| c.get_kind() == EntityKind::BaseSpecifier && c.get_accessibility() == Some(Accessibility::Public))
+        .filter_map(|base| {
+            let decl = base.get_type()?.get_declaration()?;
+            let decl = decl.get_definition().unwrap_or(decl);
+            Some(decl.get_template().unwrap_or(decl))
+        })
+        .collect()
+}
+
+/// Format the public members a class inherits from its documented bases, 
+/// grouped by the base they come from. Members hidden by members with the 
+/// same name in the class or a closer base are left out
+fn fmt_inherited_members(class: &Entity, builder: &Builder) -> (Html, usize) {
+    let mut hidden = class.get_children()
+        .into_iter()
+        .filter_map(|c| c.get_name())
+        .collect::<HashSet<_>>();
+    let mut groups = Vec::new();
+    let mut count = 0;
+    let mut queue = public_bases(class);
+    let mut seen = HashSet::new();
+    while !queue.is_empty() {
+        let base = queue.remove(0);
+        if !seen.insert(base.get_usr()) {
+            continue;
+        }
+        queue.extend(public_bases(&base));
+        let Some(url) = base.abs_docs_url(builder.config.clone()) else {
+            continue;
+        };
+        let mut items = Vec::new();
+        let mut names = HashSet::new();
+        for member in base.get_children() {
+            let Some(name) = member.get_name() else {
+                continue;
+            };
+            let link = match member.get_kind() {
+                EntityKind::Method if name != "operator=" => member_fun_link(&member),
+                _ if is_member_variable(&member) => Some(name.clone()),
+                _ => None,
+            };
+            if member.get_accessibility() != Some(Accessibility::Public)
+                || hidden.contains(&name)
+                || !is_member_shown(&member, builder)
+                || !names.insert(name.clone())
+            {
+                continue;
+            }
+            let Some(link) = link else {
+                continue;
+            };
+            items.push(HtmlElement::new("div")
+                .with_class("index-item")
+                .with_child(HtmlElement::new("a")
+                    .with_attr("href", format!("{url}#{link}"))
+                    .with_child(HtmlElement::new("code").with_text(&name))
+                )
+                .into()
+            );
+        }
+        hidden.extend(names);
+        if items.is_empty() {
+            continue;
+        }
+        count += items.len();
+        groups.push(HtmlElement::new("details")
+            .with_class("inherited")
+            .with_child(HtmlElement::new("summary")
+                .with_text(builder.config.tr("Inherited from"))
+                .with_child(HtmlElement::new("a")
+                    .with_class("space-before")
+                    .with_attr("href", url)
+                    .with_text(base.full_name().join("::"))
+                )
+            )
+            .with_child(HtmlElement::new("div").with_children(items))
+            .into()
+        );
+    }
+    (HtmlList::new(groups).into(), count)
+}
+
+/// Links to the parents of a page, like the folders of a tutorial or the 
+/// namespaces and classes an entity is in
+pub fn fmt_breadcrumbs(crumbs: &[(String, UrlPath)], config: Arc<Config>) -> Html {
+    if crumbs.is_empty() {
+        return Html::Raw(String::new());
+    }
+    HtmlElement::new("nav")
+        .with_class("breadcrumbs")
+        .with_children(
+            crumbs.iter()
+                .map(|(name, url)| Html::from(
+                    HtmlElement::new("a")
+                        .with_attr("href", url.to_absolute(config.clone()))
+                        .with_text(name)
+                ))
+                .intersperse_with(|| Html::feather("chevron-right"))
+                .collect()
+        )
+        .into()
+}
+
+/// Breadcrumbs for the namespaces and classes an entity is in
+fn fmt_entity_breadcrumbs(entity: &Entity, builder: &Builder) -> Html {
+    let mut ancestors = entity.ancestorage();
+    ancestors.pop();
+    fmt_breadcrumbs(
+        &ancestors.iter()
+            .filter_map(|a| Some((a.get_name()?, a.rel_docs_url()?)))
+            .collect::<Vec<_>>(),
+        builder.config.clone(),
+    )
+}
+
 pub fn output_entity<'e, T: ASTEntry<'e>>(
     entry: &T,
     builder: &Builder,
 ) -> Vec<(&'static str, Html)> {
     vec![
         ("name", HtmlText::new(entry.name()).into()),
-        (
-            "description",
-            entry
-                .entity()
-                .get_comment()
-                .map(|s| JSDocComment::parse(s, builder).to_html(false))
-                .unwrap_or(Html::span(&["no-desc"], "No description provided")),
-        ),
+        ("breadcrumbs", fmt_entity_breadcrumbs(entry.entity(), builder)),
+        ("description", fmt_entity_comment(entry.entity(), builder, false)),
         (
             "header_link",
             fmt_header_link(entry.entity(), builder.config.clone()),
         ),
+        ("permalink", fmt_permalink(entry.entity(), builder)),
+        ("referenced_by", fmt_referenced_by(entry.entity(), builder)),
         (
             "examples",
             fmt_section(
-                "Examples",
-                entry
-                    .entity()
-                    .get_comment()
-                    .map(|s| {
-                        JSDocComment::parse(s, builder)
+                builder.config.tr("Examples"),
+                JSDocComment::parse_for(entry.entity(), builder)
+                    .map(|comment| {
+                        comment
                             .examples()
                             .iter()
                             .map(|example| example.to_html())
@@ -399,80 +1064,266 @@ pub fn output_entity<'e, T: ASTEntry<'e>>(
     ]
 }
 
+fn collect_free_functions<'e>(ns: &Namespace<'e>, out: &mut Vec<Entity<'e>>) {
+    for entry in ns.entries.values() {
+        match entry {
+            CppItem::Namespace(ns) => collect_free_functions(ns, out),
+            CppItem::Function(fun) => out.extend(fun.overloads()),
+            _ => {}
+        }
+    }
+}
+
+/// The classes free functions say they `@relates` to by the USRs of the
+/// functions, so their comments are parsed once instead of for every class
+pub fn collect_relates(builder: &Builder) -> HashMap<String, String> {
+    let mut free = Vec::new();
+    collect_free_functions(&builder.root, &mut free);
+    free.iter()
+        .filter_map(|fun| Some((
+            fun.get_usr()?.0,
+            JSDocComment::parse_for(fun, builder)?.relates()?.clone(),
+        )))
+        .collect()
+}
+
+/// Format the free functions related to a class; friend functions, functions 
+/// marked with `@relates Class` and functions that take the class as their 
+/// first parameter
+pub fn fmt_related_functions(class: &Entity, builder: &Builder) -> Html {
+    let canonical = class.get_canonical_entity();
+    let full_name = class.full_name().join("::");
+    let name = class.get_name();
+
+    let is_related = |fun: &Entity| {
+        let relates = fun.get_usr().and_then(|usr| builder.relates().get(&usr.0));
+        if let Some(relates) = relates {
+            return *relates == full_name || Some(relates) == name.as_ref();
+        }
+        fun.get_arguments()
+            .and_then(|args| args.first().and_then(|a| a.get_type()))
+            .map(|t| t.get_pointee_type().unwrap_or(t))
+            .and_then(|t| t.get_declaration())
+            .is_some_and(|d| d.get_canonical_entity() == canonical)
+    };
+
+    // Friend functions declared inside the class
+    let mut related = class.get_children()
+        .iter()
+        .filter(|c| c.get_kind() == EntityKind::FriendDecl)
+        .flat_map(|c| c.get_children())
+        .filter(|c| c.get_kind() == EntityKind::FunctionDecl)
+        .collect::<Vec<_>>();
+
+    let mut free = Vec::new();
+    collect_free_functions(&builder.root, &mut free);
+    for fun in free {
+        if is_related(&fun) && !related.iter().any(|r|
+            r.get_canonical_entity() == fun.get_canonical_entity()
+        ) {
+            related.push(fun);
+        }
+    }
+
+    fmt_section(
+        builder.config.tr("Related functions"),
+        related.iter()
+            .map(|f| fmt_fun_decl(f, builder))
+            .collect()
+    )
+}
+
+/// Get the anchor for a template specialization on its primary template's page
+pub fn specialization_link(spec: &Entity) -> String {
+    format!(
+        "spec-{}",
+        spec.get_display_name()
+            .unwrap_or("_anon".into())
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect::<String>()
+    )
+}
+
+/// Format the partial and full specializations of a class template as their 
+/// own sections on the primary template's page
+pub fn fmt_specializations(specs: &[Entity], builder: &Builder) -> Html {
+    HtmlList::new(
+        specs.iter().map(|spec| {
+            HtmlElement::new("details")
+                .with_attr("open", "")
+                .with_attr("id", specialization_link(spec))
+                .with_classes(&["section", "specialization"])
+                .with_child(
+                    HtmlElement::new("summary").with_child(
+                        HtmlElement::new("span")
+                            .with_child(Html::feather("chevron-right"))
+                            .with_text(builder.config.tr("Specialization {name}").replace(
+                                "{name}",
+                                &spec.get_display_name().unwrap_or("_anon".into())
+                            )),
+                    ),
+                )
+                .with_child(
+                    HtmlElement::new("div")
+                        .with_child(fmt_entity_comment(spec, builder, true))
+                        .with_child(fmt_section(
+                            builder.config.tr("Public member functions"),
+                            get_member_functions(spec, Access::Public, Include::All)
+                                .into_iter()
+                                .filter(|e| is_member_shown(e, builder))
+                                .map(|e| fmt_fun_decl(&e, builder))
+                                .collect(),
+                        ))
+                        .with_child(fmt_section(
+                            builder.config.tr("Fields"),
+                            spec.get_children()
+                                .iter()
+                                .filter(|child| {
+                                    is_member_variable(child)
+                                        && child.get_accessibility() == Some(Accessibility::Public)
+                                })
+                                .map(|e| fmt_field(e, builder))
+                                .collect(),
+                        )),
+                )
+                .into()
+        })
+        .collect()
+    )
+    .into()
+}
+
 pub fn output_classlike<'e, T: ASTEntry<'e>>(
     entry: &T,
     builder: &Builder,
 ) -> Vec<(&'static str, Html)> {
-    let mut ent = output_entity(entry, builder);
-    ent.extend(vec![
+    let functions = |access, include| {
+        get_member_functions(entry.entity(), access, include)
+            .into_iter()
+            .filter(|e| is_member_shown(e, builder))
+            .collect::<Vec<_>>()
+    };
+    let fields = |access| {
+        entry
+            .entity()
+            .get_children()
+            .into_iter()
+            .filter(|child| {
+                is_member_variable(child)
+                    && child.get_accessibility() == Some(access)
+                    && is_member_shown(child, builder)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Format a section of members and add it to the table of contents
+    let mut toc = Vec::new();
+    let mut section = |title: &str, members: Vec<Entity>, are_functions: bool| {
+        let title = builder.config.tr(title);
+        let id = heading_id(&section_id(title));
+        if !members.is_empty() {
+            toc.push(TocItem {
+                title: title.into(),
+                id: id.clone(),
+                children: members.iter()
+                    .filter_map(|m| Some(TocItem::new(
+                        &m.get_name()?,
+                        &(if are_functions { member_fun_link(m) } else { m.get_name() })?,
+                    )))
+                    .collect(),
+            });
+        }
+        fmt_section_with_id(
+            &id,
+            title,
+            members.len(),
+            members.iter()
+                .map(|m| if are_functions { fmt_fun_decl(m, builder) } else { fmt_field(m, builder) })
+                .collect(),
+        )
+    };
+
+    let include_private = builder.config.docs.include_private;
+    // Protected members of final classes can't be used by anything outside 
+    // of the class, so they're as good as private
+    let show_protected = include_private || !is_final_class(entry.entity());
+    let mut vars = vec![
         (
             "base_classes",
             fmt_base_classes(entry, entry.category(), builder)
         ),
+        (
+            "nested_types",
+            fmt_nested_types(entry.entity(), builder),
+        ),
         (
             "public_static_functions",
-            fmt_section(
-                "Public static methods",
-                get_member_functions(entry.entity(), Access::Public, Include::Statics)
-                    .into_iter()
-                    .map(|e| fmt_fun_decl(&e, builder))
-                    .collect::<Vec<_>>(),
-            ),
+            section("Public static methods", functions(Access::Public, Include::Statics), true),
         ),
         (
             "public_member_functions",
-            fmt_section(
-                "Public member functions",
-                get_member_functions(entry.entity(), Access::Public, Include::Members)
-                    .into_iter()
-                    .map(|e| fmt_fun_decl(&e, builder))
-                    .collect::<Vec<_>>(),
-            ),
+            section("Public member functions", functions(Access::Public, Include::Members), true),
         ),
         (
-            // todo: hide if final class
             "protected_member_functions",
-            fmt_section(
-                "Protected member functions",
-                get_member_functions(entry.entity(), Access::Protected, Include::Members)
-                    .into_iter()
-                    .map(|e| fmt_fun_decl(&e, builder))
-                    .collect::<Vec<_>>(),
-            ),
+            if show_protected {
+                section("Protected member functions", functions(Access::Protected, Include::Members), true)
+            } else {
+                Html::Raw(String::new())
+            },
+        ),
+        (
+            "private_member_functions",
+            if include_private {
+                section("Private member functions", functions(Access::Private, Include::All), true)
+            } else {
+                Html::Raw(String::new())
+            },
         ),
         (
             "public_members",
-            fmt_section(
-                "Fields",
-                entry
-                    .entity()
-                    .get_children()
-                    .iter()
-                    .filter(|child| {
-                        child.get_kind() == EntityKind::FieldDecl
-                            && child.get_accessibility() == Some(Accessibility::Public)
-                    })
-                    .map(|e| fmt_field(e, builder))
-                    .collect::<Vec<_>>(),
-            ),
+            section("Fields", fields(Accessibility::Public), false),
+        ),
+        (
+            "related_functions",
+            fmt_related_functions(entry.entity(), builder),
         ),
         (
             "protected_members",
-            fmt_section(
-                "Protected fields",
-                entry
-                    .entity()
-                    .get_children()
-                    .iter()
-                    .filter(|child| {
-                        child.get_kind() == EntityKind::FieldDecl
-                            && child.get_accessibility() == Some(Accessibility::Protected)
-                    })
-                    .map(|e| fmt_field(e, builder))
-                    .collect::<Vec<_>>(),
-            ),
+            if show_protected {
+                section("Protected fields", fields(Accessibility::Protected), false)
+            } else {
+                Html::Raw(String::new())
+            },
+        ),
+        (
+            "private_members",
+            if include_private {
+                section("Private fields", fields(Accessibility::Private), false)
+            } else {
+                Html::Raw(String::new())
+            },
         ),
-    ]);
+    ];
+
+    // Listed after the class' own members
+    let (inherited, count) = fmt_inherited_members(entry.entity(), builder);
+    vars.push((
+        "inherited_members",
+        if count > 0 {
+            let title = builder.config.tr("Inherited members");
+            let id = heading_id(&section_id(title));
+            toc.push(TocItem::new(title, &id));
+            fmt_section_with_id(&id, title, count, vec![inherited])
+        } else {
+            Html::Raw(String::new())
+        },
+    ));
+
+    let mut ent = output_entity(entry, builder);
+    ent.extend(vars);
+    ent.push(("page_toc", fmt_toc(&toc, &builder.config)));
     ent
 }
 
@@ -507,9 +1358,231 @@ pub fn fmt_autolinks(builder: &Builder, text: &str, prefix: Option<char>) -> Str
             entry, builder.config.clone(), &mut annotations, &prefix
         );
     }
+    for ext in &builder.config.external_docs {
+        annotations.rewind();
+        while let Some(word) = annotations.next() {
+            if !word.chars().all(|c| c.is_lowercase()) && let Some(url) = ext.by_name.get(&word) {
+                annotations.annotate(format!("[{word}]({url})"));
+            }
+        }
+    }
     annotations.into_result()
 }
 
+/// Find the docs URL for an entity by its (possibly partially) qualified 
+/// name, like `ns::Class::method`
+pub fn resolve_ref(builder: &Builder, name: &str) -> Option<String> {
+    fn resolve_in(ns: &Namespace, parts: &[&str], builder: &Builder) -> Option<String> {
+        // Try resolving relative to this namespace first
+        if let Some(item) = lookup(ns, parts) {
+            let (item, rest) = item;
+            let url = item.entity().abs_docs_url(builder.config.clone())?;
+            if rest.is_empty() {
+                return Some(url.to_string());
+            }
+            let fun = match rest {
+                [member] => get_member_functions(item.entity(), Access::All, Include::All)
+                    .into_iter()
+                    .find(|f| f.get_name().is_some_and(|n| n == *member) && is_member_shown(f, builder))
+                    .and_then(|f| member_fun_link(&f)),
+                _ => None,
+            };
+            return fun
+                .or_else(|| enum_anchor(&item.entity().get_children(), rest))
+                .map(|link| format!("{url}#{link}"));
+        }
+        if let Some(url) = resolve_enum(ns, parts, builder) {
+            return Some(url);
+        }
+        // Otherwise look in nested namespaces
+        ns.entries.values().find_map(|e| match e {
+            CppItem::Namespace(ns) => resolve_in(ns, parts, builder),
+            _ => None,
+        })
+    }
+
+    let parts = ref_parts(name);
+    if parts.is_empty() {
+        return None;
+    }
+    resolve_in(&builder.root, &parts, builder)
+}
+
+/// Find the anchor of an enum or one of its enumerators among some 
+/// declarations, like `Color` or `Color::Red`
+fn enum_anchor(decls: &[Entity], parts: &[&str]) -> Option<String> {
+    let (name, rest) = parts.split_first()?;
+    let enum_ = decls.iter().find(|e|
+        e.get_kind() == EntityKind::EnumDecl && e.get_name().is_some_and(|n| n == *name)
+    )?;
+    match rest {
+        [] => Some(name.to_string()),
+        [value] => enum_.get_children()
+            .iter()
+            .find(|c| c.get_name().is_some_and(|n| n == *value))
+            .and_then(enum_value_link),
+        _ => None,
+    }
+}
+
+/// Find the docs URL for an enum or enumerator declared in a namespace. Enums 
+/// aren't loaded as entries, so they're looked up from the declarations of 
+/// the namespace the rest of the name leads to
+fn resolve_enum(ns: &Namespace, parts: &[&str], builder: &Builder) -> Option<String> {
+    if let Some(anchor) = enum_anchor(&ns.declared(EntityKind::EnumDecl), parts) {
+        let url = ns.entity().abs_docs_url(builder.config.clone())?;
+        return Some(format!("{url}#{anchor}"));
+    }
+    match ns.entries.get(*parts.first()?)? {
+        CppItem::Namespace(inner) => resolve_enum(inner, &parts[1..], builder),
+        _ => None,
+    }
+}
+
+/// Find an entity by its (possibly partially) qualified name, like 
+/// `ns::Class::member`
+pub fn resolve_entity<'e>(builder: &Builder<'e>, name: &str) -> Option<Entity<'e>> {
+    fn resolve_in<'e>(ns: &Namespace<'e>, parts: &[&str]) -> Option<Entity<'e>> {
+        if let Some(item) = lookup(ns, parts) {
+            let (item, rest) = item;
+            return match rest {
+                [] => Some(*item.entity()),
+                [member] => item.entity()
+                    .get_children()
+                    .into_iter()
+                    .find(|c| c.get_name().is_some_and(|n| n == *member)),
+                _ => None,
+            };
+        }
+        ns.entries.values().find_map(|e| match e {
+            CppItem::Namespace(ns) => resolve_in(ns, parts),
+            _ => None,
+        })
+    }
+
+    let parts = ref_parts(name);
+    if parts.is_empty() {
+        return None;
+    }
+    resolve_in(&builder.root, &parts)
+}
+
+fn ref_parts(name: &str) -> Vec<&str> {
+    name.trim()
+        .trim_end_matches("()")
+        .split("::")
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+fn lookup<'a, 'e, 'p>(
+    ns: &'a Namespace<'e>, parts: &'p [&'p str]
+) -> Option<(&'a CppItem<'e>, &'p [&'p str])> {
+    let (first, rest) = parts.split_first()?;
+    match ns.entries.get(*first)? {
+        CppItem::Namespace(inner) if !rest.is_empty() => lookup(inner, rest),
+        item => Some((item, rest)),
+    }
+}
+
+/// Whether a reference points to an entity that exists but was hidden with 
+/// `@internal` or `@hide`, in which case it's just not linked
+fn is_hidden_ref(builder: &Builder, name: &str) -> bool {
+    fn hidden_in(ns: &Namespace, parts: &[&str], builder: &Builder) -> bool {
+        let here = match lookup(ns, parts) {
+            Some((item, [member])) => item.entity()
+                .get_children()
+                .iter()
+                .any(|c| c.get_name().is_some_and(|n| n == *member) && !is_member_shown(c, builder)),
+            Some(_) => false,
+            None => parts.split_last().is_some_and(|(last, path)| match path {
+                [] => ns.hidden.contains(*last),
+                path => lookup(ns, path).is_some_and(|(item, rest)| rest.is_empty() && matches!(
+                    item, CppItem::Namespace(inner) if inner.hidden.contains(*last)
+                )),
+            }),
+        };
+        here || ns.entries.values().any(|e| match e {
+            CppItem::Namespace(inner) => hidden_in(inner, parts, builder),
+            _ => false,
+        })
+    }
+    hidden_in(&builder.root, &ref_parts(name), builder)
+}
+
+fn fmt_ref(builder: &Builder, target: &str, label: Option<&str>, location: Option<&str>) -> String {
+    let label = label.map(|l| l.trim()).filter(|l| !l.is_empty()).unwrap_or(target);
+    if let Some(url) = resolve_ref(builder, target) {
+        format!("[{label}]({url})")
+    }
+    else if is_hidden_ref(builder, target) {
+        label.to_owned()
+    }
+    else {
+        builder.diagnostics.warn_at(Category::Links, location, format!("Unable to resolve reference to {target}"));
+        label.to_owned()
+    }
+}
+
+/// Replace explicit references like `@ref ns::Class`, `{@link ns::Class text}`,
+/// and `[text](@ref ns::Class)` with markdown links to the referenced entity.
+/// `location` is where the text is, for warnings about broken references
+pub fn fmt_refs(builder: &Builder, text: &str, location: Option<&str>) -> String {
+    let is_name_char = |c: char| c.is_alphanumeric() || "_:~".contains(c);
+
+    let mut res = String::new();
+    let mut rest = text;
+    while let Some(ix) = rest.find('@') {
+        let (before, after) = rest.split_at(ix);
+        // {@link Target optional label}
+        if before.ends_with('{')
+            && let Some(body) = after.strip_prefix("@link")
+            && let Some(end) = body.find('}')
+        {
+            let body_text = body[..end].trim();
+            let (target, label) = body_text.split_once(char::is_whitespace)
+                .map(|(t, l)| (t, Some(l)))
+                .unwrap_or((body_text, None));
+            res += &before[..before.len() - 1];
+            res += &fmt_ref(builder, target, label, location);
+            rest = &body[end + 1..];
+        }
+        // [label](@ref Target)
+        else if before.ends_with("](")
+            && let Some(open) = before.rfind('[')
+            && let Some(body) = after.strip_prefix("@ref")
+            && let Some(end) = body.find(')')
+        {
+            let label = &before[open + 1..before.len() - 2];
+            res += &before[..open];
+            res += &fmt_ref(builder, body[..end].trim(), Some(label), location);
+            rest = &body[end + 1..];
+        }
+        // @ref Target
+        else if let Some(body) = after.strip_prefix("@ref")
+            && body.starts_with(char::is_whitespace)
+        {
+            let body = body.trim_start();
+            let mut end = body.find(|c: char| !is_name_char(c)).unwrap_or(body.len());
+            // `@ref func()` names a function, other parentheses are part
+            // of the text like in `(see @ref Class)`
+            if body[end..].starts_with("()") {
+                end += 2;
+            }
+            res += before;
+            res += &fmt_ref(builder, &body[..end], None, location);
+            rest = &body[end..];
+        }
+        else {
+            res += before;
+            res.push('@');
+            rest = &after[1..];
+        }
+    }
+    res += rest;
+    res
+}
+
 pub fn fmt_emoji(text: &CowStr) -> String {
     fn eat_emoji<'e>(iter: &mut MultiPeek<Chars>) -> Option<&'e str> {
         let mut buffer = String::new();
@@ -551,6 +1624,83 @@ pub fn fmt_emoji(text: &CowStr) -> String {
     res
 }
 
+/// Collect the functions declared directly in `entity` by name. Redeclarations
+/// share a USR, so only distinct functions are counted as overloads
+fn collect_functions(entity: &Entity, out: &mut HashMap<String, HashSet<String>>) {
+    for child in entity.get_children() {
+        if matches!(
+            child.get_kind(),
+            EntityKind::Method | EntityKind::FunctionDecl | EntityKind::FunctionTemplate
+        ) && let Some(name) = child.get_name() && let Some(usr) = child.get_usr() {
+            out.entry(name).or_default().insert(usr.0);
+        }
+    }
+}
+
+/// Collect the functions of every declaration of the namespace with the USR
+/// `usr` nested in `entity`
+fn collect_namespace_functions(entity: &Entity, usr: &str, out: &mut HashMap<String, HashSet<String>>) {
+    for child in entity.get_children() {
+        if child.get_kind() != EntityKind::Namespace || child.is_in_system_header() {
+            continue;
+        }
+        if child.get_usr().is_some_and(|u| u.0 == usr) {
+            collect_functions(&child, out);
+        }
+        else {
+            collect_namespace_functions(&child, usr, out);
+        }
+    }
+}
+
+/// The number of distinct functions called `name` in `parent`. Namespaces can
+/// be reopened any number of times, so the functions of all of their
+/// declarations in the translation unit are counted. These are cached per
+/// namespace since finding them means walking the whole translation unit
+fn overload_count(parent: &Entity, name: &str) -> usize {
+    type Functions = HashMap<String, HashSet<String>>;
+    static NAMESPACES: OnceLock<Mutex<HashMap<(String, String), Arc<Functions>>>> = OnceLock::new();
+
+    let mut functions = Functions::new();
+    if parent.get_kind() != EntityKind::Namespace || parent.get_usr().is_none() {
+        collect_functions(parent, &mut functions);
+        return functions.get(name).map(|f| f.len()).unwrap_or(0);
+    }
+    let usr = parent.get_usr().unwrap().0;
+    let unit = parent.get_translation_unit().get_entity();
+    let key = (unit.get_name().unwrap_or_default(), usr.clone());
+    let cache = NAMESPACES.get_or_init(Default::default);
+    let cached = cache.lock().unwrap().get(&key).cloned();
+    let functions = match cached {
+        Some(functions) => functions,
+        None => {
+            collect_namespace_functions(&unit, &usr, &mut functions);
+            let functions = Arc::new(functions);
+            cache.lock().unwrap().insert(key, functions.clone());
+            functions
+        }
+    };
+    functions.get(name).map(|f| f.len()).unwrap_or(0)
+}
+
+/// Get the anchor for a function. Overloaded functions get a hash of their
+/// signature appended so every overload has a unique anchor
 pub fn member_fun_link(entity: &Entity) -> Option<String> {
-    Some(entity.get_name()?)
+    let name = entity.get_name()?;
+    let is_overloaded = entity.get_semantic_parent()
+        .is_some_and(|parent| overload_count(&parent, &name) > 1);
+    if !is_overloaded {
+        return Some(name);
+    }
+    let signature = entity.get_type()
+        .map(|t| t.get_display_name())
+        .unwrap_or_default();
+    Some(format!("{name}-{:08x}", stable_hash(signature.as_bytes()) as u32))
+}
+
+/// FNV-1a hash, used where the hash has to stay the same between builds
+pub fn stable_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
 }
@@ -0,0 +1,289 @@
+use clang::{Accessibility, Entity, EntityKind};
+
+use super::{attributes::cpp_attributes, shared::{field_qualifiers, join_tokens}};
+
+/// Get the template parameters of a template as plain text, like
+/// `template <typename T, int N = 5> `
+fn template_prefix(entity: &Entity) -> String {
+    let params = entity.get_children()
+        .into_iter()
+        .filter(|c| matches!(
+            c.get_kind(),
+            EntityKind::TemplateTypeParameter
+                | EntityKind::NonTypeTemplateParameter
+                | EntityKind::TemplateTemplateParameter
+        ))
+        .filter_map(|c| Some(join_tokens(&c.get_range()?.tokenize())))
+        .collect::<Vec<_>>();
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("template <{}> ", params.join(", "))
+    }
+}
+
+/// Get the specifiers written before a function's name that aren't part of 
+/// its type, like `explicit` and `constexpr`
+pub fn function_specifiers(fun: &Entity) -> Vec<String> {
+    let (Some(name), Some(range)) = (fun.get_name(), fun.get_range()) else {
+        return Vec::new();
+    };
+    let tokens = range.tokenize()
+        .into_iter()
+        .map(|t| t.get_spelling())
+        .take_while(|t| *t != name && t != "operator" && t != "~")
+        .collect::<Vec<_>>();
+    tokens.into_iter().filter(|t| matches!(
+        t.as_str(), "explicit" | "constexpr" | "consteval"
+    )).collect()
+}
+
+/// Get the `override` and `final` of a member function, which are written 
+/// after its parameters and aren't part of its type either
+pub fn virt_specifiers(fun: &Entity) -> Vec<String> {
+    let (Some(name), Some(range), Some(location)) = (fun.get_name(), fun.get_range(), fun.get_location()) else {
+        return Vec::new();
+    };
+    // Start from the name, since the return type and attributes before it
+    // can have parentheses too. Operator names are more than one token, so
+    // they're matched by their spelling without spaces
+    let name_offset = location.get_file_location().offset;
+    let name = name.split_whitespace().collect::<String>();
+    let mut spelled = String::new();
+    let tokens = range.tokenize()
+        .into_iter()
+        .skip_while(|t| t.get_range().get_start().get_file_location().offset < name_offset)
+        .map(|t| t.get_spelling())
+        .skip_while(|t| {
+            if spelled == name {
+                return false;
+            }
+            spelled += t;
+            true
+        });
+
+    // The specifiers come after the parentheses of the parameter list
+    let mut res = Vec::new();
+    let mut depth = 0;
+    let mut after_params = false;
+    for token in tokens {
+        match token.as_str() {
+            "{" | ";" => break,
+            "=" | ":" if depth == 0 && after_params => break,
+            "(" | "[" => depth += 1,
+            ")" | "]" => {
+                depth -= 1;
+                after_params |= depth == 0;
+            }
+            "override" | "final" if after_params && depth == 0 => res.push(token),
+            _ => {}
+        }
+    }
+    res
+}
+
+/// Whether a class is declared `final`, in which case nothing can inherit 
+/// from it
+pub fn is_final_class(class: &Entity) -> bool {
+    let (Some(name), Some(range)) = (class.get_name(), class.get_range()) else {
+        return false;
+    };
+    range.tokenize()
+        .into_iter()
+        .map(|t| t.get_spelling())
+        .skip_while(|t| *t != name)
+        .take_while(|t| !matches!(t.as_str(), "{" | ";" | ":"))
+        .any(|t| t == "final")
+}
+
+/// Whether a function can throw according to its exception specification
+#[derive(Clone, PartialEq)]
+pub enum ExceptionSpec {
+    /// `noexcept`, `noexcept(true)` or `throw()`, or a destructor without 
+    /// `noexcept(false)`
+    Noexcept,
+    /// `noexcept(condition)`
+    Conditional(String),
+    MayThrow,
+}
+
+/// Get the exception specification of a function from the spelling of its 
+/// type
+pub fn exception_spec(fun: &Entity) -> ExceptionSpec {
+    let qualifiers = function_qualifiers(fun);
+    if let Some(ix) = qualifiers.find("noexcept") {
+        let rest = qualifiers[ix + "noexcept".len()..].trim_start();
+        let Some(cond) = rest.strip_prefix('(') else {
+            return ExceptionSpec::Noexcept;
+        };
+        let mut depth = 1;
+        let end = cond.char_indices().find_map(|(i, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            (depth == 0).then_some(i)
+        });
+        return match cond[..end.unwrap_or(cond.len())].trim() {
+            "true" => ExceptionSpec::Noexcept,
+            "false" => ExceptionSpec::MayThrow,
+            cond => ExceptionSpec::Conditional(cond.to_owned()),
+        };
+    }
+    if qualifiers.contains("throw()") || fun.get_kind() == EntityKind::Destructor {
+        ExceptionSpec::Noexcept
+    } else {
+        ExceptionSpec::MayThrow
+    }
+}
+
+/// Get what comes after the parameters of a function, like `const &`
+/// or `noexcept`, from the spelling of its type
+pub fn function_qualifiers(fun: &Entity) -> String {
+    let ty = fun.get_type().map(|t| t.get_display_name()).unwrap_or_default();
+    let ret = fun.get_result_type().map(|t| t.get_display_name()).unwrap_or_default();
+    let params = ty.strip_prefix(&ret).unwrap_or(&ty).trim_start();
+    let mut depth = 0;
+    for (i, c) in params.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return params[i + 1..].trim().to_owned();
+                }
+            }
+            _ => {}
+        }
+    }
+    String::new()
+}
+
+fn fun_signature(fun: &Entity) -> String {
+    let mut res = template_prefix(fun);
+    for attr in cpp_attributes(fun).into_iter().filter(|a| a.starts_with("[[")) {
+        res += &attr;
+        res.push(' ');
+    }
+    for specifier in function_specifiers(fun) {
+        res += &specifier;
+        res.push(' ');
+    }
+    if fun.is_static_method() {
+        res += "static ";
+    }
+    if fun.is_virtual_method() {
+        res += "virtual ";
+    }
+    // Constructors and destructors don't have a return type worth showing
+    if !matches!(fun.get_kind(), EntityKind::Constructor | EntityKind::Destructor)
+        && let Some(ret) = fun.get_result_type()
+    {
+        res += &ret.get_display_name();
+        res.push(' ');
+    }
+    res += &fun.get_name().unwrap_or("_anon".into());
+    res.push('(');
+    res += &fun.get_arguments()
+        .unwrap_or_default()
+        .iter()
+        .map(|arg| {
+            let ty = arg.get_type().map(|t| t.get_display_name()).unwrap_or_default();
+            match arg.get_display_name() {
+                Some(name) => format!("{ty} {name}"),
+                None => ty,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    res.push(')');
+    let qualifiers = function_qualifiers(fun);
+    if !qualifiers.is_empty() {
+        res.push(' ');
+        res += &qualifiers;
+    }
+    for specifier in virt_specifiers(fun) {
+        res.push(' ');
+        res += &specifier;
+    }
+    if fun.is_pure_virtual_method() {
+        res += " = 0";
+    }
+    res
+}
+
+fn classlike_signature(class: &Entity, keyword: &str) -> String {
+    let mut res = format!(
+        "{}{keyword} {}",
+        template_prefix(class),
+        class.get_name().unwrap_or("_anon".into())
+    );
+    if is_final_class(class) {
+        res += " final";
+    }
+    let bases = class.get_children()
+        .into_iter()
+        .filter(|c| c.get_kind() == EntityKind::BaseSpecifier)
+        .filter_map(|base| {
+            let access = match base.get_accessibility()? {
+                Accessibility::Public => "public",
+                Accessibility::Protected => "protected",
+                Accessibility::Private => "private",
+            };
+            Some(format!("{access} {}", base.get_type()?.get_display_name()))
+        })
+        .collect::<Vec<_>>();
+    if !bases.is_empty() {
+        res += " : ";
+        res += &bases.join(", ");
+    }
+    res
+}
+
+/// Get the declaration of an entity as plain text up to its body, from its
+/// tokens
+fn declaration_tokens(entity: &Entity) -> Option<String> {
+    let tokens = entity.get_range()?.tokenize();
+    let end = tokens.iter()
+        .position(|t| matches!(t.get_spelling().as_str(), "{" | ";"))
+        .unwrap_or(tokens.len());
+    Some(join_tokens(&tokens[..end]))
+}
+
+/// Get the canonical declaration of an entity as plain text, with template
+/// parameters, qualifiers and parameters, like
+/// `template <typename T> static T add(T a, T b) noexcept`. This is
+/// included in each page's `metadata.json` so tools don't have to parse the
+/// HTML to show signatures
+pub fn signature(entity: &Entity) -> Option<String> {
+    Some(match entity.get_kind() {
+        EntityKind::FunctionDecl
+            | EntityKind::Method
+            | EntityKind::Constructor
+            | EntityKind::Destructor
+            | EntityKind::ConversionFunction
+            | EntityKind::FunctionTemplate => fun_signature(entity),
+        EntityKind::ClassDecl => classlike_signature(entity, "class"),
+        EntityKind::StructDecl => classlike_signature(entity, "struct"),
+        EntityKind::ClassTemplate => classlike_signature(
+            entity,
+            match entity.get_template_kind() {
+                Some(EntityKind::StructDecl) => "struct",
+                _ => "class",
+            },
+        ),
+        EntityKind::FieldDecl | EntityKind::VarDecl => {
+            let mut res = field_qualifiers(entity).join(" ");
+            if !res.is_empty() {
+                res.push(' ');
+            }
+            res += &entity.get_type()?.get_display_name();
+            res.push(' ');
+            res += &entity.get_name()?;
+            res
+        }
+        EntityKind::Namespace => format!("namespace {}", entity.get_name()?),
+        _ => declaration_tokens(entity)?,
+    })
+}
@@ -0,0 +1,71 @@
+use std::{fs, path::Path};
+
+use super::builder::Builder;
+
+/// Get the code block language for a file from its name
+fn language_for(path: &Path) -> String {
+    if path.file_name().is_some_and(|n| n == "CMakeLists.txt") {
+        return "cmake".into();
+    }
+    match path.extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+        Some("c" | "cc" | "cpp" | "cxx" | "h" | "hh" | "hpp" | "hxx" | "inl") => "cpp".into(),
+        Some(ext) => ext.into(),
+        None => "none".into(),
+    }
+}
+
+/// Remove the indentation every line of a snippet has in common
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines.iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines.iter()
+        .map(|l| l.get(indent..).unwrap_or_default().trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Load code from a file in the project for `@snippet` and `@include`. The
+/// path is relative to the project root. If `lines` is given (like `10-42`
+/// or `7`), only those lines are included, and if `tag` is given, only the
+/// lines between the two lines with a `[tag]` marker in them (like
+/// `//! [tag]`). Returns the code and its language
+pub fn load_snippet(
+    builder: &Builder, path: &str, tag: Option<&str>, lines: Option<&str>
+) -> Result<(String, String), String> {
+    let file = builder.config.input_dir.join(path);
+    let data = fs::read_to_string(&file)
+        .map_err(|e| format!("Unable to read snippet file '{path}': {e}"))?;
+    let all = data.lines().collect::<Vec<_>>();
+
+    let selected = if let Some(lines) = lines {
+        let (start, end) = lines.split_once('-').unwrap_or((lines, lines));
+        let parse = |n: &str| n.trim().parse::<usize>()
+            .map_err(|_| format!("Invalid line range '{lines}' for snippet '{path}'"));
+        let (start, end) = (parse(start)?.max(1), parse(end)?.min(all.len()));
+        if start > end {
+            return Err(format!("Line range '{lines}' is outside of snippet file '{path}'"));
+        }
+        &all[start - 1..end]
+    }
+    else if let Some(tag) = tag {
+        let marker = format!("[{tag}]");
+        let markers = all.iter()
+            .enumerate()
+            .filter(|(_, l)| l.contains(&marker))
+            .map(|(i, _)| i)
+            .take(2)
+            .collect::<Vec<_>>();
+        let [start, end] = markers[..] else {
+            return Err(format!("Unable to find two '{marker}' markers in snippet file '{path}'"));
+        };
+        &all[start + 1..end]
+    }
+    else {
+        &all[..]
+    };
+
+    Ok((dedent(selected), language_for(&file)))
+}
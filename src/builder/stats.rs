@@ -0,0 +1,77 @@
+use std::{
+    fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use indicatif::ProgressBar;
+
+use crate::log::{self, Category};
+
+use super::{builder::Builder, namespace::CppItemKind, traits::ASTEntry};
+
+/// Keeps track of how long each phase of the docs build took
+#[derive(Default)]
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+    current: Option<(&'static str, Instant)>,
+}
+
+impl Timings {
+    /// Finish the current phase (if any) and start a new one
+    pub fn phase(&mut self, name: &'static str, pbar: &ProgressBar) {
+        self.finish();
+        pbar.set_message(name);
+        self.current = Some((name, Instant::now()));
+    }
+
+    pub fn finish(&mut self) {
+        if let Some((name, start)) = self.current.take() {
+            log::debug(Category::Timing, format!("{name} took {:.2}s", start.elapsed().as_secs_f64()));
+            self.phases.push((name, start.elapsed()));
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut res = String::from("Build phases:\n");
+        for (name, time) in &self.phases {
+            res += &format!("  {name:<20} {:.2}s\n", time.as_secs_f64());
+        }
+        res
+    }
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+fn fmt_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1048575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1048576.0),
+    }
+}
+
+/// Summary of what was generated, printed at the end of the build
+pub fn fmt_stats<'e>(builder: &'e Builder<'e>, pages: usize) -> String {
+    let count = |kinds: &[CppItemKind]| builder.root
+        .get(&|e| CppItemKind::from(e.entity()).is_some_and(|k| kinds.contains(&k)))
+        .len();
+    format!(
+        "Built {} classes, {} functions and {} pages ({} total)\n",
+        count(&[CppItemKind::Class, CppItemKind::Struct]),
+        count(&[CppItemKind::Function]),
+        pages,
+        fmt_size(dir_size(&builder.config.output_dir).unwrap_or(0)),
+    )
+}
@@ -1,20 +1,33 @@
 
 use std::sync::Arc;
-use crate::{html::Html, url::UrlPath};
+use crate::{config::Config, html::Html, url::UrlPath};
 use clang::Entity;
 use super::{
     traits::{ASTEntry, BuildResult, EntityMethods, Entry, NavItem, OutputEntry, SubItem},
     builder::Builder,
-    shared::output_classlike,
+    member::build_member_pages,
+    namespace::{load_nested, CppItem},
+    shared::{fmt_specializations, output_classlike},
+    signature::signature,
 };
 
 pub struct Struct<'e> {
     entity: Entity<'e>,
+    specializations: Vec<Entity<'e>>,
+    nested: Vec<CppItem<'e>>,
 }
 
 impl<'e> Struct<'e> {
-    pub fn new(entity: Entity<'e>) -> Self {
-        Self { entity }
+    pub fn new(entity: Entity<'e>, config: Arc<Config>) -> Self {
+        Self { entity, specializations: Vec::new(), nested: load_nested(&entity, config) }
+    }
+
+    pub fn add_specialization(&mut self, spec: Entity<'e>) {
+        self.specializations.push(spec);
+    }
+
+    pub fn nested(&self) -> &[CppItem<'e>] {
+        &self.nested
     }
 }
 
@@ -26,11 +39,16 @@ impl<'e> Entry<'e> for Struct<'e> {
     }
 
     fn url(&self) -> UrlPath {
-        self.entity.rel_docs_url().expect("Unable to get struct URL")
+        self.entity.page_url()
     }
 
     fn build(&self, builder: &Builder<'e>) -> BuildResult {
-        builder.create_output_for(self)
+        let mut handles = builder.create_output_for(self)?;
+        handles.extend(build_member_pages(&self.entity, builder)?);
+        for nested in &self.nested {
+            handles.extend(nested.build(builder)?);
+        }
+        Ok(handles)
     }
 
     fn nav(&self) -> NavItem {
@@ -53,13 +71,22 @@ impl<'e> ASTEntry<'e> for Struct<'e> {
 
 impl<'e> OutputEntry<'e> for Struct<'e> {
     fn output(&self, builder: &Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>) {
-        (
-            builder.config.templates.struct_.clone(),
-            output_classlike(self, builder),
-        )
+        let mut vars = output_classlike(self, builder);
+        vars.push(("specializations", fmt_specializations(&self.specializations, builder)));
+        // C structs have no member functions, access or inheritance to show
+        let template = if builder.config.analysis.c {
+            &builder.config.templates.c_struct
+        } else {
+            &builder.config.templates.struct_
+        };
+        (template.clone(), vars)
     }
 
     fn description(&self, builder: &'e Builder<'e>) -> String {
         self.output_description(builder)
     }
+
+    fn signatures(&self) -> Vec<String> {
+        signature(&self.entity).into_iter().collect()
+    }
 }
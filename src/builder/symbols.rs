@@ -0,0 +1,76 @@
+use std::fs;
+
+use clang::Entity;
+
+use super::{
+    builder::Builder,
+    namespace::CppItem,
+    shared::{is_member_shown, member_fun_link},
+    traits::{get_member_functions, ASTEntry, Access, EntityMethods, Include},
+};
+
+/// Full name, URL and kind of something that can be opened from the
+/// quick-open palette
+pub type Symbol = (String, String, &'static str);
+
+fn symbol<'e>(entity: &Entity<'e>, builder: &Builder, kind: &'static str) -> Option<(Entity<'e>, Symbol)> {
+    Some((*entity, (
+        entity.full_name().join("::"),
+        entity.abs_docs_url(builder.config.clone())?.to_string(),
+        kind,
+    )))
+}
+
+fn collect<'e>(item: &CppItem<'e>, builder: &Builder, out: &mut Vec<(Entity<'e>, Symbol)>) {
+    match item {
+        CppItem::Namespace(ns) => {
+            out.extend(symbol(item.entity(), builder, "namespace"));
+            for entry in ns.entries.values() {
+                collect(entry, builder, out);
+            }
+        }
+        CppItem::Class(_) | CppItem::Struct(_) => {
+            let kind = if matches!(item, CppItem::Class(_)) { "class" } else { "struct" };
+            let Some((entity, (name, url, kind))) = symbol(item.entity(), builder, kind) else {
+                return;
+            };
+            for fun in get_member_functions(item.entity(), Access::All, Include::All) {
+                if !is_member_shown(&fun, builder) {
+                    continue;
+                }
+                if let Some(member) = fun.get_name() && let Some(link) = member_fun_link(&fun) {
+                    out.push((fun, (format!("{name}::{member}"), format!("{url}#{link}"), "function")));
+                }
+            }
+            out.push((entity, (name, url, kind)));
+            for nested in item.nested() {
+                collect(nested, builder, out);
+            }
+        }
+        CppItem::Function(_) => out.extend(symbol(item.entity(), builder, "function")),
+        CppItem::Concept(_) => out.extend(symbol(item.entity(), builder, "concept")),
+    }
+}
+
+/// All documented entities including member functions with their symbols
+pub fn all_symbols<'e>(builder: &Builder<'e>) -> Vec<(Entity<'e>, Symbol)> {
+    let mut symbols = Vec::new();
+    for entry in builder.root.entries.values() {
+        collect(entry, builder, &mut symbols);
+    }
+    symbols
+}
+
+/// All documented entities including member functions, for the quick-open
+/// palette. Each symbol is a `[name, url, kind]` array to keep the file small
+pub fn symbols_json(builder: &Builder) -> Result<String, String> {
+    let mut symbols = all_symbols(builder).into_iter().map(|(_, s)| s).collect::<Vec<_>>();
+    symbols.sort();
+    symbols.dedup();
+    serde_json::to_string(&symbols).map_err(|e| format!("Unable to serialize symbols: {e}"))
+}
+
+pub fn build_symbols(builder: &Builder) -> Result<(), String> {
+    fs::write(builder.config.output_dir.join("symbols.json"), symbols_json(builder)?)
+        .map_err(|e| format!("Unable to save symbols.json: {e}"))
+}
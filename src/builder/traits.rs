@@ -10,7 +10,7 @@ use crate::{
     url::UrlPath,
 };
 
-use super::{namespace::CppItemKind, builder::Builder, shared::member_fun_link};
+use super::{concept::concept_name, namespace::CppItemKind, builder::Builder, objc::objc_docs_url, shared::member_fun_link};
 
 pub trait EntityMethods<'e> {
     /// Get the config source for this entity
@@ -25,12 +25,25 @@ pub trait EntityMethods<'e> {
     /// Get the relative for this entity
     fn rel_docs_url(&self) -> Option<UrlPath>;
 
+    /// Get the URL of this entity's page relative to docs root. Entities 
+    /// that don't have a docs category get a URL from their name so one odd 
+    /// declaration doesn't stop the build
+    fn page_url(&self) -> UrlPath;
+
     /// Get the full URL for this entity, valid for links
     fn abs_docs_url(&self, config: Arc<Config>) -> Option<UrlPath>;
 
+    /// Get the URL of this entity in the external docs configured in 
+    /// external-docs, if it's a foreign entity
+    fn external_docs_url(&self, config: Arc<Config>) -> Option<String>;
+
     /// Get the full online URL of this entity
     fn github_url(&self, config: Arc<Config>) -> Option<String>;
 
+    /// Get the URL of this entity's definition line in the source code 
+    /// browser, if source pages are enabled
+    fn source_url(&self, config: Arc<Config>) -> Option<String>;
+
     /// Get the include path for this entity
     fn include_path(&self, config: Arc<Config>) -> Option<UrlPath>;
 
@@ -71,43 +84,78 @@ impl<'e> EntityMethods<'e> for Entity<'e> {
     }
 
     fn rel_docs_url(&self) -> Option<UrlPath> {
-        Some(
-            CppItemKind::from(self)?
-                .docs_category()
-                .join(UrlPath::new_with_path(self.full_name()))
-        )
+        let Some(kind) = CppItemKind::from(self) else {
+            return objc_docs_url(self);
+        };
+        Some(kind.docs_category().join(UrlPath::new_with_path(self.full_name())))
+    }
+
+    fn page_url(&self) -> UrlPath {
+        self.rel_docs_url().unwrap_or_else(|| UrlPath::new_with_path(self.full_name()))
     }
 
     fn abs_docs_url(&self, config: Arc<Config>) -> Option<UrlPath> {
-        // If this is an std item, redirect to cppreference instead
+        // The standard library isn't documented here, it links to 
+        // cppreference as external docs instead
         if self.full_name().first().is_some_and(|n| n == "std") {
-            UrlPath::parse(&format!(
-                "en.cppreference.com/w/cpp/{}/{}",
-                self.definition_file()?.file_name()?.to_str()?,
-                self.get_name()?
-            ))
-            .ok()
+            None
         } else {
             Some(self.rel_docs_url()?.to_absolute(config))
         }
     }
 
+    fn external_docs_url(&self, config: Arc<Config>) -> Option<String> {
+        // Entities from our own sources are never external
+        if self.config_source(config.clone()).is_some() {
+            return None;
+        }
+        let name = self.full_name();
+        if name.first().is_some_and(|n| n == "std") {
+            return config.std_docs_url(&name);
+        }
+        config.external_docs.iter().find_map(|ext| ext.url_for(&name))
+    }
+
     fn github_url(&self, config: Arc<Config>) -> Option<String> {
         // If this is an std item, redirect to cppreference instead
         if self.full_name().first().is_some_and(|n| n == "std") {
-            Some(format!(
-                "https://en.cppreference.com/w/cpp/{}/{}",
-                self.definition_file()?.file_name()?.to_str()?,
-                self.get_name()?
-            ))
+            config.std_docs_url(&self.full_name())
         } else {
-            Some(
-                config.project.tree.clone()?
-                    + &UrlPath::try_from(&self.header(config)?).ok()?.to_string(),
-            )
+            let url = config.project.tree.clone()?
+                + &UrlPath::try_from(&self.header(config)?).ok()?.to_string();
+
+            // Link to the exact lines of the definition if possible
+            let Some(range) = self.get_definition().unwrap_or(*self).get_range() else {
+                return Some(url);
+            };
+            let start = range.get_start().get_file_location().line;
+            let end = range.get_end().get_file_location().line;
+            if end > start {
+                Some(format!("{url}#L{start}-L{end}"))
+            } else {
+                Some(format!("{url}#L{start}"))
+            }
         }
     }
 
+    fn source_url(&self, config: Arc<Config>) -> Option<String> {
+        if !config.docs.source_pages {
+            return None;
+        }
+        let line = self.get_definition()
+            .unwrap_or(*self)
+            .get_location()?
+            .get_file_location()
+            .line;
+        Some(format!(
+            "{}#L{line}",
+            UrlPath::part("files")
+                .join(self.include_path(config.clone())?)
+                .join(UrlPath::part("source"))
+                .to_absolute(config)
+        ))
+    }
+
     fn include_path(&self, config: Arc<Config>) -> Option<UrlPath> {
         UrlPath::try_from(&self.header(config.clone())?)
             .ok()?
@@ -118,7 +166,7 @@ impl<'e> EntityMethods<'e> for Entity<'e> {
     fn full_name(&self) -> Vec<String> {
         self.ancestorage()
             .iter()
-            .map(|a| a.get_name().unwrap_or("_anon".into()))
+            .map(|a| a.get_name().or_else(|| concept_name(a)).unwrap_or("_anon".into()))
             .collect()
     }
 
@@ -167,7 +215,7 @@ impl SubItem {
                     .collect()
             }
 
-            CppItemKind::Namespace | CppItemKind::Function => Vec::new()
+            CppItemKind::Namespace | CppItemKind::Function | CppItemKind::Concept => Vec::new()
         }
     }
 }
@@ -205,6 +253,16 @@ impl NavItem {
         NavItem::Root(name.map(|s| s.into()), items)
     }
 
+    /// URLs of all the links in this item, in the order they are shown
+    pub fn links(&self) -> Vec<UrlPath> {
+        match self {
+            NavItem::Link(_, url, _, _) => vec![url.clone()],
+            NavItem::Dir(_, items, _, _) | NavItem::Root(_, items) => items.iter()
+                .flat_map(|i| i.links())
+                .collect(),
+        }
+    }
+
     pub fn suboptions_titles(&self, config: Arc<Config>) -> HashMap<String, usize> {
         match self {
             NavItem::Link(name, _, _, suboptions) => {
@@ -237,10 +295,6 @@ impl NavItem {
             NavItem::Link(name, url, icon, _) => {
                 HtmlList::new(vec![
                     HtmlElement::new("a")
-                        .with_attr(
-                            "onclick",
-                            format!("return navigate('{}')", url.to_absolute(config.clone())),
-                        )
                         .with_attr("href", url.to_absolute(config.clone()))
                         .with_child_opt(icon.as_ref().map(|i| {
                             HtmlElement::new("i")
@@ -311,6 +365,10 @@ pub trait Entry<'e> {
 pub trait OutputEntry<'e>: Entry<'e> {
     fn output(&self, builder: &'e Builder<'e>) -> (Arc<String>, Vec<(&'static str, Html)>);
     fn description(&self, builder: &'e Builder<'e>) -> String;
+    /// Plain-text signatures of what this page documents, for metadata.json
+    fn signatures(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 pub trait ASTEntry<'e>: Entry<'e> {
@@ -330,6 +388,7 @@ pub enum Access {
     All,
     Public,
     Protected,
+    Private,
 }
 
 pub enum Include {
@@ -358,6 +417,8 @@ pub fn get_member_functions<'e>(
                     => matches!(visibility, Access::All | Access::Protected),
                     Some(Accessibility::Public)
                     => matches!(visibility, Access::All | Access::Public),
+                    Some(Accessibility::Private)
+                    => matches!(visibility, Access::Private),
                     _ => false,
                 }
         })
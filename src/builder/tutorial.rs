@@ -3,29 +3,66 @@ use crate::{
     html::{Html, HtmlElement},
     url::UrlPath,
 };
-use std::{collections::HashMap, ffi::OsStr, fs, path::PathBuf, sync::Arc, cmp::Ordering};
+use std::{collections::HashMap, ffi::OsStr, fs, path::{Path, PathBuf}, sync::Arc, cmp::Ordering};
 
 use super::{
     traits::{BuildResult, Entry, NavItem, OutputEntry},
     builder::Builder,
-    shared::fmt_section,
+    shared::{fmt_autolinks, fmt_breadcrumbs, fmt_section},
     markdown::{extract_metadata_from_md, output_tutorial, Metadata},
 };
 
+/// Link to another tutorial page as a (title, url) pair
+type TutorialLink = (String, UrlPath);
+
+/// Get the variant of a markdown file in the configured language, like 
+/// `intro.ja.md` for `intro.md`, if there is one
+fn localized_path(path: &Path, config: &Config) -> PathBuf {
+    let variant = path.with_extension(format!("{}.md", config.language));
+    if variant.is_file() { variant } else { path.to_path_buf() }
+}
+
+/// Whether a markdown file is a language variant of another one, like 
+/// `intro.ja.md` next to `intro.md`
+fn is_language_variant(path: &Path) -> bool {
+    path.file_stem()
+        .map(Path::new)
+        .is_some_and(|stem| stem.extension().is_some() && path.with_file_name(stem.with_extension("md")).is_file())
+}
+
+fn fmt_prev_next(prev: &Option<TutorialLink>, next: &Option<TutorialLink>, config: Arc<Config>) -> Html {
+    let link = |link: &Option<TutorialLink>, class: &str, label: &str| link.as_ref().map(|(name, url)|
+        HtmlElement::new("a")
+            .with_attr("href", url.to_absolute(config.clone()))
+            .with_class(class)
+            .with_child(Html::span(&["label"], label))
+            .with_child(Html::span(&["title"], name))
+    );
+    HtmlElement::new("div")
+        .with_class("tutorial-links")
+        .with_child_opt(link(prev, "prev", config.tr("Previous")))
+        .with_child_opt(link(next, "next", config.tr("Next")))
+        .into()
+}
+
 pub struct Tutorial {
     path: UrlPath,
     metadata: Metadata,
     unparsed_content: String,
+    prev: Option<TutorialLink>,
+    next: Option<TutorialLink>,
+    breadcrumbs: Vec<TutorialLink>,
 }
 
 impl Tutorial {
     pub fn new(config: Arc<Config>, path: UrlPath) -> Self {
-        let unparsed_content = fs::read_to_string(
-            config
+        let unparsed_content = fs::read_to_string(localized_path(
+            &config
                 .input_dir
                 .join(&config.tutorials.as_ref().unwrap().dir)
                 .join(path.to_pathbuf()),
-        )
+            &config,
+        ))
         .unwrap_or_else(|_| panic!("Unable to read tutorial {}", path.to_raw_string()));
 
         Self {
@@ -35,8 +72,21 @@ impl Tutorial {
             ).unwrap(),
             unparsed_content,
             path,
+            prev: None,
+            next: None,
+            breadcrumbs: Vec::new(),
         }
     }
+
+    /// Path of the tutorial's markdown file relative to the tutorials 
+    /// directory
+    pub fn path(&self) -> &UrlPath {
+        &self.path
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
 }
 
 impl<'e> Entry<'e> for Tutorial {
@@ -74,7 +124,11 @@ impl<'e> OutputEntry<'e> for Tutorial {
                 self,
                 builder,
                 &self.unparsed_content,
-                Html::Raw(String::new())
+                &builder.config.input_dir
+                    .join(&builder.config.tutorials.as_ref().unwrap().dir)
+                    .join(self.path.parent().to_pathbuf()),
+                fmt_prev_next(&self.prev, &self.next, builder.config.clone()),
+                fmt_breadcrumbs(&self.breadcrumbs, builder.config.clone()),
             )
         )
     }
@@ -95,6 +149,7 @@ pub struct TutorialFolder {
     index: Option<String>,
     folders: HashMap<String, TutorialFolder>,
     tutorials: HashMap<String, Tutorial>,
+    breadcrumbs: Vec<TutorialLink>,
 }
 
 impl TutorialFolder {
@@ -131,7 +186,9 @@ impl TutorialFolder {
                 match path.file_name().map(|f| f.to_string_lossy().to_lowercase()) {
                     Some(val) => !matches!(val.as_str(), "readme.md" | "index.md"),
                     None => false,
-                }
+                } &&
+                // translations are read in place of the tutorial they translate
+                !is_language_variant(&path)
             {
                 let stripped_path = path
                     .strip_prefix(
@@ -149,7 +206,7 @@ impl TutorialFolder {
         }
 
         let index = if path.join("index.md").exists() {
-            fs::read_to_string(path.join("index.md")).ok()
+            fs::read_to_string(localized_path(&path.join("index.md"), &config)).ok()
         } else {
             None
         };
@@ -163,11 +220,12 @@ impl TutorialFolder {
             index,
             folders,
             tutorials,
+            breadcrumbs: Vec::new(),
         })
     }
 
-    pub fn from_config(config: Arc<Config>) -> Self {
-        if let Some(ref tutorials) = config.tutorials &&
+    pub fn from_config(config: Arc<Config>) -> Result<Self, String> {
+        let mut res = if let Some(ref tutorials) = config.tutorials &&
             let Some(mut res) = Self::from_folder(
                 config.clone(), &config.input_dir.join(&tutorials.dir), 0
             )
@@ -184,7 +242,60 @@ impl TutorialFolder {
                 index: None,
                 folders: HashMap::new(),
                 tutorials: HashMap::new(),
+                breadcrumbs: Vec::new(),
             }
+        };
+
+        // the project readme replaces the tutorial index as the home page
+        if let Some(ref readme) = config.project.readme {
+            let content = fs::read_to_string(localized_path(&config.input_dir.join(readme), &config))
+                .map_err(|e| format!("Unable to read readme {}: {e}", readme.to_string_lossy()))?;
+            res.metadata = extract_metadata_from_md(&content, None);
+            res.index = Some(content);
+        }
+
+        res.link_tutorials(Vec::new(), config.clone());
+        Ok(res)
+    }
+
+    /// Set up the previous / next links between tutorials in the same folder 
+    /// and the breadcrumbs up to the tutorial root
+    fn link_tutorials(&mut self, crumbs: Vec<TutorialLink>, config: Arc<Config>) {
+        self.breadcrumbs = crumbs.clone();
+
+        let mut crumbs = crumbs;
+        crumbs.push((
+            if self.is_root { config.project.name.clone() } else { Entry::name(self) },
+            Entry::url(self),
+        ));
+
+        let order = self.tutorials_sorted()
+            .iter()
+            .map(|t| (Entry::name(*t), Entry::url(*t)))
+            .collect::<Vec<_>>();
+        for (i, (name, _)) in order.iter().enumerate() {
+            let tut = self.tutorials.get_mut(name).unwrap();
+            tut.prev = i.checked_sub(1).map(|i| order[i].clone());
+            tut.next = order.get(i + 1).cloned();
+            tut.breadcrumbs = crumbs.clone();
+        }
+
+        for folder in self.folders.values_mut() {
+            folder.link_tutorials(crumbs.clone(), config.clone());
+        }
+    }
+
+    /// Directory that links in the index page are relative to
+    fn source_dir(&self, config: Arc<Config>) -> PathBuf {
+        match config.project.readme {
+            Some(ref readme) if self.is_root => config.input_dir
+                .join(readme)
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default(),
+            _ => config.input_dir
+                .join(config.tutorials.as_ref().map(|t| t.dir.clone()).unwrap_or_default())
+                .join(self.path.to_pathbuf()),
         }
     }
 
@@ -194,6 +305,14 @@ impl TutorialFolder {
         vec.into_iter().map(|(_, v)| v).collect()
     }
 
+    /// Every tutorial in this folder and its subfolders
+    pub fn all_tutorials(&self) -> Vec<&Tutorial> {
+        self.tutorials_sorted()
+            .into_iter()
+            .chain(self.folders_sorted().into_iter().flat_map(|f| f.all_tutorials()))
+            .collect()
+    }
+
     pub fn tutorials_sorted(&self) -> Vec<&Tutorial> {
         let mut vec = self.tutorials.iter().collect::<Vec<_>>();
         vec.sort_unstable_by(|a, b| {
@@ -274,9 +393,14 @@ impl<'e> OutputEntry<'e> for TutorialFolder {
             output_tutorial(
                 self,
                 builder,
-                self.index.as_ref().map(|s| s.as_str()).unwrap_or(""),
+                &if self.is_root && builder.config.project.readme.is_some() {
+                    fmt_autolinks(builder, self.index.as_deref().unwrap_or(""), None)
+                } else {
+                    self.index.clone().unwrap_or_default()
+                },
+                &self.source_dir(builder.config.clone()),
                 fmt_section(
-                    "Pages",
+                    builder.config.tr("Pages"),
                     self.tutorials_sorted()
                         .iter()
                         .map(|tut| {
@@ -292,7 +416,8 @@ impl<'e> OutputEntry<'e> for TutorialFolder {
                                 .into()
                         })
                         .collect(),
-                )
+                ),
+                fmt_breadcrumbs(&self.breadcrumbs, builder.config.clone()),
             )
         )
     }
@@ -0,0 +1,60 @@
+use serde::Deserialize;
+use std::{path::PathBuf, sync::Arc};
+
+use crate::config::Config;
+
+use super::{compile_args_for, run_output, CompileCommand, CompileCommands};
+
+#[derive(Deserialize)]
+struct Action {
+    #[serde(default)]
+    arguments: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ActionGraph {
+    #[serde(default)]
+    actions: Vec<Action>,
+}
+
+/// Get the compile commands of the configured targets through 
+/// `bazel aquery`. Bazel compiles everything in its execution root, which 
+/// mirrors the workspace layout
+pub fn bazel_compile_commands(config: Arc<Config>) -> Result<CompileCommands, String> {
+    let bazel = config.bazel.as_ref().unwrap();
+    let directory = PathBuf::from(run_output(
+        "bazel",
+        &["info".into(), "execution_root".into()],
+        &config.input_dir,
+        "Bazel info",
+    )?.trim());
+
+    let query = format!("mnemonic(\"CppCompile\", deps({}))", bazel.targets.join(" + "));
+    let graph: ActionGraph = serde_json::from_str(&run_output(
+        "bazel",
+        &["aquery".into(), query, "--output=jsonproto".into()],
+        &config.input_dir,
+        "Bazel aquery",
+    )?)
+    .map_err(|e| format!("Unable to parse Bazel action graph: {e}"))?;
+
+    Ok(graph.actions.into_iter()
+        .filter_map(|action| {
+            let file = action.arguments.iter()
+                .position(|a| a == "-c")
+                .and_then(|ix| action.arguments.get(ix + 1))?
+                .into();
+            Some(CompileCommand {
+                directory: directory.clone(),
+                command: None,
+                arguments: Some(action.arguments),
+                file,
+            })
+        })
+        .collect())
+}
+
+pub fn bazel_compile_args(config: Arc<Config>) -> Result<Vec<String>, String> {
+    let bazel = config.bazel.as_ref().ok_or(String::from("Project does not use Bazel"))?;
+    compile_args_for(config.clone(), bazel_compile_commands(config.clone())?, &bazel.infer_args_from)
+}
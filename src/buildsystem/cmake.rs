@@ -0,0 +1,45 @@
+use std::{fs, sync::Arc};
+
+use crate::config::Config;
+
+use super::{compile_args_for, run, CompileCommands};
+
+pub fn cmake_configure(config: Arc<Config>) -> Result<(), String> {
+    let cmake = config.cmake.as_ref().unwrap();
+    let args = [".".into(), "-B".into(), cmake.build_dir.clone()].into_iter()
+        .chain(cmake.config_args.clone())
+        .collect::<Vec<_>>();
+    run("cmake", &args, &config.input_dir, "CMake configure")
+}
+
+pub fn cmake_build(config: Arc<Config>) -> Result<(), String> {
+    let cmake = config.cmake.as_ref().unwrap();
+    let args = ["--build".into(), cmake.build_dir.clone()].into_iter()
+        .chain(cmake.build_args.clone())
+        .collect::<Vec<_>>();
+    run("cmake", &args, &config.input_dir, "CMake build")
+}
+
+pub fn cmake_compile_commands(config: Arc<Config>) -> Result<CompileCommands, String> {
+    serde_json::from_str(
+        &fs::read_to_string(
+            config
+                .input_dir
+                .join(&config.cmake.as_ref().unwrap().build_dir)
+                .join("compile_commands.json"),
+        )
+        .map_err(|e| format!("Unable to read compile_commands.json: {e}"))?,
+    )
+    .map_err(|e| format!("Unable to parse compile_commands.json: {e}"))
+}
+
+/// Configure (and optionally build) the CMake project and get the compile 
+/// args from its `compile_commands.json`
+pub fn cmake_compile_args(config: Arc<Config>) -> Result<Vec<String>, String> {
+    let cmake = config.cmake.as_ref().ok_or(String::from("Project does not use CMake"))?;
+    cmake_configure(config.clone())?;
+    if cmake.build {
+        cmake_build(config.clone())?;
+    }
+    compile_args_for(config.clone(), cmake_compile_commands(config.clone())?, &cmake.infer_args_from)
+}
@@ -0,0 +1,70 @@
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::config::Config;
+
+use super::{compile_args_for, run, run_output, CompileCommand, CompileCommands};
+
+#[derive(Deserialize)]
+struct TargetSource {
+    #[serde(default)]
+    parameters: Vec<String>,
+    #[serde(default)]
+    sources: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    #[serde(default)]
+    target_sources: Vec<TargetSource>,
+}
+
+/// Set up the Meson build directory if it hasn't been already
+pub fn meson_setup(config: Arc<Config>) -> Result<(), String> {
+    let build_dir = config.meson.as_ref().unwrap().builddir.clone();
+    if config.input_dir.join(&build_dir).join("meson-private").exists() {
+        return Ok(());
+    }
+    let args = ["setup".into(), build_dir].into_iter()
+        .chain(config.meson.as_ref().unwrap().setup_args.clone())
+        .collect::<Vec<_>>();
+    run("meson", &args, &config.input_dir, "Meson setup")
+}
+
+/// Get the compile commands of every source file in the project through 
+/// `meson introspect --targets`
+pub fn meson_compile_commands(config: Arc<Config>) -> Result<CompileCommands, String> {
+    let build_dir = config.meson.as_ref().unwrap().builddir.clone();
+    let targets: Vec<Target> = serde_json::from_str(&run_output(
+        "meson",
+        &["introspect".into(), "--targets".into(), build_dir.clone()],
+        &config.input_dir,
+        "Meson introspect",
+    )?)
+    .map_err(|e| format!("Unable to parse Meson targets: {e}"))?;
+
+    let directory = config.input_dir.join(build_dir);
+    Ok(targets.into_iter()
+        .flat_map(|t| t.target_sources)
+        .flat_map(|src| {
+            // The compiler may be a wrapper like `ccache c++`, so substitute 
+            // it with a single placeholder that gets skipped
+            let arguments = std::iter::once(String::from("c++"))
+                .chain(src.parameters.clone())
+                .collect::<Vec<_>>();
+            let directory = directory.clone();
+            src.sources.into_iter().map(move |file| CompileCommand {
+                directory: directory.clone(),
+                command: None,
+                arguments: Some(arguments.clone()),
+                file: file.into(),
+            })
+        })
+        .collect())
+}
+
+pub fn meson_compile_args(config: Arc<Config>) -> Result<Vec<String>, String> {
+    let meson = config.meson.as_ref().ok_or(String::from("Project does not use Meson"))?;
+    meson_setup(config.clone())?;
+    compile_args_for(config.clone(), meson_compile_commands(config.clone())?, &meson.infer_args_from)
+}
@@ -0,0 +1,134 @@
+use serde::Deserialize;
+use std::{fs, path::{Path, PathBuf}, process::Command, sync::Arc};
+
+use crate::config::Config;
+
+pub mod bazel;
+pub mod cmake;
+pub mod meson;
+
+#[derive(Deserialize, Clone)]
+pub struct CompileCommand {
+    pub directory: PathBuf,
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Already split arguments, used instead of `command` by build systems
+    /// that don't join them into a single string
+    #[serde(default)]
+    pub arguments: Option<Vec<String>>,
+    pub file: PathBuf,
+}
+
+impl CompileCommand {
+    pub fn get_command_list(&self, config: Arc<Config>) -> Vec<String> {
+        let args = match &self.arguments {
+            Some(args) => args.clone(),
+            // Not using shlex because that screws up -DFMT_CONSTEVAL=\"\"
+            None => self.command.as_deref()
+                .unwrap_or_default()
+                .split(' ')
+                .map(|s| s.to_owned())
+                .collect(),
+        };
+        let mut list: Vec<String> = args.into_iter()
+            // Skip clang.exe
+            .skip(1)
+            .flat_map(|s|
+                // Expand .rsp files into their include directives
+                // For some reason LibClang just doesn't want to work with the .rsp 
+                // files so got to do this
+                if s.ends_with(".rsp") {
+                    fs::read_to_string(
+                        self.directory.join(s.replace('@', ""))
+                    ).expect("Unable to read compiler .rsp includes file")
+                        .split(' ')
+                        .map(|s| s.to_owned())
+                        .collect()
+                } else {
+                    // Hacky fix to make sure -DMACRO="" defines MACRO as empty and not as ""
+                    vec![s.replace("=\"\"", "=")]
+                }
+            )
+            // Add header root to include directories
+            .chain(vec![format!("-I{}", config.input_dir.to_str().unwrap())])
+            // Set working directory
+            .chain(vec![format!("-working-directory={}", self.directory.to_str().unwrap())])
+            // Add extra compile args
+            .chain(config.analysis.compile_args.clone())
+            .collect();
+
+        // Passing -c crashes LibClang
+        while let Some(ix) = list.iter().position(|s| s == "-c") {
+            list.drain(ix..(ix + 2).min(list.len()));
+        }
+
+        list
+    }
+}
+
+pub type CompileCommands = Vec<CompileCommand>;
+
+/// Run a build system command, failing if it doesn't exit successfully
+fn run(program: &str, args: &[String], dir: &Path, what: &str) -> Result<(), String> {
+    Command::new(program)
+        .current_dir(dir)
+        .args(args)
+        .spawn()
+        .map_err(|e| format!("Error running {what}: {e}"))?
+        .wait()
+        .unwrap()
+        .success()
+        .then_some(())
+        .ok_or(format!("{what} failed"))
+}
+
+/// Run a build system command and return what it printed
+fn run_output(program: &str, args: &[String], dir: &Path, what: &str) -> Result<String, String> {
+    let output = Command::new(program)
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Error running {what}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("{what} failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+    String::from_utf8(output.stdout).map_err(|e| format!("{what} printed invalid UTF-8: {e}"))
+}
+
+/// Find the compile args used for the file `from` (relative to the input 
+/// directory) in a list of compile commands
+pub fn compile_args_for(
+    config: Arc<Config>,
+    commands: CompileCommands,
+    from: &Path,
+) -> Result<Vec<String>, String> {
+    let path = config.input_dir.join(from);
+    for cmd in commands {
+        let file = cmd.directory.join(&cmd.file);
+        if file == path || file.canonicalize().is_ok_and(|f| path.canonicalize().is_ok_and(|p| f == p)) {
+            return Ok(cmd.get_command_list(config));
+        }
+    }
+    Err(format!("Unable to find compile args for '{}'", path.to_string_lossy()))
+}
+
+/// Get the compile args from the build system the project is configured to 
+/// use, or `None` if it doesn't use one
+pub fn infer_compile_args(config: Arc<Config>) -> Result<Option<Vec<String>>, String> {
+    let systems = [
+        config.cmake.is_some().then_some("cmake"),
+        config.meson.is_some().then_some("meson"),
+        config.bazel.is_some().then_some("bazel"),
+    ];
+    let systems = systems.into_iter().flatten().collect::<Vec<_>>();
+    match systems.as_slice() {
+        [] => Ok(None),
+        ["cmake"] => cmake::cmake_compile_args(config).map(Some),
+        ["meson"] => meson::meson_compile_args(config).map(Some),
+        ["bazel"] => bazel::bazel_compile_args(config).map(Some),
+        _ => Err(format!(
+            "Only one build system can be used at a time, but the project configures {}",
+            systems.join(", ")
+        )),
+    }
+}
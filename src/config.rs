@@ -1,16 +1,25 @@
 use flash_macros::decl_config;
 use glob::glob;
+use regex::Regex;
 use serde::{Deserialize, Deserializer};
-use std::{fs, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::Arc};
 
-use crate::url::UrlPath;
+use crate::{
+    builder::markdown::{parse_definition_lists, DefinitionLine},
+    log::{self, Category},
+    normalize::Normalize,
+    template::expand_includes,
+    url::UrlPath,
+};
 
 fn parse_template<'de, D>(deserializer: D) -> Result<Arc<String>, D::Error>
 where
     D: Deserializer<'de>,
 {
+    let path = PathBuf::deserialize(deserializer)?;
+    let template = fs::read_to_string(&path).map_err(serde::de::Error::custom)?;
     Ok(Arc::from(
-        fs::read_to_string(PathBuf::deserialize(deserializer)?)
+        expand_includes(&template, path.parent().unwrap_or(Path::new("")))
             .map_err(serde::de::Error::custom)?,
     ))
 }
@@ -19,10 +28,20 @@ fn parse_sources<'de, D>(deserializer: D) -> Result<Vec<Arc<Source>>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    Ok(Vec::<RawSource>::deserialize(deserializer)?
+    Vec::<RawSource>::deserialize(deserializer)?
         .into_iter()
-        .map(|src| Arc::from(Source::from_raw(src).unwrap()))
-        .collect())
+        .map(|src| Source::from_raw(src).map(Arc::from).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn parse_external_docs<'de, D>(deserializer: D) -> Result<Vec<Arc<ExternalDocs>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<RawExternalDocs>::deserialize(deserializer)?
+        .into_iter()
+        .map(|ext| ExternalDocs::from_raw(ext).map(Arc::from).map_err(serde::de::Error::custom))
+        .collect()
 }
 
 fn parse_glob<'de, D>(deserializer: D) -> Result<Vec<PathBuf>, D::Error>
@@ -62,11 +81,265 @@ macro_rules! default_scripts {
     };
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    Html,
+    Json,
+    Both,
+}
+
+impl OutputFormat {
+    pub fn has_html(&self) -> bool {
+        matches!(self, Self::Html | Self::Both)
+    }
+
+    pub fn has_json(&self) -> bool {
+        matches!(self, Self::Json | Self::Both)
+    }
+}
+
+/// Whether to generate a regular site or one self-contained HTML file
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputMode {
+    Standard,
+    /// Also bundle every page with its CSS, JS and icons into `offline.html`
+    SingleFile,
+}
+
+/// How page URLs are formed
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UrlScheme {
+    /// `classes/Class`, served from `classes/Class/index.html`
+    Pretty,
+    /// `classes/Class.html`, for hosts that don't serve directory indices
+    File,
+}
+
+/// Case style of page URLs
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UrlCase {
+    /// Entity and file names as written
+    Preserve,
+    /// `classes/myclass`
+    Lower,
+    /// `classes/my-class`
+    Kebab,
+}
+
+/// How links to other pages and assets are written
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkMode {
+    /// `/project/classes/Class`, relative to the site root
+    Absolute,
+    /// `../classes/Class`, relative to the page, so the docs work from any 
+    /// subpath without knowing it
+    Relative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Report {
+    /// Documentation coverage of public classes, functions and fields
+    Coverage,
+}
+
+/// A key in flash.toml. The keys of every config struct are listed by the 
+/// `schema()` generated by `decl_config!`
+pub struct ConfigKey {
+    pub name: &'static str,
+    pub required: bool,
+    /// Whether the value is an array
+    pub list: bool,
+    /// Keys of the table (or array of tables) this key holds
+    pub keys: Option<fn() -> Vec<ConfigKey>>,
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            cur.push((prev[j] + (ca != *cb) as usize).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Paths of every key in the config, like `sources[].include`
+fn all_key_paths(schema: &[ConfigKey], prefix: &str, into: &mut Vec<String>) {
+    for key in schema {
+        let path = format!("{prefix}{}", key.name);
+        if let Some(keys) = key.keys {
+            all_key_paths(&keys(), &format!("{path}{}", if key.list { "[]." } else { "." }), into);
+        }
+        into.push(path);
+    }
+}
+
+/// Suggest what an unknown key was probably meant to be: a similarly 
+/// named key in the same table, or a key with that name elsewhere
+fn suggest_key(name: &str, path: &str, schema: &[ConfigKey]) -> Option<String> {
+    let close = |other: &str| edit_distance(name, other) <= (name.len() / 3).max(1);
+    if let Some(key) = schema.iter().find(|k| close(k.name)) {
+        return Some(format!("`{path}{}`", key.name));
+    }
+    let mut paths = Vec::new();
+    all_key_paths(&Config::schema(), "", &mut paths);
+    let matches = paths.into_iter()
+        .filter(|p| p.rsplit(['.', ']']).next().is_some_and(|last| last == name))
+        .map(|p| format!("`{p}`"))
+        .collect::<Vec<_>>();
+    (!matches.is_empty()).then(|| matches.join(" or "))
+}
+
+fn validate_table(
+    table: &toml::value::Table,
+    schema: &[ConfigKey],
+    path: &str,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) {
+    for key in schema {
+        let key_path = format!("{path}{}", key.name);
+        match (table.get(key.name), key.keys) {
+            (None, _) if key.required => errors.push(format!("Missing required key `{key_path}`")),
+            (Some(toml::Value::Table(value)), Some(keys)) => {
+                validate_table(value, &keys(), &format!("{key_path}."), errors, warnings);
+            }
+            (Some(toml::Value::Array(values)), Some(keys)) => {
+                for (i, value) in values.iter().enumerate() {
+                    match value {
+                        toml::Value::Table(value) => validate_table(
+                            value, &keys(), &format!("{key_path}[{i}]."), errors, warnings
+                        ),
+                        _ => errors.push(format!("`{key_path}[{i}]` should be a table")),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for name in table.keys() {
+        if !schema.iter().any(|k| k.name == name) {
+            warnings.push(match suggest_key(name, path, schema) {
+                Some(suggestion) => format!(
+                    "Unknown key `{path}{name}` in flash.toml, did you mean {suggestion}?"
+                ),
+                None => format!("Unknown key `{path}{name}` in flash.toml"),
+            });
+        }
+    }
+}
+
+/// Make sure the directories and include patterns of sources exist, since 
+/// otherwise the docs would just silently be empty
+fn validate_sources(value: &toml::Value, input_dir: &Path, errors: &mut Vec<String>) {
+    let sources = value.get("sources").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+    for (i, src) in sources.iter().enumerate() {
+        let Some(dir) = src.get("dir").and_then(|d| d.as_str()) else {
+            continue;
+        };
+        let dir = input_dir.join(dir);
+        if !dir.is_dir() {
+            errors.push(format!(
+                "Directory '{}' of `sources[{i}]` doesn't exist", dir.to_string_lossy()
+            ));
+            continue;
+        }
+        let includes = src.get("include").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+        for pattern in includes.iter().filter_map(|p| p.as_str()) {
+            match glob(&dir.join(pattern).to_string_lossy()) {
+                Ok(mut paths) => if paths.next().is_none() {
+                    errors.push(format!(
+                        "Include pattern '{pattern}' of `sources[{i}]` doesn't match any files"
+                    ));
+                },
+                Err(e) => errors.push(format!(
+                    "Invalid include pattern '{pattern}' in `sources[{i}]`: {e}"
+                )),
+            }
+        }
+    }
+}
+
+/// Check flash.toml for missing and unknown keys and sources that don't 
+/// exist before deserializing it, since serde's errors for these are not 
+/// very helpful. Returns the warnings, or all the errors found
+fn validate_config(value: &toml::Value, input_dir: &Path) -> Result<Vec<String>, String> {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    match value {
+        toml::Value::Table(table) => {
+            validate_table(table, &Config::schema(), "", &mut errors, &mut warnings);
+        }
+        _ => errors.push("flash.toml should be a table".into()),
+    }
+    if errors.is_empty() {
+        validate_sources(value, input_dir, &mut errors);
+    }
+    if errors.is_empty() {
+        Ok(warnings)
+    } else {
+        Err(format!("Invalid flash.toml:\n  - {}", errors.join("\n  - ")))
+    }
+}
+
+/// Merge a config over the config it extends. Tables are merged key by key, 
+/// and everything else (including arrays) replaces what the base has
+fn merge_toml(base: &mut toml::Value, over: toml::Value) {
+    match (base, over) {
+        (toml::Value::Table(base), toml::Value::Table(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+/// Read a config file along with the configs it `extends`, which are 
+/// relative to the file that extends them
+fn read_config(path: &Path, chain: &mut Vec<PathBuf>) -> Result<toml::Value, String> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read {}: {e}", path.to_string_lossy()))?;
+    let mut value: toml::Value = toml::from_str(&text)
+        .map_err(|e| format!("Unable to parse {name}: {e}"))?;
+    let Some(extends) = value.as_table_mut().and_then(|t| t.remove("extends")) else {
+        return Ok(value);
+    };
+    let extends = extends.as_str()
+        .ok_or_else(|| format!("`extends` in {name} should be a path"))?;
+    let base_path = path.parent().unwrap_or(Path::new("")).join(extends).normalize();
+    if chain.contains(&base_path) {
+        return Err(format!("{} is extended in a loop", base_path.to_string_lossy()));
+    }
+    chain.push(base_path.clone());
+    let mut base = read_config(&base_path, chain)?;
+    merge_toml(&mut base, value);
+    Ok(base)
+}
+
 pub struct Source {
     pub name: String,
     pub dir: UrlPath,
     pub include: Vec<PathBuf>,
     pub exists_online: bool,
+    include_entities: Vec<glob::Pattern>,
+    exclude_entities: Vec<glob::Pattern>,
+    exclude_entities_regex: Vec<Regex>,
+    exclude_attributes: Vec<String>,
 }
 
 impl Source {
@@ -94,13 +367,121 @@ impl Source {
             .filter(|p| !exclude.contains(p))
             .collect::<Vec<_>>();
 
+        let patterns = |list: Vec<String>| list.iter()
+            .map(|p| glob::Pattern::new(p).map_err(|e| format!("Invalid entity pattern '{p}': {e}")))
+            .collect::<Result<Vec<_>, _>>();
+
         Ok(Self {
+            include_entities: patterns(src.include_entities)?,
+            exclude_entities: patterns(src.exclude_entities)?,
+            exclude_entities_regex: src.exclude_entities_regex.iter()
+                .map(|r| Regex::new(r).map_err(|e| format!("Invalid entity regex '{r}': {e}")))
+                .collect::<Result<_, _>>()?,
+            exclude_attributes: src.exclude_attributes,
             name: src.name,
             dir: src.dir,
             exists_online: src.exists_online,
             include,
         })
     }
+
+    /// Whether an entity from this source should be documented based on its 
+    /// qualified name (like `ns::Class`) and `annotate` attributes. 
+    /// Namespaces are only checked against the exclusions since their 
+    /// members may still be included
+    pub fn includes_entity(&self, name: &str, attributes: &[String], is_namespace: bool) -> bool {
+        if self.exclude_entities.iter().any(|p| p.matches(name))
+            || self.exclude_entities_regex.iter().any(|r| r.is_match(name))
+            || attributes.iter().any(|a| self.exclude_attributes.contains(a))
+        {
+            return false;
+        }
+        is_namespace
+            || self.include_entities.is_empty()
+            || self.include_entities.iter().any(|p| p.matches(name))
+    }
+}
+
+pub struct ExternalDocs {
+    pub namespace: Vec<String>,
+    pub url: String,
+    /// Full name (like `ns::Class`) to URL
+    pub inventory: HashMap<String, String>,
+    /// Unqualified name to URL, used for autolinking
+    pub by_name: HashMap<String, String>,
+}
+
+impl ExternalDocs {
+    fn collect_flash_entries(value: &serde_json::Value, into: &mut HashMap<String, String>) {
+        let Some(entries) = value.get("entries").and_then(|e| e.as_array()) else {
+            return;
+        };
+        for entry in entries {
+            if let Some(name) = entry.get("full_name").and_then(|n| n.as_str()) &&
+                let Some(url) = entry.get("url").and_then(|u| u.as_str())
+            {
+                into.insert(name.to_owned(), url.to_owned());
+            }
+            Self::collect_flash_entries(entry, into);
+        }
+    }
+
+    pub fn from_raw(src: RawExternalDocs) -> Result<ExternalDocs, String> {
+        let mut inventory = HashMap::new();
+        if let Some(ref path) = src.inventory {
+            let data: serde_json::Value = serde_json::from_str(
+                &fs::read_to_string(path).map_err(|e| format!(
+                    "Unable to read inventory {}: {e}", path.to_string_lossy()
+                ))?
+            ).map_err(|e| format!("Unable to parse inventory {}: {e}", path.to_string_lossy()))?;
+
+            // docs.json output from another Flash site
+            if data.get("entries").is_some() {
+                Self::collect_flash_entries(&data, &mut inventory);
+            }
+            // plain name -> url map
+            else {
+                inventory = serde_json::from_value(data).map_err(|e| format!(
+                    "Inventory {} should be an object of names to URLs: {e}",
+                    path.to_string_lossy()
+                ))?;
+            }
+        }
+
+        let url = src.url.trim_end_matches('/').to_owned();
+
+        // make relative inventory URLs point to the external site
+        for value in inventory.values_mut() {
+            if !value.contains("://") {
+                *value = format!("{url}/{}", value.trim_start_matches('/'));
+            }
+        }
+
+        let mut by_name = HashMap::new();
+        for (name, value) in &inventory {
+            if let Some(last) = name.split("::").last() {
+                by_name.entry(last.to_owned()).or_insert(value.clone());
+            }
+        }
+
+        Ok(Self {
+            namespace: src.namespace.split("::").map(|s| s.to_owned()).collect(),
+            url,
+            inventory,
+            by_name,
+        })
+    }
+
+    /// Get the URL for an entity with the given fully qualified name, if it
+    /// belongs to this external project
+    pub fn url_for(&self, full_name: &[String]) -> Option<String> {
+        if let Some(url) = self.inventory.get(&full_name.join("::")) {
+            return Some(url.clone());
+        }
+        full_name.starts_with(&self.namespace).then(|| format!(
+            "{}/{}", self.url, full_name.join("/")
+        ))
+    }
 }
 
 decl_config! {
@@ -109,12 +490,33 @@ decl_config! {
         content: Arc<String> as parse_template,
     }
 
+    struct PageDef {
+        file: PathBuf,
+        url?: UrlPath,
+    }
+
+    struct GroupDef {
+        name: String,
+        description?: String,
+        include: Vec<String> = Vec::new(),
+    }
+
+    struct RawExternalDocs {
+        namespace: String,
+        url: String,
+        inventory?: PathBuf,
+    }
+
     struct RawSource {
         name: String,
         dir: UrlPath,
         include: Vec<PathBuf>,
         exclude: Vec<PathBuf> = Vec::new(),
         exists_online: bool = true,
+        include_entities: Vec<String> = Vec::new(),
+        exclude_entities: Vec<String> = Vec::new(),
+        exclude_entities_regex: Vec<String> = Vec::new(),
+        exclude_attributes: Vec<String> = Vec::new(),
     }
 
     struct Config {
@@ -124,17 +526,52 @@ decl_config! {
             repository?: String,
             tree?: String,
             icon?: PathBuf,
+            readme?: PathBuf,
+            url?: String,
         },
         tutorials? {
             dir: PathBuf,
             assets: Vec<PathBuf> as parse_glob = Vec::new(),
         },
+        feed? {
+            dir?: PathBuf,
+            limit?: usize,
+        },
         sources: Vec<Arc<Source>> as parse_sources,
+        pages: Vec<PageDef> = Vec::new(),
+        groups: Vec<GroupDef> = Vec::new(),
+        static_dirs: Vec<PathBuf> = Vec::new(),
+        glossary?: PathBuf,
+        external_docs: Vec<Arc<ExternalDocs>> as parse_external_docs = Vec::new(),
+        versions? {
+            all: Vec<String>,
+            latest?: String,
+        },
         run? {
             prebuild: Vec<String> = Vec::new(),
         },
         analysis {
             compile_args: Vec<String> = Vec::new(),
+            include_dirs: Vec<PathBuf> = Vec::new(),
+            defines: Vec<String> = Vec::new(),
+            std?: String,
+            compile_flags?: PathBuf,
+            cache: bool = false,
+            cache_dir: PathBuf = PathBuf::from(".flash-cache"),
+            objc: bool = false,
+            c: bool = false,
+        },
+        docs {
+            include_private: bool = false,
+            source_pages: bool = false,
+            member_pages: bool = false,
+            group_above: usize = 100,
+            permalink_history?: PathBuf,
+            referenced_by: bool = true,
+            include_graph: bool = false,
+            cppreference?: PathBuf,
+            heading_shift: usize = 0,
+            min_heading: usize = 1,
         },
         cmake? {
             config_args: Vec<String> = Vec::new(),
@@ -143,24 +580,152 @@ decl_config! {
             build_dir: String = String::from("build"),
             infer_args_from: PathBuf,
         },
+        meson? {
+            setup_args: Vec<String> = Vec::new(),
+            builddir: String = String::from("builddir"),
+            infer_args_from: PathBuf,
+        },
+        bazel? {
+            targets: Vec<String> = vec![String::from("//...")],
+            infer_args_from: PathBuf,
+        },
         templates {
             class:          Arc<String> as parse_template = default_template!("../templates/class.html"),
             struct_:        Arc<String> as parse_template = default_template!("../templates/struct.html"),
             function:       Arc<String> as parse_template = default_template!("../templates/function.html"),
+            member:         Arc<String> as parse_template = default_template!("../templates/member.html"),
             head:           Arc<String> as parse_template = default_template!("../templates/head.html"),
             nav:            Arc<String> as parse_template = default_template!("../templates/nav.html"),
             file:           Arc<String> as parse_template = default_template!("../templates/file.html"),
             page:           Arc<String> as parse_template = default_template!("../templates/page.html"),
             tutorial:       Arc<String> as parse_template = default_template!("../templates/tutorial.html"),
             tutorial_index: Arc<String> as parse_template = default_template!("../templates/tutorial-index.html"),
+            group:          Arc<String> as parse_template = default_template!("../templates/group.html"),
+            source:         Arc<String> as parse_template = default_template!("../templates/source.html"),
+            concept:        Arc<String> as parse_template = default_template!("../templates/concept.html"),
+            namespace:      Arc<String> as parse_template = default_template!("../templates/namespace.html"),
+            entity_index:   Arc<String> as parse_template = default_template!("../templates/entity-index.html"),
+            objc_item:      Arc<String> as parse_template = default_template!("../templates/objc.html"),
+            headers:        Arc<String> as parse_template = default_template!("../templates/headers.html"),
+            glossary:       Arc<String> as parse_template = default_template!("../templates/glossary.html"),
+            c_file:         Arc<String> as parse_template = default_template!("../templates/c-file.html"),
+            c_struct:       Arc<String> as parse_template = default_template!("../templates/c-struct.html"),
+            vars:           HashMap<String, String> = HashMap::new(),
+        },
+        theme {
+            default: String = String::from("dark"),
+            primary?: String,
+            variables: HashMap<String, String> = HashMap::new(),
+            tokens: HashMap<String, String> = HashMap::new(),
+        },
+        output {
+            format: OutputFormat = OutputFormat::Html,
+            urls: UrlScheme = UrlScheme::Pretty,
+            url_case: UrlCase = UrlCase::Preserve,
+            fingerprint: bool = true,
+            mode: OutputMode = OutputMode::Standard,
+            minify_html: bool = true,
+            minify_css: bool = true,
+            minify_js: bool = true,
+            link_previews: bool = true,
+        },
+        site {
+            base_url?: String,
+            links: LinkMode = LinkMode::Absolute,
+            prefetch: bool = false,
+        },
+        i18n {
+            language: String = String::from("en"),
+            languages: Vec<String> = Vec::new(),
+            strings: HashMap<String, String> = HashMap::new(),
+            strings_for: HashMap<String, HashMap<String, String>> = HashMap::new(),
+        },
+        changelog? {
+            repo?: PathBuf,
+            tags?: String,
+        },
+        export? {
+            pages?: Vec<String>,
+            pdf_command?: String,
         },
         scripts {
             css: Vec<Script> = default_scripts!("default.css", "nav.css", "content.css", "themes.css"),
             js:  Vec<Script> = default_scripts!("script.js"),
+            mermaid: String = String::from("https://cdn.jsdelivr.net/npm/mermaid@10/dist/mermaid.esm.min.mjs"),
+            katex: String = String::from("https://cdn.jsdelivr.net/npm/katex@0.16.9/dist"),
         },
         let input_dir: PathBuf,
         let output_dir: PathBuf,
         let output_url: Option<UrlPath>,
+        let strict: bool,
+        let report: Option<Report>,
+        let check_examples: bool,
+        let check_links: bool,
+        // Skip all minification so the output can be read
+        let pretty: bool,
+        // Names and URLs of all projects when building a workspace
+        let projects: Vec<(String, UrlPath)>,
+        // Language being built, which is `i18n.language` unless building 
+        // one of `i18n.languages`
+        let language: String,
+        // Generated text in the language being built, from the built-in 
+        // translations and `i18n.strings`
+        let translations: HashMap<String, String>,
+        // Codes and URLs of all languages when building several
+        let languages: Vec<(String, UrlPath)>,
+        // Pages on cppreference for entities in the standard library, from 
+        // the built-in list and `docs.cppreference`
+        let std_docs: HashMap<String, String>,
+        // Terms and their definitions from the `glossary` file
+        let glossary_terms: Vec<(String, String)>,
+    }
+
+    struct Workspace {
+        workspace {
+            members: Vec<PathBuf>,
+        },
+    }
+}
+
+/// Built-in translations of the text Flash generates, by language code. 
+/// The keys are the English text
+fn builtin_translations(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "de" => include_str!("../templates/i18n/de.toml"),
+        "fr" => include_str!("../templates/i18n/fr.toml"),
+        "ja" => include_str!("../templates/i18n/ja.toml"),
+        _ => return None,
+    })
+}
+
+/// Parse a glossary written as a markdown definition list, the same way
+/// definition lists are found in other markdown. Headings are skipped, and
+/// more definitions of the same term become more paragraphs
+fn parse_glossary(text: &str) -> Vec<(String, String)> {
+    parse_definition_lists(text).into_iter()
+        .filter_map(|line| match line {
+            DefinitionLine::Term(term, definitions) if !term.starts_with('#') => Some((
+                term.to_owned(),
+                definitions.iter().map(|d| d.trim()).collect::<Vec<_>>().join("\n\n"),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Workspace {
+    /// Parse flash.toml as a workspace config if it has a `workspace` section
+    pub fn parse(input_dir: &Path) -> Result<Option<Workspace>, String> {
+        let text = fs::read_to_string(input_dir.join("flash.toml"))
+            .map_err(|e| format!("Unable to read flash.toml: {e}"))?;
+        let value: toml::Value = toml::from_str(&text)
+            .map_err(|e| format!("Unable to parse config: {e}"))?;
+        if value.get("workspace").is_none() {
+            return Ok(None);
+        }
+        toml::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("Unable to parse workspace config: {e}"))
     }
 }
 
@@ -169,17 +734,162 @@ impl Config {
         input_dir: PathBuf,
         output_dir: PathBuf,
         output_url: Option<UrlPath>,
-    ) -> Result<Arc<Config>, String> {
-        let mut config: Config = toml::from_str(
-            &fs::read_to_string(input_dir.join("flash.toml"))
-                .map_err(|e| format!("Unable to read flash.toml: {e}"))?,
-        )
-        .map_err(|e| format!("Unable to parse config: {e}"))?;
+    ) -> Result<Config, String> {
+        let path = input_dir.join("flash.toml");
+        let value = read_config(&path, &mut vec![path.clone()])?;
+        for warning in validate_config(&value, &input_dir)? {
+            log::warn(Category::Config, warning);
+        }
+
+        let mut config: Config = value.try_into()
+            .map_err(|e| format!("Unable to parse config: {e}"))?;
 
         config.input_dir = input_dir;
         config.output_dir = output_dir;
-        config.output_url = output_url;
-        Ok(Arc::from(config))
+        config.output_url = match config.site.base_url {
+            Some(ref base) => Some(UrlPath::from_base_url(base)),
+            None => output_url,
+        };
+        let language = config.i18n.language.clone();
+        config.set_language(&language)?;
+        config.load_std_docs()?;
+        if let Some(ref path) = config.glossary {
+            let path = config.input_dir.join(path);
+            config.glossary_terms = parse_glossary(
+                &fs::read_to_string(&path)
+                    .map_err(|e| format!("Unable to read {}: {e}", path.to_string_lossy()))?
+            );
+        }
+        Ok(config)
+    }
+
+    /// Load the cppreference pages of standard library entities. Entries in 
+    /// `docs.cppreference` replace the built-in ones
+    fn load_std_docs(&mut self) -> Result<(), String> {
+        self.std_docs = toml::from_str(include_str!("../templates/cppreference.toml"))
+            .map_err(|e| format!("Unable to parse built-in cppreference links: {e}"))?;
+        if let Some(ref path) = self.docs.cppreference {
+            let path = self.input_dir.join(path);
+            let custom: HashMap<String, String> = toml::from_str(
+                &fs::read_to_string(&path)
+                    .map_err(|e| format!("Unable to read {}: {e}", path.to_string_lossy()))?
+            ).map_err(|e| format!("Unable to parse {}: {e}", path.to_string_lossy()))?;
+            self.std_docs.extend(custom);
+        }
+        Ok(())
+    }
+
+    /// Get the cppreference page of a standard library entity. Inline 
+    /// namespaces like libstdc++'s `__cxx11` are skipped, and members that 
+    /// aren't listed get the page named after them under their class' page. 
+    /// Entities that aren't known don't get a link, since guessed ones are 
+    /// usually dead
+    pub fn std_docs_url(&self, full_name: &[String]) -> Option<String> {
+        let name = full_name.iter()
+            .filter(|part| !part.starts_with("__"))
+            .cloned()
+            .collect::<Vec<_>>();
+        let page = match self.std_docs.get(&name.join("::")) {
+            Some(page) => page.clone(),
+            None => {
+                let (last, parent) = name.split_last()?;
+                format!("{}/{last}", self.std_docs.get(&parent.join("::"))?)
+            }
+        };
+        Some(if page.contains("://") {
+            page
+        } else {
+            format!("https://en.cppreference.com/w/cpp/{page}")
+        })
+    }
+
+    /// Switch the language the docs are built in, loading its translations. 
+    /// `i18n.strings` only applies to the default language and 
+    /// `i18n.strings-for` to the others
+    pub fn set_language(&mut self, language: &str) -> Result<(), String> {
+        let custom = if language == self.i18n.language {
+            self.i18n.strings.clone()
+        } else {
+            HashMap::new()
+        };
+        let custom_for = self.i18n.strings_for.get(language).cloned().unwrap_or_default();
+        self.translations = match builtin_translations(language) {
+            Some(table) => toml::from_str(table)
+                .map_err(|e| format!("Unable to parse translations: {e}"))?,
+            None => {
+                if language != "en" && custom.is_empty() && custom_for.is_empty() {
+                    log::warn(Category::Config, format!(
+                        "No built-in translations for language '{language}', \
+                        add them to `i18n.strings`"
+                    ));
+                }
+                HashMap::new()
+            }
+        };
+        self.translations.extend(custom);
+        self.translations.extend(custom_for);
+        self.language = language.to_owned();
+        Ok(())
+    }
+
+    /// Get generated text like "Parameters" in the configured language. Text 
+    /// with no translation is used as-is
+    pub fn tr<'a>(&'a self, text: &'a str) -> &'a str {
+        self.translations.get(text).map(|t| t.as_str()).unwrap_or(text)
+    }
+
+    /// Generate the CSS for the custom colors set in the theme config
+    pub fn theme_css(&self) -> String {
+        let mut vars = Vec::new();
+        if let Some(ref primary) = self.theme.primary {
+            vars.push(format!("--flash-highlight: {primary};"));
+            vars.push(format!("--flash-search-match: {primary};"));
+        }
+        let mut custom = self.theme.variables.iter().collect::<Vec<_>>();
+        custom.sort();
+        for (name, value) in custom {
+            vars.push(format!("--{}: {value};", name.trim_start_matches("--")));
+        }
+
+        // body[class] to beat the specificity of the .flash-theme-* classes
+        let mut css = format!("body[class] {{ {} }}\n", vars.join(" "));
+
+        let mut tokens = self.theme.tokens.iter().collect::<Vec<_>>();
+        tokens.sort();
+        for (class, color) in tokens {
+            css += &format!("code .{class} {{ color: {color}; }}\n");
+        }
+        css
+    }
+
+    /// The arguments for LibClang when not using CMake, built from the 
+    /// analysis section
+    pub fn analysis_args(&self) -> Result<Vec<String>, String> {
+        // Custom attributes like `[[flash::hide]]` are read from the tokens,
+        // so LibClang doesn't need to warn about not knowing them
+        let mut args = vec![String::from("-Wno-unknown-attributes")];
+        if let Some(ref path) = self.analysis.compile_flags {
+            // Same format as clangd's compile_flags.txt: one argument per line
+            args.extend(
+                fs::read_to_string(self.input_dir.join(path))
+                    .map_err(|e| format!("Unable to read {}: {e}", path.to_string_lossy()))?
+                    .lines()
+                    .map(|l| l.trim())
+                    .filter(|l| !l.is_empty())
+                    .map(|l| l.to_owned())
+            );
+        }
+        if let Some(ref std) = self.analysis.std {
+            args.push(format!("-std={}", std.trim_start_matches("-std=")));
+        }
+        for dir in &self.analysis.include_dirs {
+            args.push(format!("-I{}", self.input_dir.join(dir).to_string_lossy()));
+        }
+        for define in &self.analysis.defines {
+            args.push(format!("-D{define}"));
+        }
+        args.extend(self.analysis.compile_args.iter().cloned());
+        Ok(args)
     }
 
     pub fn all_includes(&self) -> Vec<PathBuf> {
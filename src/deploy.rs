@@ -0,0 +1,152 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::log::{self, Category};
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Host {
+    /// `.nojekyll` so files starting with `_` are served, and `CNAME` for
+    /// custom domains
+    GithubPages,
+    /// `_headers` for caching assets and `_redirects` for pages that don't 
+    /// exist
+    Netlify,
+}
+
+/// Prepare built docs for hosting and optionally publish them
+#[derive(clap::Args, Debug)]
+pub struct DeployArgs {
+    /// Output directory of a previous build
+    dir: PathBuf,
+
+    /// Host to write the configuration files for
+    #[arg(long, value_enum, default_value_t = Host::GithubPages)]
+    host: Host,
+
+    /// Custom domain the docs are served from, like `docs.example.com`
+    #[arg(long)]
+    cname: Option<String>,
+
+    /// Push the output directory to a branch of the project's repository,
+    /// replacing whatever is on it
+    #[arg(long, default_value_t = false)]
+    push: bool,
+
+    /// Branch to push to
+    #[arg(long, default_value = "gh-pages")]
+    branch: String,
+
+    /// Remote to push to, as a name of a remote of the repository in the
+    /// working directory or a URL
+    #[arg(long, default_value = "origin")]
+    remote: String,
+
+    /// Name to commit the docs as. Defaults to `user.name` from git's
+    /// config, or `Flash` if it isn't set (like in CI)
+    #[arg(long)]
+    user_name: Option<String>,
+
+    /// Email to commit the docs as. Defaults to `user.email` from git's
+    /// config, or `flash@localhost` if it isn't set
+    #[arg(long)]
+    user_email: Option<String>,
+}
+
+fn write(dir: &Path, name: &str, content: &str) -> Result<(), String> {
+    fs::write(dir.join(name), content).map_err(|e| format!("Unable to save {name}: {e}"))?;
+    println!("Created {name}");
+    Ok(())
+}
+
+fn git(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Unable to run git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Commit the output directory on its own and force-push it to a branch.
+/// The repository is kept outside the output directory so it doesn't end up
+/// in the docs
+fn push(args: &DeployArgs) -> Result<(), String> {
+    let (dir, remote, branch) = (&args.dir, &args.remote, &args.branch);
+    let url = git(&["remote", "get-url", remote]).unwrap_or(remote.to_owned());
+    // The commit needs an identity even where git doesn't have one set up
+    let identity = |arg: &Option<String>, key: &str, default: &str| arg.clone()
+        .or_else(|| git(&["config", key]).ok().filter(|v| !v.is_empty()))
+        .unwrap_or(default.to_owned());
+    let user_name = format!("user.name={}", identity(&args.user_name, "user.name", "Flash"));
+    let user_email = format!("user.email={}", identity(&args.user_email, "user.email", "flash@localhost"));
+    let git_dir = std::env::temp_dir().join(format!("flash-deploy-{}", std::process::id()));
+    let git_dir_arg = format!("--git-dir={}", git_dir.to_string_lossy());
+    let work_tree_arg = format!("--work-tree={}", dir.to_string_lossy());
+    let repo = |args: &[&str]| git(&[[git_dir_arg.as_str(), work_tree_arg.as_str()].as_slice(), args].concat());
+
+    let res = repo(&["init", "--quiet"])
+        .and_then(|_| repo(&["add", "--all"]))
+        .and_then(|_| repo(&["-c", &user_name, "-c", &user_email, "commit", "--quiet", "--message", "Deploy docs"]))
+        .and_then(|_| repo(&["push", "--quiet", "--force", &url, &format!("HEAD:refs/heads/{branch}")]));
+    fs::remove_dir_all(&git_dir).ok();
+    res?;
+    println!("Pushed {} to {branch} on {url}", dir.to_string_lossy());
+    Ok(())
+}
+
+/// Whether the CSS files in the output have a content hash in their names, 
+/// like `default.1a2b3c4d.css`
+fn is_fingerprinted(dir: &Path) -> bool {
+    fs::read_dir(dir).into_iter().flatten().flatten().any(|entry| {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let parts = name.split('.').collect::<Vec<_>>();
+        matches!(parts[..], [_, hash, "css"] if hash.len() == 8 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+    })
+}
+
+pub fn run_deploy(args: DeployArgs) -> Result<(), String> {
+    if !args.dir.join("index.html").exists() {
+        return Err(format!(
+            "{} doesn't have an index.html, build the docs into it first",
+            args.dir.to_string_lossy()
+        ));
+    }
+    match args.host {
+        Host::GithubPages => {
+            write(&args.dir, ".nojekyll", "")?;
+            if let Some(ref cname) = args.cname {
+                write(&args.dir, "CNAME", &format!("{cname}\n"))?;
+            }
+        }
+        Host::Netlify => {
+            // JSON is readable from other sites so tools can load docs.json 
+            // and symbols.json
+            let mut headers = String::from("/*.json\n  Access-Control-Allow-Origin: *\n");
+            // Fingerprinted CSS & JS never change, so they can be cached 
+            // forever
+            if is_fingerprinted(&args.dir) {
+                headers += "/*.css\n  Cache-Control: public, max-age=31536000, immutable\n";
+                headers += "/*.js\n  Cache-Control: public, max-age=31536000, immutable\n";
+            }
+            write(&args.dir, "_headers", &headers)?;
+            // Pages that don't exist show the docs home page
+            write(&args.dir, "_redirects", "/*  /index.html  404\n")?;
+            if args.cname.is_some() {
+                log::warn(Category::Config, "Custom domains are set up in Netlify's settings, --cname is ignored");
+            }
+        }
+    }
+    if args.push {
+        push(&args)?;
+    }
+    Ok(())
+}
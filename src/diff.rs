@@ -0,0 +1,264 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde_json::{json, Value};
+
+use crate::log::{self, Category};
+
+/// Compare the API of two versions of a project
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// The old and the new build, as output directories or `docs.json` files
+    /// from builds with `--format json` (or `both`). With `--against` only
+    /// the new build is given
+    #[arg(num_args = 1..=2, required = true)]
+    builds: Vec<PathBuf>,
+
+    /// Build the old version from a git tag, branch or commit of the project
+    /// instead of giving its output
+    #[arg(long)]
+    against: Option<String>,
+
+    /// Input directory with the flash.toml file, for building `--against`
+    #[arg(short, long, default_value = ".")]
+    input: PathBuf,
+
+    /// Print the report as JSON, for generating changelogs
+    #[arg(long, default_value_t = false)]
+    json: bool,
+
+    /// Fail if anything was removed or changed, so CI can catch breaking
+    /// changes in releases that shouldn't have them
+    #[arg(long, default_value_t = false)]
+    fail_on_breaking: bool,
+}
+
+/// What kind of entity something is and its signature as far as users of
+/// the API are concerned
+type ApiItem = (String, String);
+
+/// Find the `docs.json` of a build. Versioned docs are built into a
+/// subdirectory named after the version, so those are looked in too
+fn find_docs_json(path: &Path) -> Result<PathBuf, String> {
+    if path.is_file() {
+        return Ok(path.to_owned());
+    }
+    if path.join("docs.json").is_file() {
+        return Ok(path.join("docs.json"));
+    }
+    // Docs built for several versions or languages have one docs.json in
+    // each of their subdirectories
+    let mut found = fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path().join("docs.json"))
+        .filter(|p| p.is_file())
+        .collect::<Vec<_>>();
+    found.sort();
+    let first = found.first().cloned().ok_or(format!(
+        "{} doesn't have a docs.json, build the docs with --format json or both first",
+        path.to_string_lossy()
+    ))?;
+    if found.len() > 1 {
+        log::warn(Category::Build, format!(
+            "{} has {} docs.json files, using {}. Pass the directory of the one to compare instead",
+            path.to_string_lossy(),
+            found.len(),
+            first.to_string_lossy(),
+        ));
+    }
+    Ok(first)
+}
+
+fn str_of<'a>(value: &'a Value, key: &str) -> &'a str {
+    value[key].as_str().unwrap_or_default()
+}
+
+fn list_of<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value[key].as_array().map(|a| a.as_slice()).unwrap_or_default()
+}
+
+/// Functions are told apart by their parameters and constness so overloads
+/// don't get mixed up, everything else about them is their signature
+fn add_function(fun: &Value, out: &mut BTreeMap<String, ApiItem>) {
+    let params = list_of(fun, "params").iter()
+        .map(|p| str_of(p, "type"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let konst = if fun["const"].as_bool().unwrap_or(false) { " const" } else { "" };
+    let key = format!("{}({params}){konst}", str_of(fun, "full_name"));
+    let mut signature = format!("{} {key}", str_of(fun, "return_type"));
+    for (flag, name) in [("static", "static"), ("virtual", "virtual"), ("pure_virtual", "pure")] {
+        if fun[flag].as_bool().unwrap_or(false) {
+            signature = format!("{name} {signature}");
+        }
+    }
+    if let Some(access) = fun["access"].as_str() && access != "public" {
+        signature = format!("{access}: {signature}");
+    }
+    out.insert(key, ("function".into(), signature));
+}
+
+fn add_item(item: &Value, out: &mut BTreeMap<String, ApiItem>) {
+    let name = str_of(item, "full_name").to_owned();
+    match str_of(item, "kind") {
+        "namespace" => {
+            for entry in list_of(item, "entries") {
+                add_item(entry, out);
+            }
+        }
+        "function" => {
+            add_function(item, out);
+            for overload in list_of(item, "overloads") {
+                add_function(overload, out);
+            }
+        }
+        "concept" => {
+            out.insert(name, ("concept".into(), str_of(item, "definition").to_owned()));
+        }
+        kind => {
+            let bases = list_of(item, "bases").iter()
+                .map(|b| format!("{} {}", str_of(b, "access"), str_of(b, "type")))
+                .collect::<Vec<_>>();
+            let signature = if bases.is_empty() {
+                name.clone()
+            } else {
+                format!("{name} : {}", bases.join(", "))
+            };
+            for fun in list_of(item, "functions") {
+                add_function(fun, out);
+            }
+            for field in list_of(item, "fields") {
+                out.insert(
+                    str_of(field, "full_name").to_owned(),
+                    ("field".into(), format!("{} {}", str_of(field, "type"), str_of(field, "full_name"))),
+                );
+            }
+            for nested in list_of(item, "nested") {
+                add_item(nested, out);
+            }
+            out.insert(name, (kind.to_owned(), signature));
+        }
+    }
+}
+
+fn load_api(path: &Path) -> Result<BTreeMap<String, ApiItem>, String> {
+    let path = find_docs_json(path)?;
+    let docs: Value = serde_json::from_str(
+        &fs::read_to_string(&path).map_err(|e| format!("Unable to read {}: {e}", path.to_string_lossy()))?
+    ).map_err(|e| format!("Unable to parse {}: {e}", path.to_string_lossy()))?;
+    let mut api = BTreeMap::new();
+    for entry in list_of(&docs, "entries") {
+        add_item(entry, &mut api);
+    }
+    Ok(api)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Unable to run git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Check out a git ref of the project in a temporary worktree and build its
+/// JSON docs with this same executable
+fn build_ref(input: &Path, git_ref: &str, out: &Path) -> Result<(), String> {
+    let input = input.canonicalize()
+        .map_err(|e| format!("Unable to find {}: {e}", input.to_string_lossy()))?;
+    let repo = PathBuf::from(git(&input, &["rev-parse", "--show-toplevel"])?);
+    let subdir = input.strip_prefix(repo.canonicalize().unwrap_or(repo.clone()))
+        .map(|p| p.to_owned())
+        .unwrap_or_default();
+    let worktree = std::env::temp_dir().join(format!("flash-diff-{}", std::process::id()));
+    let worktree_arg = worktree.to_string_lossy().to_string();
+    git(&repo, &["worktree", "add", "--detach", "--quiet", &worktree_arg, git_ref])?;
+
+    log::info(Category::Build, format!("Building docs for {git_ref}"));
+    let res = std::env::current_exe()
+        .map_err(|e| format!("Unable to find the flash executable: {e}"))
+        .and_then(|exe| Command::new(exe)
+            .arg("--input").arg(worktree.join(subdir))
+            .arg("--output").arg(out)
+            .args(["--format", "json", "--overwrite", "--quiet"])
+            .status()
+            .map_err(|e| format!("Unable to run flash: {e}"))
+        )
+        .and_then(|status| if status.success() {
+            Ok(())
+        } else {
+            Err(format!("Unable to build the docs for {git_ref}"))
+        });
+    git(&repo, &["worktree", "remove", "--force", &worktree_arg]).ok();
+    res
+}
+
+pub fn run_diff(args: DiffArgs) -> Result<(), String> {
+    let (old, new) = match (&args.against, &args.builds[..]) {
+        (None, [old, new]) => (load_api(old)?, load_api(new)?),
+        (Some(git_ref), [new]) => {
+            let out = std::env::temp_dir().join(format!("flash-diff-{}-out", std::process::id()));
+            let old = build_ref(&args.input, git_ref, &out).and_then(|_| load_api(&out));
+            fs::remove_dir_all(&out).ok();
+            (old?, load_api(new)?)
+        }
+        (None, _) => return Err("Give both the old and the new build, or use --against".into()),
+        (Some(_), _) => return Err("Only give the new build with --against".into()),
+    };
+
+    let added = new.iter().filter(|(k, _)| !old.contains_key(*k)).collect::<Vec<_>>();
+    let removed = old.iter().filter(|(k, _)| !new.contains_key(*k)).collect::<Vec<_>>();
+    let changed = old.iter()
+        .filter_map(|(k, o)| new.get(k).filter(|n| n.1 != o.1).map(|n| (k, o, n)))
+        .collect::<Vec<_>>();
+
+    // Removing or changing anything can break users of the API, adding
+    // things only needs a minor release
+    let breaking = !removed.is_empty() || !changed.is_empty();
+    let bump = if breaking { "major" } else if !added.is_empty() { "minor" } else { "patch" };
+
+    if args.json {
+        let item = |(kind, signature): &ApiItem| json!({ "kind": kind, "signature": signature });
+        println!("{}", json!({
+            "added": added.iter().map(|(name, i)| json!({ "name": name, "new": item(*i) })).collect::<Vec<_>>(),
+            "removed": removed.iter().map(|(name, i)| json!({ "name": name, "old": item(*i) })).collect::<Vec<_>>(),
+            "changed": changed.iter().map(|(name, o, n)| json!({
+                "name": name, "old": item(*o), "new": item(*n)
+            })).collect::<Vec<_>>(),
+            "bump": bump,
+        }));
+    } else {
+        for (_, (kind, signature)) in &added {
+            println!("+ {kind} {signature}");
+        }
+        for (_, (kind, signature)) in &removed {
+            println!("- {kind} {signature}");
+        }
+        for (_, (kind, old), (_, new)) in &changed {
+            println!("~ {kind} {old}\n  -> {new}");
+        }
+        println!(
+            "{} added, {} removed, {} changed, needs a {bump} version bump",
+            added.len(), removed.len(), changed.len()
+        );
+    }
+
+    if breaking && args.fail_on_breaking {
+        return Err("The API has breaking changes".into());
+    }
+    Ok(())
+}
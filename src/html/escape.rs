@@ -0,0 +1,117 @@
+//! Escaping for the different contexts user-provided strings can end up in
+//! the generated HTML
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// Characters that aren't allowed to appear as-is in a URL. Reserved
+/// characters like `/`, `#` and `%` are left alone since URLs passed to
+/// attributes are expected to already be valid
+const URL_UNSAFE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'\'')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`');
+
+/// Escape text placed inside an element
+pub fn escape_text(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Escape the value of a quoted attribute. Also safe to use for text
+pub fn escape_attr(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Make text safe to place inside an inline `<script>`. Browsers end the
+/// script at the first `</script` in any case, so it's written as `<\/script`
+/// which means the same thing in JavaScript strings and JSON
+pub fn escape_script(script: &str) -> String {
+    let lower = script.to_ascii_lowercase();
+    let mut res = String::with_capacity(script.len());
+    let mut last = 0;
+    for (ix, _) in lower.match_indices("</script") {
+        res.push_str(&script[last..ix + 1]);
+        res.push('\\');
+        last = ix + 1;
+    }
+    res.push_str(&script[last..]);
+    res
+}
+
+/// Make a URL safe to use in `href` or `src`. Characters that aren't valid
+/// in URLs are percent-encoded and script URLs are replaced with `#`. The
+/// result still has to be escaped with `escape_attr`
+pub fn escape_url(url: &str) -> String {
+    let scheme = url.trim_start()
+        .split_once(':')
+        .map(|(scheme, _)| scheme.to_lowercase())
+        .filter(|s| s.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)));
+    let is_script = match scheme.as_deref() {
+        Some("javascript" | "vbscript") => true,
+        Some("data") => !url.trim_start()[5..].to_lowercase().starts_with("image/"),
+        _ => false,
+    };
+    if is_script {
+        return "#".into();
+    }
+    utf8_percent_encode(url, URL_UNSAFE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text() {
+        assert_eq!(escape_text("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+        assert_eq!(escape_text("\"quotes\" and 'apostrophes'"), "\"quotes\" and 'apostrophes'");
+        assert_eq!(escape_text("&amp;"), "&amp;amp;");
+        assert_eq!(escape_text("ünïcödé 日本"), "ünïcödé 日本");
+    }
+
+    #[test]
+    fn attr() {
+        assert_eq!(escape_attr("<a href=\"x\">"), "&lt;a href=&quot;x&quot;&gt;");
+        assert_eq!(escape_attr("Tom & Jerry's"), "Tom &amp; Jerry&#39;s");
+        assert_eq!(escape_attr("ünïcödé 日本"), "ünïcödé 日本");
+    }
+
+    #[test]
+    fn script() {
+        assert_eq!(escape_script("let s = \"</script>\";"), "let s = \"<\\/script>\";");
+        assert_eq!(escape_script("\"</SCRIPT>\" + \"</Script \""), "\"<\\/SCRIPT>\" + \"<\\/Script \"");
+        assert_eq!(escape_script("a < b && \"c\""), "a < b && \"c\"");
+        assert_eq!(escape_script("\"ü</script>\""), "\"ü<\\/script>\"");
+    }
+
+    #[test]
+    fn url() {
+        assert_eq!(escape_url("javascript:alert(1)"), "#");
+        assert_eq!(escape_url(" JavaScript:alert(1)"), "#");
+        assert_eq!(escape_url("data:text/html,<b>"), "#");
+        assert_eq!(escape_url("data:image/png;base64,AAAA"), "data:image/png;base64,AAAA");
+        assert_eq!(escape_url("/a b/\"c\""), "/a%20b/%22c%22");
+    }
+}
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 
+use escape::{escape_attr, escape_text, escape_url};
+
+pub mod escape;
 pub mod process;
 
 pub trait GenHtml: Into<Html> {
@@ -157,7 +160,17 @@ impl HtmlElement {
 }
 
 impl GenHtml for HtmlElement {
-    fn gen_html(self) -> String {
+    fn gen_html(mut self) -> String {
+        // Screen readers are told whether a <details> is open through its 
+        // <summary>, the script updates this when it's toggled
+        if self.tag == "details" {
+            let open = self.has_attr("open");
+            if let Some(Html::Element(summary)) = self.children.iter_mut()
+                .find(|c| matches!(c, Html::Element(e) if e.tag == "summary"))
+            {
+                *summary.attr_mut("aria-expanded") = open.to_string();
+            }
+        }
         format!(
             "<{tag} {classes} {attrs}>{children}</{tag}>",
             tag = self.tag,
@@ -165,13 +178,13 @@ impl GenHtml for HtmlElement {
                 .classes
                 .is_empty()
                 .then_some(String::new())
-                .unwrap_or(format!("class=\"{}\"", self.classes.join(" "))),
+                .unwrap_or(format!("class=\"{}\"", escape_attr(&self.classes.join(" ")))),
             attrs = self
                 .attributes
                 .iter()
                 .map(|(k, v)| match k.as_str() {
-                    "onclick" => format!("{k}=\"{v}\""),
-                    _ => format!("{k}=\"{}\"", v.escape_default()),
+                    "href" | "src" => format!("{k}=\"{}\"", escape_attr(&escape_url(v))),
+                    _ => format!("{k}=\"{}\"", escape_attr(v)),
                 })
                 .collect::<Vec<_>>()
                 .join(" "),
@@ -205,7 +218,7 @@ impl HtmlText {
 
 impl GenHtml for HtmlText {
     fn gen_html(self) -> String {
-        sanitize_html(&self.content)
+        escape_text(&self.content)
     }
 }
 
@@ -240,7 +253,3 @@ impl From<HtmlList> for Html {
         Html::List(val)
     }
 }
-
-fn sanitize_html(html: &str) -> String {
-    html.replace('<', "&lt;").replace('>', "&gt;")
-}
@@ -1,11 +1,151 @@
 
+#[cfg(feature = "swc")]
 use std::sync::Arc;
 
+use crate::config::Config;
+
 use lightningcss::stylesheet::{ParserOptions, PrinterOptions};
+#[cfg(feature = "swc")]
 use swc::{try_with_handler, HandlerOpts, config::{JsMinifyOptions, Options}, BoolOrDataConfig};
+#[cfg(feature = "swc")]
 use swc_common::{SourceMap, GLOBALS, FileName};
 
-pub fn minify_html(input: String) -> Result<String, String> {
+/// Elements that go on their own lines when pretty-printing. Whitespace
+/// around them isn't rendered, so breaking lines there doesn't change how
+/// pages look
+const BLOCK_ELEMENTS: &[&str] = &[
+    "html", "head", "body", "meta", "link", "title", "base", "script", "style", "template",
+    "noscript", "div", "section", "nav", "main", "header", "footer", "article", "aside",
+    "h1", "h2", "h3", "h4", "h5", "h6", "p", "pre", "hr", "blockquote", "figure", "figcaption",
+    "ul", "ol", "li", "dl", "dt", "dd", "table", "caption", "thead", "tbody", "tfoot", "tr",
+    "th", "td", "details", "summary", "form", "fieldset", "select", "option", "iframe",
+];
+
+/// Elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source",
+    "track", "wbr",
+];
+
+/// Elements whose content is kept exactly as it is
+const RAW_ELEMENTS: &[&str] = &["pre", "script", "style", "textarea"];
+
+/// Length of the tag, comment or doctype at the start of `html`, skipping
+/// over quoted attribute values
+fn tag_len(html: &str) -> usize {
+    if html.starts_with("<!--") {
+        return html.find("-->").map(|i| i + 3).unwrap_or(html.len());
+    }
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Indent HTML so each block element is on its own line, for `--pretty`.
+/// Inline content stays on one line and the content of `<pre>`, scripts and
+/// styles is left alone, so the pages look the same as minified ones
+pub fn pretty_html(input: &str) -> String {
+    let mut res = String::with_capacity(input.len() * 2);
+    let mut depth = 0usize;
+    // Whether there's inline content on the current line
+    let mut inline = false;
+    let mut rest = input;
+
+    let new_line = |res: &mut String, depth: usize| {
+        if !res.is_empty() {
+            res.push('\n');
+        }
+        res.push_str(&"    ".repeat(depth));
+    };
+
+    while !rest.is_empty() {
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            rest = &rest[end..];
+            // Whitespace at the start of a line isn't rendered
+            let text = if inline { text } else { text.trim_start() };
+            if text.is_empty() {
+                continue;
+            }
+            if !inline {
+                new_line(&mut res, depth);
+                inline = true;
+            }
+            res.push_str(text);
+            continue;
+        }
+
+        let len = tag_len(rest);
+        let tag = &rest[..len];
+        rest = &rest[len..];
+        let closing = tag.starts_with("</");
+        let name = tag.trim_start_matches(['<', '/'])
+            .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        let is_block = tag.starts_with("<!") || BLOCK_ELEMENTS.contains(&name.as_str());
+        if !is_block {
+            if !inline {
+                new_line(&mut res, depth);
+                inline = true;
+            }
+            res.push_str(tag);
+        }
+        else {
+            // Trailing whitespace before a block isn't rendered either
+            if inline {
+                res.truncate(res.trim_end().len());
+            }
+            if closing {
+                depth = depth.saturating_sub(1);
+            }
+            new_line(&mut res, depth);
+            res.push_str(tag);
+            inline = false;
+            if !closing && !tag.starts_with("<!") && !tag.ends_with("/>") && !VOID_ELEMENTS.contains(&name.as_str()) {
+                depth += 1;
+            }
+        }
+
+        // Copy raw content as-is up to its closing tag
+        if !closing && RAW_ELEMENTS.contains(&name.as_str()) && !tag.ends_with("/>") {
+            let lower = rest.to_ascii_lowercase();
+            let end = lower.find(&format!("</{name}")).unwrap_or(rest.len());
+            res.push_str(&rest[..end]);
+            rest = &rest[end..];
+            let close = tag_len(rest);
+            res.push_str(&rest[..close]);
+            rest = &rest[close..];
+            if is_block {
+                depth = depth.saturating_sub(1);
+            }
+            inline = !is_block;
+        }
+    }
+    res.push('\n');
+    res
+}
+
+/// Minify HTML unless `output.minify-html` is off. Building with `--pretty`
+/// indents it instead
+pub fn minify_html(config: &Config, input: String) -> Result<String, String> {
+    if config.pretty {
+        return Ok(pretty_html(&input));
+    }
+    if !config.output.minify_html {
+        return Ok(input);
+    }
     String::from_utf8(minify_html::minify(
         input.as_bytes(),
         &minify_html::Cfg {
@@ -15,7 +155,13 @@ pub fn minify_html(input: String) -> Result<String, String> {
     )).map_err(|e| format!("{e}"))
 }
  
-pub fn minify_js(input: String) -> Result<String, String> {
+/// Transpile and minify JS. With `output.minify-js` off or `--pretty` the 
+/// scripts are copied as-is, skipping swc entirely
+#[cfg(feature = "swc")]
+pub fn minify_js(config: &Config, input: String) -> Result<String, String> {
+    if !config.output.minify_js || config.pretty {
+        return Ok(input);
+    }
     // minify
     let cm = Arc::<SourceMap>::default();
     let c = swc::Compiler::new(cm.clone());
@@ -53,7 +199,33 @@ pub fn minify_js(input: String) -> Result<String, String> {
     .map_err(|e| format!("{e}"))
 }
 
-pub fn minify_css(input: String) -> Result<String, String> {
+/// Minify JS with minify-html's JS minifier, which is much lighter than swc 
+/// but doesn't transpile
+#[cfg(not(feature = "swc"))]
+pub fn minify_js(config: &Config, input: String) -> Result<String, String> {
+    if !config.output.minify_js || config.pretty {
+        return Ok(input);
+    }
+    // minify-html only minifies scripts inside HTML, so wrap the script in a 
+    // tag and take it back out
+    let html = String::from_utf8(minify_html::minify(
+        format!("<script>{input}</script>").as_bytes(),
+        &minify_html::Cfg {
+            minify_js: true,
+            ..Default::default()
+        }
+    )).map_err(|e| format!("{e}"))?;
+    Ok(html
+        .strip_prefix("<script>")
+        .and_then(|s| s.strip_suffix("</script>"))
+        .map(|s| s.to_owned())
+        .unwrap_or(input))
+}
+
+pub fn minify_css(config: &Config, input: String) -> Result<String, String> {
+    if !config.output.minify_css || config.pretty {
+        return Ok(input);
+    }
     let sheet = lightningcss::stylesheet::StyleSheet::parse(
         &input, ParserOptions::default()
     ).map_err(|e| format!("{e}"))?;
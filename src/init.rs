@@ -0,0 +1,196 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use glob::glob;
+
+/// Generate a starter flash.toml
+#[derive(clap::Args, Debug)]
+pub struct InitArgs {
+    /// Project directory to create flash.toml in
+    #[arg(default_value = ".")]
+    dir: PathBuf,
+
+    /// Project name. Defaults to the CMake project name or directory name
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Project version. Defaults to the CMake project version or 1.0.0
+    #[arg(long)]
+    version: Option<String>,
+
+    /// GitHub repository, like `user/repo`
+    #[arg(long)]
+    repository: Option<String>,
+
+    /// Directory with the headers to document. Inferred from existing
+    /// include directories if not specified
+    #[arg(long)]
+    include_dir: Option<PathBuf>,
+
+    /// Whether to get compile arguments from CMake. Defaults to true if the
+    /// project has a CMakeLists.txt
+    #[arg(long)]
+    cmake: Option<bool>,
+
+    /// Don't ask anything, just use the flags and inferred values
+    #[arg(short, long, default_value_t = false)]
+    yes: bool,
+
+    /// Overwrite flash.toml if it already exists
+    #[arg(long, default_value_t = false)]
+    overwrite: bool,
+}
+
+fn prompt(question: &str, default: String, interactive: bool) -> Result<String, String> {
+    if !interactive {
+        return Ok(default);
+    }
+    print!("{question} [{default}]: ");
+    io::stdout().flush().map_err(|e| format!("Unable to write prompt: {e}"))?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).map_err(|e| format!("Unable to read answer: {e}"))?;
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer.to_owned() })
+}
+
+fn prompt_bool(question: &str, default: bool, interactive: bool) -> Result<bool, String> {
+    let answer = prompt(question, (if default { "Y/n" } else { "y/N" }).into(), interactive)?;
+    Ok(match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+/// Get the name and version from `project(Name VERSION 1.0.0)` in
+/// CMakeLists.txt
+fn cmake_project(dir: &Path) -> Option<(String, Option<String>)> {
+    let cmake = fs::read_to_string(dir.join("CMakeLists.txt")).ok()?;
+    let start = cmake.to_ascii_lowercase().find("project(")? + "project(".len();
+    let args = cmake[start..].split(')').next()?.split_whitespace().collect::<Vec<_>>();
+    let version = args.iter()
+        .position(|a| *a == "VERSION")
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.to_string());
+    Some((args.first()?.to_string(), version))
+}
+
+fn has_files(dir: &Path, pattern: &str) -> bool {
+    glob(&dir.join(pattern).to_string_lossy())
+        .is_ok_and(|mut paths| paths.next().is_some())
+}
+
+/// Find the directory with the project's public headers
+fn infer_include_dir(dir: &Path) -> Option<PathBuf> {
+    ["include", "inc", "src", "."].into_iter()
+        .map(PathBuf::from)
+        .find(|d| has_files(&dir.join(d), "**/*.h") || has_files(&dir.join(d), "**/*.hpp"))
+}
+
+/// Find a source file to get the compile arguments from
+fn infer_source_file(dir: &Path) -> Option<PathBuf> {
+    ["src/**/*.cpp", "**/*.cpp"].into_iter()
+        .find_map(|pattern| glob(&dir.join(pattern).to_string_lossy()).ok()?.flatten().next())
+        .map(|file| file.strip_prefix(dir).unwrap_or(&file).to_owned())
+}
+
+fn toml_str<S: AsRef<str>>(value: S) -> String {
+    toml::Value::String(value.as_ref().replace('\\', "/")).to_string()
+}
+
+pub fn run_init(args: InitArgs) -> Result<(), String> {
+    let path = args.dir.join("flash.toml");
+    if path.exists() && !args.overwrite {
+        return Err(format!(
+            "{} already exists and no --overwrite option was specified",
+            path.to_string_lossy()
+        ));
+    }
+    let interactive = !args.yes && io::stdin().is_terminal();
+
+    let cmake_project = cmake_project(&args.dir);
+    let dir_name = fs::canonicalize(&args.dir)
+        .ok()
+        .and_then(|d| d.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or("Project".into());
+
+    let name = match args.name {
+        Some(name) => name,
+        None => prompt(
+            "Project name",
+            cmake_project.as_ref().map(|p| p.0.clone()).unwrap_or(dir_name),
+            interactive,
+        )?,
+    };
+    let version = match args.version {
+        Some(version) => version,
+        None => prompt(
+            "Project version",
+            cmake_project.as_ref().and_then(|p| p.1.clone()).unwrap_or("1.0.0".into()),
+            interactive,
+        )?,
+    };
+    let repository = match args.repository {
+        Some(repo) => Some(repo),
+        None => Some(prompt("GitHub repository (leave empty for none)", String::new(), interactive)?)
+            .filter(|r| !r.is_empty()),
+    };
+    let include_dir = match args.include_dir {
+        Some(dir) => dir,
+        None => PathBuf::from(prompt(
+            "Directory with the headers to document",
+            infer_include_dir(&args.dir)
+                .unwrap_or("include".into())
+                .to_string_lossy()
+                .to_string(),
+            interactive,
+        )?),
+    };
+    let use_cmake = match args.cmake {
+        Some(cmake) => cmake,
+        None => prompt_bool(
+            "Get compile arguments from CMake?",
+            cmake_project.is_some(),
+            interactive,
+        )?,
+    };
+
+    let mut res = format!(
+        "[project]\nname = {}\nversion = {}\n",
+        toml_str(&name),
+        toml_str(&version),
+    );
+    if let Some(repo) = repository {
+        res += &format!("repository = {}\n", toml_str(repo));
+    }
+    if args.dir.join("README.md").exists() {
+        res += "readme = \"README.md\"\n";
+    }
+
+    let include_dir = include_dir.to_string_lossy().to_string();
+    let patterns = ["**/*.hpp", "**/*.h"].into_iter()
+        .filter(|p| has_files(&args.dir.join(&include_dir), p))
+        .map(toml_str)
+        .collect::<Vec<_>>();
+    res += &format!(
+        "\n[[sources]]\nname = {}\ndir = {}\ninclude = [{}]\n",
+        toml_str(&name),
+        toml_str(&include_dir),
+        if patterns.is_empty() { toml_str("**/*.hpp") } else { patterns.join(", ") },
+    );
+
+    if use_cmake {
+        res += "\n[cmake]\n";
+        match infer_source_file(&args.dir) {
+            Some(file) => res += &format!("infer-args-from = {}\n", toml_str(file.to_string_lossy())),
+            None => res += "# Source file to get the compile arguments from\ninfer-args-from = \"src/main.cpp\"\n",
+        }
+    }
+
+    fs::write(&path, res).map_err(|e| format!("Unable to save flash.toml: {e}"))?;
+    println!("Created {}", path.to_string_lossy());
+    Ok(())
+}
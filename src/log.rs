@@ -0,0 +1,143 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    /// Nothing is printed, only used as the level to show
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+/// What a message is about, so CI can filter for example only broken links
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Problems with flash.toml
+    Config,
+    /// Doc comments and markdown that couldn't be understood
+    Parse,
+    /// Undocumented entities
+    MissingDocs,
+    /// References and links that don't go anywhere
+    Links,
+    /// How long each phase of the build took
+    Timing,
+    /// Everything else about generating the docs
+    Build,
+}
+
+impl Level {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.trim().to_lowercase().as_str() {
+            "off" => Self::Off,
+            "error" => Self::Error,
+            "warn" | "warning" => Self::Warn,
+            "info" => Self::Info,
+            "debug" | "trace" => Self::Debug,
+            _ => return None,
+        })
+    }
+}
+
+impl Category {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Config => "config",
+            Self::Parse => "parse",
+            Self::MissingDocs => "missing-docs",
+            Self::Links => "links",
+            Self::Timing => "timing",
+            Self::Build => "build",
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Get the level from `RUST_LOG`, like `debug` or `flash=debug,warn`
+fn env_level() -> Option<Level> {
+    let value = std::env::var("RUST_LOG").ok()?;
+    let mut res = None;
+    for directive in value.split(',') {
+        match directive.split_once('=') {
+            Some((target, level)) if target.trim() == "flash" => return Level::from_name(level),
+            Some(_) => {}
+            None => res = res.or(Level::from_name(directive)),
+        }
+    }
+    res
+}
+
+/// Set up logging from the command line. `--verbose` and `--quiet` take
+/// precedence over `RUST_LOG`
+pub fn init(verbose: bool, quiet: bool, json: bool) {
+    let level = if quiet {
+        Level::Warn
+    } else if verbose {
+        Level::Debug
+    } else {
+        env_level().unwrap_or(Level::Info)
+    };
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level != Level::Off && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Whether messages are printed as JSON, in which case progress bars and
+/// other plain text output should be left out
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Print a message if its level is enabled. Warnings and errors go to
+/// stderr, with JSON output everything does as one object per line
+pub fn log<T: AsRef<str>>(level: Level, category: Category, msg: T) {
+    if !enabled(level) {
+        return;
+    }
+    let msg = msg.as_ref();
+    if is_json() {
+        eprintln!("{}", json!({
+            "level": level.name(),
+            "category": category.name(),
+            "message": msg,
+        }));
+        return;
+    }
+    match level {
+        Level::Error => eprintln!("Error: {msg}"),
+        Level::Warn => eprintln!("Warning: {msg}"),
+        Level::Info => println!("{msg}"),
+        Level::Debug => println!("[{}] {msg}", category.name()),
+        Level::Off => {}
+    }
+}
+
+pub fn warn<T: AsRef<str>>(category: Category, msg: T) {
+    log(Level::Warn, category, msg);
+}
+
+pub fn info<T: AsRef<str>>(category: Category, msg: T) {
+    log(Level::Info, category, msg);
+}
+
+pub fn debug<T: AsRef<str>>(category: Category, msg: T) {
+    log(Level::Debug, category, msg);
+}
@@ -5,34 +5,108 @@
 #![feature(iter_intersperse)]
 
 use crate::{analyze::create_docs, url::UrlPath, normalize::Normalize};
-use clap::Parser;
-use config::Config;
-use std::{fs, path::{PathBuf, Path}, process::exit, io, time::Instant};
+use clap::{Parser, Subcommand};
+use config::{Config, ExternalDocs, OutputFormat, OutputMode, RawExternalDocs, Report, Workspace};
+use log::Category;
+use std::{fs, path::{PathBuf, Path}, process::exit, io, sync::Arc, time::Instant};
 
 mod analyze;
 mod builder;
-mod cmake;
+mod buildsystem;
 mod config;
+mod deploy;
+mod diff;
 mod html;
+mod init;
+mod log;
 mod url;
 mod normalize;
 mod annotation;
 mod lookahead;
+mod template;
 
 #[derive(Parser, Debug)]
-#[command(name("Flash"), version, about)]
+#[command(name("Flash"), version, about, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input directory with the flash.json file
-    #[arg(short, long)]
-    input: PathBuf,
+    #[arg(short, long, required = true)]
+    input: Option<PathBuf>,
 
     /// Output directory where to place the generated docs
-    #[arg(short, long)]
-    output: PathBuf,
+    #[arg(short, long, required = true)]
+    output: Option<PathBuf>,
 
     /// Whether to overwrite output directory if it already exists
     #[arg(long, default_value_t = false)]
     overwrite: bool,
+
+    /// Fail the build if there are any documentation warnings, such as
+    /// undocumented entities or broken references
+    #[arg(long, default_value_t = false)]
+    strict: bool,
+
+    /// What kind of output to generate. Overrides output.format in flash.toml
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Generate a report after building the docs. The report is printed and
+    /// also saved as JSON in the output directory
+    #[arg(long, value_enum)]
+    report: Option<Report>,
+
+    /// Compile all `@example[flash]` snippets and fail the build if any of
+    /// them don't compile
+    #[arg(long, default_value_t = false)]
+    check_examples: bool,
+
+    /// Check that every internal link in the generated pages points to an
+    /// existing page and anchor. Dangling links are reported as warnings
+    #[arg(long, default_value_t = false)]
+    check_links: bool,
+
+    /// Set to single-file to also bundle the docs into one offline HTML
+    /// file. Overrides output.mode in flash.toml
+    #[arg(long, value_enum)]
+    output_mode: Option<OutputMode>,
+
+    /// URL the docs are served from, like `/project` or
+    /// `https://user.github.io/project/`. Overrides site.base-url in
+    /// flash.toml and the URL inferred from the output directory
+    #[arg(long)]
+    base_url: Option<String>,
+
+    /// Indent the generated HTML and don't minify the CSS and JS, for
+    /// debugging templates
+    #[arg(long, default_value_t = false)]
+    pretty: bool,
+
+    /// Also print undocumented entities and how long each build phase took.
+    /// The level can also be set with RUST_LOG
+    #[arg(short, long, global = true, default_value_t = false)]
+    verbose: bool,
+
+    /// Only print warnings and errors
+    #[arg(short, long, global = true, default_value_t = false, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print messages as one JSON object per line on stderr, for CI
+    #[arg(long, global = true, default_value_t = false)]
+    log_json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate a starter flash.toml for a project
+    Init(init::InitArgs),
+    /// Write the files GitHub Pages or Netlify need to host built docs, and
+    /// optionally push them to a gh-pages branch
+    Deploy(deploy::DeployArgs),
+    /// Compare the API of two builds, or of a build and a git ref, and list
+    /// what was added, removed or changed
+    Diff(diff::DiffArgs),
 }
 
 fn remove_dir_contents<P: AsRef<Path>>(path: P) -> io::Result<()> {
@@ -52,32 +126,21 @@ fn remove_dir_contents<P: AsRef<Path>>(path: P) -> io::Result<()> {
 
 #[tokio::main]
 async fn main() -> Result<(), String> {
-    let args = Args::parse();
-
-    // Check if output dir exists
-    if args.output.exists()
-        // Check if it's empty
-        && args.output.read_dir().map(|mut i| i.next().is_some()).unwrap_or(false)
-        // Then overwrite must be specified
-        && !args.overwrite
-    {
-        println!(
-            "Output directory {} already exists and no --overwrite option was specified, aborting",
-            args.output.to_str().unwrap()
-        );
-        exit(1);
+    let mut args = Args::parse();
+    log::init(args.verbose, args.quiet, args.log_json);
+    match args.command.take() {
+        Some(Command::Init(init)) => return init::run_init(init),
+        Some(Command::Deploy(deploy)) => return deploy::run_deploy(deploy),
+        Some(Command::Diff(diff)) => return diff::run_diff(diff),
+        None => {}
     }
 
-    // Clear output dir if it exists
-    if args.output.exists() {
-        remove_dir_contents(&args.output).unwrap();
-    }
-    else {
-        fs::create_dir_all(&args.output).unwrap();
-    }
+    // Clap requires these when there's no subcommand
+    let input = args.input.take().unwrap();
+    let output = args.output.take().unwrap();
 
-    let relative_output = if args.output.is_relative() {
-        Some(UrlPath::try_from(&args.output).ok()).flatten()
+    let relative_output = if output.is_relative() {
+        Some(UrlPath::try_from(&output).ok()).flatten()
     } else {
         None
     };
@@ -85,32 +148,217 @@ async fn main() -> Result<(), String> {
     // Relink working directory to input dir and use absolute path for output
     // Not using fs::canonicalize because that returns UNC paths on Windows and
     // those break things
-    let full_output = if args.output.is_absolute() {
-        args.output
+    let full_output = if output.is_absolute() {
+        output
     } else {
-        std::env::current_dir().unwrap().join(args.output).normalize()
+        std::env::current_dir().unwrap().join(output).normalize()
     };
-    let full_input = if args.input.is_absolute() {
-        args.input
+    let full_input = if input.is_absolute() {
+        input
     } else {
-        std::env::current_dir().unwrap().join(args.input).normalize()
+        std::env::current_dir().unwrap().join(input).normalize()
     };
     std::env::set_current_dir(&full_input).expect(
         "Unable to set input dir as working directory \
             (probable reason is it doesn't exist)",
     );
 
+    if let Some(workspace) = Workspace::parse(&full_input)? {
+        prepare_output_dir(&full_output, args.overwrite);
+        let root_url = args.base_url.as_deref().map(UrlPath::from_base_url).or(relative_output);
+        return build_workspace(&args, workspace, full_input, full_output, root_url).await;
+    }
+
     // Parse config
-    let conf = Config::parse(full_input, full_output, relative_output)?;
+    let mut conf = Config::parse(full_input, full_output, relative_output)?;
+    if let Some(ref base) = args.base_url {
+        conf.output_url = Some(UrlPath::from_base_url(base));
+    }
+    if !conf.i18n.languages.is_empty() {
+        prepare_output_dir(&conf.output_dir, args.overwrite);
+        return build_languages(&args, conf).await;
+    }
+    apply_args(&mut conf, &args);
+    prepare_output_dir(&conf.output_dir, args.overwrite);
+    build(&[Arc::from(conf)]).await
+}
+
+/// Apply command line overrides to a project's config
+fn apply_args(conf: &mut Config, args: &Args) {
+    if let Some(format) = args.format {
+        conf.output.format = format;
+    }
+    if let Some(mode) = args.output_mode {
+        conf.output.mode = mode;
+    }
+    conf.strict = args.strict;
+    conf.report = args.report;
+    conf.check_examples = args.check_examples;
+    conf.check_links = args.check_links;
+    conf.pretty = args.pretty;
+
+    // Versioned docs are placed in a subdirectory of the output named after 
+    // the version
+    if conf.versions.is_some() {
+        let version = UrlPath::part(&conf.project.version);
+        conf.output_dir = conf.output_dir.join(version.to_pathbuf());
+        conf.output_url = Some(conf.output_url.clone().unwrap_or(UrlPath::new()).join(version));
+    }
+}
+
+fn prepare_output_dir(dir: &Path, overwrite: bool) {
+    // Check if output dir exists
+    if dir.exists()
+        // Check if it's empty
+        && dir.read_dir().map(|mut i| i.next().is_some()).unwrap_or(false)
+        // Then overwrite must be specified
+        && !overwrite
+    {
+        log::log(log::Level::Error, Category::Build, format!(
+            "Output directory {} already exists and no --overwrite option was specified, aborting",
+            dir.to_str().unwrap()
+        ));
+        exit(1);
+    }
 
-    // Build the docs
-    println!(
+    // Clear output dir if it exists
+    if dir.exists() {
+        remove_dir_contents(dir).unwrap();
+    }
+    else {
+        fs::create_dir_all(dir).unwrap();
+    }
+}
+
+/// Build the docs for the configs of a project, which only differ in the 
+/// language they're generated in
+async fn build(confs: &[Arc<Config>]) -> Result<(), String> {
+    let conf = &confs[0];
+    log::info(Category::Build, format!(
         "Building docs for {} ({})",
         conf.project.name, conf.project.version
-    );
+    ));
     let now = Instant::now();
-    create_docs(conf.clone()).await?;
-    println!("Docs built for {} in {}s", conf.project.name, now.elapsed().as_secs());
+    create_docs(confs).await?;
+    log::info(
+        Category::Build,
+        format!("Docs built for {} in {}s", conf.project.name, now.elapsed().as_secs()),
+    );
+
+    Ok(())
+}
+
+/// Build the docs once for every language in `i18n.languages`, each into 
+/// its own subdirectory of the output like `en` and `ja`. Pages that aren't 
+/// translated use the default language's content
+async fn build_languages(args: &Args, conf: Config) -> Result<(), String> {
+    let input_dir = conf.input_dir.clone();
+    let root_dir = conf.output_dir.clone();
+    let root_url = conf.output_url.clone().unwrap_or_default();
+    let mut languages = conf.i18n.languages.clone();
+    if !languages.contains(&conf.i18n.language) {
+        languages.insert(0, conf.i18n.language.clone());
+    }
+    let urls = languages.iter()
+        .map(|lang| (lang.clone(), root_url.join(UrlPath::part(lang))))
+        .collect::<Vec<_>>();
+
+    let default_language = conf.i18n.language.clone();
+    let mut first = Some(conf);
+    let mut confs = Vec::new();
+    for (lang, url) in &urls {
+        let mut conf = match first.take() {
+            Some(conf) => conf,
+            None => Config::parse(input_dir.clone(), root_dir.clone(), None)?,
+        };
+        conf.set_language(lang)?;
+        conf.output_dir = root_dir.join(UrlPath::part(lang).to_pathbuf());
+        conf.output_url = Some(url.clone());
+        conf.languages = urls.clone();
+        apply_args(&mut conf, args);
+        fs::create_dir_all(&conf.output_dir).map_err(|e| format!(
+            "Unable to create output directory '{}': {e}", conf.output_dir.to_string_lossy()
+        ))?;
+        confs.push(Arc::from(conf));
+    }
+    // The headers are parsed once for every language
+    build(&confs).await?;
+
+    // The root takes you to the default language
+    fs::write(
+        root_dir.join("index.html"),
+        format!(
+            "<!DOCTYPE html><html><head><meta http-equiv=\"refresh\" content=\"0; url={}/\"></head></html>",
+            root_url.join(UrlPath::part(&default_language))
+        ),
+    ).map_err(|e| format!("Unable to save language index: {e}"))?;
+
+    Ok(())
+}
+
+/// Build every project in a workspace into its own subdirectory of the 
+/// output. Projects link to entities from the projects listed before them, 
+/// so libraries should be listed before the libraries that use them
+async fn build_workspace(
+    args: &Args,
+    workspace: Workspace,
+    full_input: PathBuf,
+    full_output: PathBuf,
+    relative_output: Option<UrlPath>,
+) -> Result<(), String> {
+    let root_url = relative_output.unwrap_or_default();
+
+    let mut confs = Vec::new();
+    for member in &workspace.workspace.members {
+        let input = full_input.join(member).normalize();
+        std::env::set_current_dir(&input).map_err(|e| format!(
+            "Unable to open workspace member '{}': {e}", input.to_string_lossy()
+        ))?;
+        let mut conf = Config::parse(input, PathBuf::new(), None)?;
+        let part = UrlPath::part(&conf.project.name);
+        conf.output_dir = full_output.join(part.to_pathbuf());
+        conf.output_url = Some(root_url.join(&part));
+        apply_args(&mut conf, args);
+        // Other projects link to this one through its docs.json
+        if conf.output.format == OutputFormat::Html {
+            conf.output.format = OutputFormat::Both;
+        }
+        confs.push(conf);
+    }
+
+    let projects = confs.iter()
+        .map(|c| (c.project.name.clone(), root_url.join(UrlPath::part(&c.project.name))))
+        .collect::<Vec<_>>();
+    let mut inventories = Vec::new();
+    for mut conf in confs {
+        std::env::set_current_dir(&conf.input_dir).map_err(|e| format!(
+            "Unable to open workspace member '{}': {e}", conf.input_dir.to_string_lossy()
+        ))?;
+        conf.projects = projects.clone();
+        for inventory in &inventories {
+            conf.external_docs.push(Arc::new(ExternalDocs::from_raw(RawExternalDocs {
+                namespace: String::new(),
+                url: String::new(),
+                inventory: Some(inventory.clone()),
+            })?));
+        }
+        inventories.push(conf.output_dir.join("docs.json"));
+        fs::create_dir_all(&conf.output_dir).map_err(|e| format!(
+            "Unable to create output directory '{}': {e}", conf.output_dir.to_string_lossy()
+        ))?;
+        build(&[Arc::from(conf)]).await?;
+    }
+
+    // The workspace root just takes you to the first project
+    if let Some((_, url)) = projects.first() {
+        fs::write(
+            full_output.join("index.html"),
+            format!(
+                "<!DOCTYPE html><html><head><meta http-equiv=\"refresh\" content=\"0; url={}/\"></head></html>",
+                url
+            ),
+        ).map_err(|e| format!("Unable to save workspace index: {e}"))?;
+    }
 
     Ok(())
 }
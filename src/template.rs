@@ -0,0 +1,209 @@
+use std::{collections::HashMap, fs, path::Path};
+
+/// Templates can't include each other deeper than this, which also stops
+/// templates that include themselves
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+enum Token<'a> {
+    Text(String),
+    Var(&'a str),
+    Tag(&'a str),
+}
+
+enum Node<'a> {
+    Text(String),
+    Var(&'a str),
+    /// `{% if [not] name %}`, with the nodes for when the condition is true
+    /// and the nodes after `{% else %}`
+    If(bool, &'a str, Vec<Node<'a>>, Vec<Node<'a>>),
+    /// `{% for item in list %}`
+    For(&'a str, &'a str, Vec<Node<'a>>),
+}
+
+/// Split a template into text, `{placeholder}`s and `{% tags %}`. Like with
+/// strfmt, literal braces are written as `{{` and `}}`
+fn tokenize(template: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(ix) = rest.find(['{', '}']) {
+        text += &rest[..ix];
+        let after = &rest[ix..];
+        if after.starts_with("{{") || after.starts_with("}}") {
+            text += &after[..1];
+            rest = &after[2..];
+        }
+        else if let Some(tag) = after.strip_prefix("{%") {
+            let end = tag.find("%}").ok_or("Unclosed '{%' in template")?;
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+            tokens.push(Token::Tag(tag[..end].trim()));
+            rest = &tag[end + 2..];
+        }
+        else if after.starts_with('{') {
+            let end = after.find('}').ok_or("Unclosed '{' in template")?;
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+            tokens.push(Token::Var(after[1..end].trim()));
+            rest = &after[end + 1..];
+        }
+        else {
+            text.push('}');
+            rest = &after[1..];
+        }
+    }
+    text += rest;
+    tokens.push(Token::Text(text));
+    Ok(tokens)
+}
+
+/// Parse tokens until one of the `end` tags, returning the nodes and the tag
+/// that ended them
+fn parse<'a>(
+    tokens: &mut impl Iterator<Item = Token<'a>>, end: &[&str]
+) -> Result<(Vec<Node<'a>>, &'a str), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Var(name) => {
+                nodes.push(Node::Var(name));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+        if end.contains(&tag) {
+            return Ok((nodes, tag));
+        }
+        let words = tag.split_whitespace().collect::<Vec<_>>();
+        match words[..] {
+            ["if", name] | ["if", "not", name] => {
+                let negate = words.len() == 3;
+                let (then, ended) = parse(tokens, &["else", "endif"])?;
+                let otherwise = if ended == "else" {
+                    parse(tokens, &["endif"])?.0
+                } else {
+                    Vec::new()
+                };
+                nodes.push(Node::If(negate, name, then, otherwise));
+            }
+            ["for", item, "in", list] => {
+                nodes.push(Node::For(item, list, parse(tokens, &["endfor"])?.0));
+            }
+            ["include", path] => {
+                return Err(format!("Unable to include {path}: includes only work in template files"));
+            }
+            _ => return Err(format!("Unknown template tag '{{% {tag} %}}'")),
+        }
+    }
+    match end.last() {
+        Some(tag) => Err(format!("Missing '{{% {tag} %}}' in template")),
+        None => Ok((nodes, "")),
+    }
+}
+
+struct Scope<'v> {
+    vars: &'v HashMap<String, String>,
+    lists: &'v HashMap<String, Vec<String>>,
+    /// Loop variables, innermost last
+    locals: Vec<(&'v str, &'v str)>,
+}
+
+impl<'v> Scope<'v> {
+    fn get(&self, name: &str) -> Option<&'v str> {
+        self.locals.iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| *v)
+            .or_else(|| self.vars.get(name).map(|v| v.as_str()))
+    }
+
+    /// Variables are true if they aren't empty. Missing variables are false
+    /// so templates can check for variables that aren't always provided
+    fn is_true(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|v| !v.trim().is_empty())
+            || self.lists.get(name).is_some_and(|l| !l.is_empty())
+    }
+}
+
+fn render_nodes<'v>(nodes: &'v [Node<'v>], scope: &mut Scope<'v>, out: &mut String) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => *out += text,
+            Node::Var(name) => *out += scope.get(name)
+                .ok_or_else(|| format!("Unknown template variable '{name}'"))?,
+            Node::If(negate, name, then, otherwise) => {
+                if scope.is_true(name) != *negate {
+                    render_nodes(then, scope, out)?;
+                } else {
+                    render_nodes(otherwise, scope, out)?;
+                }
+            }
+            Node::For(item, list, body) => {
+                let lists = scope.lists;
+                let values = lists.get(*list)
+                    .ok_or_else(|| format!("Unknown template list '{list}'"))?;
+                for value in values {
+                    scope.locals.push((*item, value.as_str()));
+                    render_nodes(body, scope, out)?;
+                    scope.locals.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fill in a template. On top of `{placeholder}`s, templates can have
+/// `{% if name %}...{% else %}...{% endif %}` (or `if not name`) to only
+/// show something when a variable isn't empty, and
+/// `{% for item in list %}...{% endfor %}` to repeat something for every
+/// item in one of `lists`
+pub fn render_with_lists(
+    template: &str, vars: &HashMap<String, String>, lists: &HashMap<String, Vec<String>>
+) -> Result<String, String> {
+    let tokens = tokenize(template)?;
+    let (nodes, _) = parse(&mut tokens.into_iter(), &[])?;
+    let mut scope = Scope { vars, lists, locals: Vec::new() };
+    let mut out = String::new();
+    render_nodes(&nodes, &mut scope, &mut out)?;
+    Ok(out)
+}
+
+pub fn render(template: &str, vars: &HashMap<String, String>) -> Result<String, String> {
+    render_with_lists(template, vars, &HashMap::new())
+}
+
+/// Replace `{% include "path" %}` tags in a template file with the contents
+/// of the included file, relative to the including file. This is done once
+/// when the config is loaded so partials don't have to be read for every page
+pub fn expand_includes(template: &str, dir: &Path) -> Result<String, String> {
+    fn expand(template: &str, dir: &Path, depth: usize) -> Result<String, String> {
+        let mut res = String::new();
+        let mut rest = template;
+        while let Some(ix) = rest.find("{%") {
+            let (before, after) = rest.split_at(ix);
+            let Some(end) = after.find("%}") else {
+                break;
+            };
+            let tag = after[2..end].trim();
+            res += before;
+            rest = &after[end + 2..];
+            let Some(path) = tag.strip_prefix("include").filter(|p| p.starts_with(char::is_whitespace)) else {
+                res += &after[..end + 2];
+                continue;
+            };
+            let path = dir.join(path.trim().trim_matches(['"', '\'']));
+            if depth >= MAX_INCLUDE_DEPTH {
+                return Err(format!("Templates are included too deep at {}", path.to_string_lossy()));
+            }
+            let partial = fs::read_to_string(&path)
+                .map_err(|e| format!("Unable to include {}: {e}", path.to_string_lossy()))?;
+            res += &expand(&partial, path.parent().unwrap_or(dir), depth + 1)?;
+        }
+        res += rest;
+        Ok(res)
+    }
+    expand(template, dir, 0)
+}
@@ -1,4 +1,4 @@
-use crate::config::Config;
+use crate::config::{Config, UrlCase, UrlScheme};
 use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use serde::{de::Visitor, Deserialize};
 use std::{fmt::Display, path::PathBuf, sync::Arc};
@@ -36,7 +36,7 @@ pub const URL_RESERVED: &AsciiSet = &CONTROLS
     .add(b'|')
     .add(b'"');
 
-#[derive(Hash, Debug, Clone, PartialEq)]
+#[derive(Hash, Debug, Default, Clone, PartialEq, Eq)]
 pub struct UrlPath {
     parts: Vec<String>,
 }
@@ -63,6 +63,16 @@ impl UrlPath {
         ))
     }
 
+    /// Parse the path of a site's base URL, like `/project` or 
+    /// `https://user.github.io/project/`
+    pub fn from_base_url(url: &str) -> Self {
+        let path = match url.split_once("://") {
+            Some((_, rest)) => rest.split_once('/').map(|(_, path)| path).unwrap_or_default(),
+            None => url,
+        };
+        UrlPath::new_with_path(path.split('/').map(|s| s.to_owned()).collect())
+    }
+
     fn clean(mut self) -> Self {
         // based on https://github.com/ivanceras/url_path/blob/ffdf3dd883ed4a9395eeb9cf9b1990539508a7a6/src/lib.rs
         let mut filtered = Vec::new();
@@ -103,6 +113,13 @@ impl UrlPath {
         self.clone()
     }
 
+    /// Get this URL without its last component
+    pub fn parent(&self) -> Self {
+        let mut copy = self.clone();
+        copy.parts.pop();
+        copy
+    }
+
     pub fn remove_extension(&self, extension: &str) -> Self {
         let mut copy = self.clone();
         if let Some(last) = copy.parts.last_mut() {
@@ -141,7 +158,21 @@ impl UrlPath {
         PathBuf::from_iter(&self.url_safe_parts())
     }
 
+    /// Get the absolute URL of a page, with the URL scheme and case style 
+    /// from the config applied
     pub fn to_absolute(&self, config: Arc<Config>) -> Self {
+        let root = config.output_url.clone().unwrap_or_default();
+        if !root.is_empty() && self.starts_with(&root) {
+            self.clone()
+        }
+        else {
+            root.join(self.styled(config))
+        }
+    }
+
+    /// Get the absolute URL of something that isn't a page, like an image 
+    /// linked in a tutorial, without applying the URL style
+    pub fn to_absolute_unstyled(&self, config: Arc<Config>) -> Self {
         if self.is_absolute(config.clone()) {
             self.clone()
         }
@@ -154,6 +185,56 @@ impl UrlPath {
         }
     }
 
+    /// Apply the configured case style to every part of this URL
+    pub fn cased(&self, config: Arc<Config>) -> Self {
+        fn to_kebab(part: &str) -> String {
+            let chars = part.chars().collect::<Vec<_>>();
+            let mut res = String::new();
+            for (i, c) in chars.iter().enumerate() {
+                if c.is_uppercase() && i > 0 {
+                    let prev = chars[i - 1];
+                    let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+                    if prev.is_lowercase() || prev.is_ascii_digit()
+                        || (prev.is_uppercase() && next_is_lower)
+                    {
+                        res.push('-');
+                    }
+                }
+                res.extend(if *c == '_' { '-' } else { *c }.to_lowercase());
+            }
+            res
+        }
+
+        Self {
+            parts: self.parts.iter().map(|p| match config.output.url_case {
+                UrlCase::Preserve => p.clone(),
+                UrlCase::Lower => p.to_lowercase(),
+                UrlCase::Kebab => to_kebab(p),
+            }).collect(),
+        }
+    }
+
+    /// Apply the configured URL scheme and case style to a page URL
+    pub fn styled(&self, config: Arc<Config>) -> Self {
+        let mut res = self.cased(config.clone());
+        if config.output.urls == UrlScheme::File
+            && let Some(last) = res.parts.last_mut()
+            && !last.ends_with(".html")
+        {
+            last.push_str(".html");
+        }
+        res
+    }
+
+    /// Get where the full page for this URL should be saved relative to the 
+    /// output directory
+    pub fn page_file(&self, config: Arc<Config>) -> PathBuf {
+        match config.output.urls {
+            UrlScheme::File if !self.is_empty() => self.styled(config).to_pathbuf(),
+            _ => self.cased(config).to_pathbuf().join("index.html"),
+        }
+    }
+
     pub fn is_absolute(&self, config: Arc<Config>) -> bool {
         self.starts_with(&config.output_url.as_ref().unwrap_or(&UrlPath::new()))
     }
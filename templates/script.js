@@ -12,7 +12,10 @@ const searchX = document.getElementById('nav-clear-x');
 let searchNav = undefined;
 let searchQuery = '';
 
-let memberFunctionsList = null;
+// Set by the offline single-file export, which embeds all pages and the
+// functions list into one file
+const embeddedPages = document.getElementById('flash-embedded-pages')?.content;
+let memberFunctionsList = typeof FLASH_FUNCTIONS !== 'undefined' ? FLASH_FUNCTIONS : null;
 
 function createCopyButton(icon, text, callback = undefined) {
     const button = document.createElement('button');
@@ -91,12 +94,32 @@ function headingLink(value) {
         .replace(/\s+/g, '-')
 }
 
+let katexLoaded = null;
+
+function loadKatex() {
+    if (!katexLoaded) {
+        const style = document.createElement('link');
+        style.rel = 'stylesheet';
+        style.href = `${FLASH_KATEX_URL}/katex.min.css`;
+        document.head.appendChild(style);
+        katexLoaded = new Promise((resolve, reject) => {
+            const script = document.createElement('script');
+            script.src = `${FLASH_KATEX_URL}/katex.min.js`;
+            script.addEventListener('load', resolve);
+            script.addEventListener('error', reject);
+            document.head.appendChild(script);
+        });
+    }
+    return katexLoaded;
+}
+
 function highlight() {
     // Add links to all top-level headings
     document.querySelectorAll('.text > h1, .text > h2, .text > h3')
         .forEach(head => {
             if (!head.querySelector('.get-header-link')) {
                 let currentUrl = window.location.href;
+                currentUrl = currentUrl.slice(0, currentUrl.length - currentAnchor().length);
                 while (currentUrl.endsWith('/')) {
                     currentUrl = currentUrl.slice(0, -1)
                 }
@@ -104,7 +127,7 @@ function highlight() {
                     feather.icons.link.toSvg(),
                     `${currentUrl}#${head.getAttribute('id')}`,
                     () => {
-                        window.location.hash = head.getAttribute('id');
+                        setAnchor(head.getAttribute('id'));
                     }
                 );
                 linkBtn.classList.add('get-header-link');
@@ -112,6 +135,22 @@ function highlight() {
             }
         });
     
+    // Add copy buttons to function signatures and include links
+    document.querySelectorAll('[data-copy]').forEach(elem => {
+        if (elem.querySelector(':scope > .copy-button')) {
+            return;
+        }
+        const btn = createCopyButton(feather.icons.copy.toSvg(), elem.getAttribute('data-copy'));
+        btn.classList.add('copy-button');
+        btn.setAttribute('title', 'Copy');
+        // Don't toggle the declaration or follow the link
+        btn.addEventListener('click', e => {
+            e.preventDefault();
+            e.stopPropagation();
+        });
+        elem.appendChild(btn);
+    });
+
     // Highlight warning quotes
     document.querySelectorAll('blockquote > p')
         .forEach(quote => {
@@ -126,6 +165,33 @@ function highlight() {
             }
         });
 
+    // Render diagrams, loading the renderer only on pages that have some
+    const diagrams = document.querySelectorAll('pre.mermaid:not([data-processed])');
+    if (diagrams.length) {
+        import(FLASH_MERMAID_URL).then(({ default: mermaid }) => {
+            mermaid.initialize({ startOnLoad: false, theme: 'dark' });
+            mermaid.run({ nodes: diagrams });
+        });
+    }
+
+    // Render math, loading KaTeX only on pages that have some
+    const math = document.querySelectorAll('.math:not(.rendered)');
+    if (math.length) {
+        loadKatex().then(() => math.forEach(elem => {
+            katex.render(elem.textContent, elem, {
+                displayMode: elem.classList.contains('display'),
+                throwOnError: false,
+            });
+            elem.classList.add('rendered');
+        }));
+    }
+
+    // Show the last picked tab in tabbed code blocks
+    const codeTab = localStorage.getItem('code-tab');
+    if (codeTab) {
+        pickCodeTab(codeTab);
+    }
+
     Prism.highlightAll();
     feather.replace();
     twemoji.parse(document.body);
@@ -172,6 +238,7 @@ function furryMatch(str, query) {
     
     let score = 0;
     let matchedString = '';
+    let matchedIndices = [];
     let toMatch = 0;
     let matchedInARow = 0;
     for (let i = 0; i < str.length; i++) {
@@ -200,6 +267,7 @@ function furryMatch(str, query) {
                 matchedString += '<span class="matched">';
             }
             matchedString += current;
+            matchedIndices.push(i);
 
             // match next char in query next
             toMatch++;
@@ -224,12 +292,14 @@ function furryMatch(str, query) {
         {
             // the more of the string was matched by the query, the better
             score: (score - (str.length - query.length) / 10),
-            matched: matchedString
+            matched: matchedString,
+            indices: matchedIndices
         } : undefined;
 }
 
 function furryMatchMany(list, query, separator) {
     let matched = '';
+    let parts = [];
     let score = 0;
     let someMatched = false;
     let i = 0;
@@ -238,6 +308,7 @@ function furryMatchMany(list, query, separator) {
             matched += `<span class="scope">${separator}</span>`;
         }
         const match = furryMatch(item, query);
+        parts.push([item, match?.indices ?? []]);
         if (match) {
             matched += match.matched;
             score += match.score;
@@ -252,7 +323,7 @@ function furryMatchMany(list, query, separator) {
         }
         i++;
     }
-    return someMatched ? { score, matched } : undefined;
+    return someMatched ? { score, matched, parts } : undefined;
 }
 
 function currentNav() {
@@ -309,10 +380,6 @@ function updateNav() {
                     const node = document.createElement('a');
                     const url = `${FLASH_OUTPUT_URL}/classes/${f.join('/')}#${name.replace(/\s+\([0-9]+\)/, '')}`;
                     node.setAttribute('href', url);
-                    node.addEventListener('click', e => {
-                        navigate(url);
-                        e.preventDefault();
-                    });
                     f = f.map(a => `<span class="namespace">${a}</span>`);
                     f.push(match.matched);
                     node.innerHTML = feather.icons.code.toSvg({ 'class': 'icon class' }) + 
@@ -330,7 +397,7 @@ function updateNav() {
         if (!results.length) {
             const info = document.createElement('p');
             info.classList.add('nothing-found');
-            info.innerText = 'No results found';
+            info.innerText = searchInput.dataset.noResults ?? 'No results found';
             searchResults.appendChild(info);
         }
     
@@ -377,23 +444,72 @@ function scrollAndOpenElement(id) {
     }
 }
 
+// Embedded pages keep the page to show in the hash, so anchors on them go
+// after it like `#/classes/Class#member`
+function currentAnchor() {
+    const hash = window.location.hash;
+    if (embeddedPages && hash.startsWith('#/')) {
+        const ix = hash.indexOf('#', 1);
+        return ix === -1 ? '' : hash.substring(ix);
+    }
+    return hash;
+}
+
+// Go to an anchor on the current page without losing the embedded page
+function setAnchor(id) {
+    if (embeddedPages) {
+        const hash = window.location.hash;
+        const page = hash.substring(0, hash.length - currentAnchor().length);
+        window.history.pushState(window.history.state, '', `${page}#${id}`);
+        scrollAndOpenElement(id);
+    } else {
+        window.location.hash = id;
+    }
+}
+
 function showNav(id) {
     [...navModeBtns.children].forEach(node => node.classList.remove('selected'));
     navModeBtns.querySelector(`#nav-tab-${id}`).classList.add('selected');
     updateNav();
 }
 
+function loadPage(url) {
+    if (embeddedPages) {
+        const page = [...embeddedPages.children].find(p => p.dataset.url === url);
+        if (!page) {
+            return Promise.reject(`No embedded page for ${url}`);
+        }
+        return Promise.resolve([page.innerHTML, JSON.parse(page.dataset.metadata)]);
+    }
+    return Promise.all([
+        fetch(`${url}/content.html`).then(res => res.text()),
+        fetch(`${url}/metadata.json`).then(res => res.json()),
+    ]);
+}
+
+// The directory with the content of a page, since file-style URLs keep it 
+// in a directory without the .html
+function pageURL(url) {
+    return url.split('#').shift().replace(/\.html$/, '').replace(/\/+$/, '');
+}
+
+// Load a page in place, which is only an enhancement over following the 
+// link normally so every link also has a real href
 function navigate(url) {
-    const trueURL = url.split('#').shift();
+    // Relative links only work from the page they were generated for, so 
+    // load the whole page instead of swapping the content
+    if (FLASH_RELATIVE_LINKS && !embeddedPages) {
+        window.location.href = url;
+        return false;
+    }
+    const trueURL = pageURL(url);
     const head = url.split('#').pop();
-    Promise.all([
-        fetch(`${trueURL}/content.html`).then(res => res.text()),
-        fetch(`${trueURL}/metadata.json`).then(res => res.json()),
-    ]).then(([content, metadata]) => {
+    loadPage(trueURL).then(([content, metadata]) => {
+            // file:// URLs can't be changed, so embedded pages go in the hash
             window.history.pushState({
                 html: content,
                 ...metadata,
-            }, "", url);
+            }, "", embeddedPages ? `#${url}` : url);
             document.title = metadata.title;
             mainBody.innerHTML = content;
             mainBody.scrollTo({ left: 0, top: 0 });
@@ -406,6 +522,11 @@ function navigate(url) {
         })
         .catch(err => {
             console.error(err);
+            // Pages without their content.html (like from custom templates) 
+            // can still be loaded normally
+            if (!embeddedPages) {
+                window.location.href = url;
+            }
         });
     
     // Prevent calling default onclick handler
@@ -442,6 +563,35 @@ function pickTheme(name) {
     localStorage.setItem('theme', name);
 }
 
+function switchLanguage(select) {
+    // Every language has the same pages, so go to this page in the new one
+    const current = select.dataset.current;
+    const path = window.location.pathname;
+    window.location.href = path.startsWith(current) ?
+        select.value + path.slice(current.length) + window.location.hash :
+        select.value;
+}
+
+// Versions built after this one aren't in the picker it was built with, so
+// the list is read from the versions.json shared by every version
+document.querySelectorAll('.version-picker[data-versions]').forEach(select => {
+    const url = select.dataset.versions;
+    fetch(url)
+        .then(res => res.json())
+        .then(versions => {
+            const root = url.slice(0, -'versions.json'.length);
+            select.replaceChildren(...versions.map(version => {
+                const option = document.createElement('option');
+                option.value = `${root}${encodeURIComponent(version)}/`;
+                option.textContent = version;
+                option.selected = version === select.dataset.current;
+                return option;
+            }));
+        })
+        // Keep the versions from the build if the list can't be loaded
+        .catch(() => {});
+});
+
 function toggleMenu() {
     nav.classList.toggle('collapsed');
 }
@@ -472,16 +622,313 @@ highlight();
         a.classList.add('selected');
         a.scrollIntoView(false);
 
-        scrollAndOpenElement(window.location.hash);
+        scrollAndOpenElement(currentAnchor());
+    }
+}
+
+// Get the href of a link if it points to another page of the docs, as
+// opposed to other sites, anchors on this page or files like images
+function internalHref(a) {
+    const href = a?.getAttribute('href');
+    if (!href || a.target || a.hasAttribute('download')) {
+        return null;
+    }
+    if (!href.startsWith(`${FLASH_OUTPUT_URL}/`) || href.startsWith('//')) {
+        return null;
+    }
+    if (/\.(?!html$)[a-z0-9]+$/i.test(pageURL(href))) {
+        return null;
+    }
+    return href;
+}
+
+// Load internal links in place. Clicks with modifier keys or other mouse 
+// buttons are left to the browser so links can be opened in new tabs
+document.addEventListener('click', e => {
+    if (e.defaultPrevented || e.button !== 0 || e.ctrlKey || e.metaKey || e.shiftKey || e.altKey) {
+        return;
+    }
+    // Following an anchor normally would replace the embedded page's hash
+    const anchor = e.target.closest('a[href^="#"]')?.getAttribute('href');
+    if (embeddedPages && anchor && anchor.length > 1 && !anchor.startsWith('#/')) {
+        e.preventDefault();
+        setAnchor(anchor.substring(1));
+        return;
+    }
+    const href = internalHref(e.target.closest('a[href]'));
+    if (href) {
+        e.preventDefault();
+        navigate(href);
+    }
+});
+
+// Embedded pages have no real URLs, so the page to show is in the hash
+if (embeddedPages && window.location.hash.startsWith('#/')) {
+    navigate(window.location.hash.substring(1));
+}
+
+// Hint the browser to fetch pages when links to them are hovered, so 
+// opening them is faster
+const prefetchedPages = new Set();
+
+function prefetchPage(url) {
+    const trueURL = pageURL(url);
+    if (prefetchedPages.has(trueURL)) {
+        return;
+    }
+    prefetchedPages.add(trueURL);
+    // Whole pages are loaded with relative links, otherwise only the content
+    const files = FLASH_RELATIVE_LINKS ? [url.split('#').shift()] : [
+        `${trueURL}/content.html`, `${trueURL}/metadata.json`
+    ];
+    for (const file of files) {
+        const link = document.createElement('link');
+        link.rel = 'prefetch';
+        link.href = file;
+        document.head.appendChild(link);
     }
 }
 
+if (FLASH_PREFETCH && !embeddedPages) {
+    for (const event of ['mouseover', 'touchstart']) {
+        document.addEventListener(event, e => {
+            const href = internalHref(e.target.closest?.('a[href]'));
+            if (href) {
+                prefetchPage(href);
+            }
+        }, { passive: true });
+    }
+}
+
+// Keep the state of <details> up to date for screen readers. Toggle events 
+// don't bubble, so they're caught on the way down
+document.addEventListener('toggle', e => {
+    e.target.querySelector(':scope > summary')?.setAttribute('aria-expanded', e.target.open);
+}, true);
+
 // Detect header link change
 window.addEventListener('hashchange', () => {
-    scrollAndOpenElement(window.location.hash);
+    scrollAndOpenElement(currentAnchor());
+});
+
+// Switch every group of tabbed code blocks that has the picked tab, so 
+// picking a platform once shows it everywhere
+function pickCodeTab(name) {
+    document.querySelectorAll('.code-tabs').forEach(tabs => {
+        const children = [...tabs.querySelectorAll(':scope > .tab-buttons > button, :scope > .tab')];
+        if (children.some(c => c.getAttribute('data-tab') === name)) {
+            children.forEach(c => c.classList.toggle('selected', c.getAttribute('data-tab') === name));
+        }
+    });
+    localStorage.setItem('code-tab', name);
+}
+
+document.addEventListener('click', e => {
+    const btn = e.target.closest('.code-tabs > .tab-buttons > button');
+    if (btn) {
+        pickCodeTab(btn.getAttribute('data-tab'));
+    }
+});
+
+// Quick-open palette for jumping to any entity, opened with Ctrl+K
+let symbolsList = typeof FLASH_SYMBOLS !== 'undefined' ? FLASH_SYMBOLS : null;
+let quickOpen = null;
+
+function loadSymbols() {
+    if (symbolsList) {
+        return Promise.resolve(symbolsList);
+    }
+    return fetch(`${FLASH_OUTPUT_URL}/symbols.json`)
+        .then(res => res.json())
+        .then(res => symbolsList = res);
+}
+
+function quickOpenResults() {
+    return [...quickOpen.querySelectorAll('.results > a')];
+}
+
+function selectQuickOpenResult(index) {
+    const results = quickOpenResults();
+    results.forEach(r => r.classList.remove('selected'));
+    if (results.length) {
+        const selected = results[(index + results.length) % results.length];
+        selected.classList.add('selected');
+        selected.scrollIntoView({ block: 'nearest' });
+    }
+}
+
+// Build the name of a quick open result with its matched characters
+// highlighted out of text nodes, so the name is never parsed as HTML
+function matchedNameNode(parts) {
+    const node = document.createElement('span');
+    node.classList.add('name');
+    parts.forEach(([item, indices], i) => {
+        if (i) {
+            const scope = document.createElement('span');
+            scope.classList.add('scope');
+            scope.textContent = '::';
+            node.appendChild(scope);
+        }
+        let start = 0;
+        while (start < item.length) {
+            const matched = indices.includes(start);
+            let end = start + 1;
+            while (end < item.length && indices.includes(end) === matched) {
+                end++;
+            }
+            if (matched) {
+                const span = document.createElement('span');
+                span.classList.add('matched');
+                span.textContent = item.substring(start, end);
+                node.appendChild(span);
+            }
+            else {
+                node.appendChild(document.createTextNode(item.substring(start, end)));
+            }
+            start = end;
+        }
+    });
+    return node;
+}
+
+function updateQuickOpen(query) {
+    const results = quickOpen.querySelector('.results');
+    results.innerHTML = '';
+    (symbolsList ?? [])
+        .map(([name, url, kind]) => [furryMatchMany(name.split('::'), query, '::'), url, kind])
+        .filter(([match]) => match)
+        .sort(([a], [b]) => b.score - a.score)
+        .slice(0, 50)
+        .forEach(([match, url, kind]) => {
+            const node = document.createElement('a');
+            node.setAttribute('href', url);
+            const kindNode = document.createElement('span');
+            kindNode.classList.add('kind');
+            kindNode.textContent = kind;
+            node.append(kindNode, matchedNameNode(match.parts));
+            // The link itself is followed by the handler for internal links
+            node.addEventListener('click', () => closeQuickOpen());
+            results.appendChild(node);
+        });
+    selectQuickOpenResult(0);
+}
+
+function openQuickOpen() {
+    if (!quickOpen) {
+        quickOpen = document.createElement('div');
+        quickOpen.classList.add('quick-open');
+        quickOpen.innerHTML = `
+            <div class="palette">
+                <div class="input"><input placeholder="Go to symbol..."></div>
+                <div class="results"></div>
+            </div>
+        `;
+        quickOpen.addEventListener('click', e => {
+            if (e.target === quickOpen) {
+                closeQuickOpen();
+            }
+        });
+        const input = quickOpen.querySelector('input');
+        input.addEventListener('input', () => updateQuickOpen(input.value));
+        input.addEventListener('keydown', e => {
+            const results = quickOpenResults();
+            const selected = results.findIndex(r => r.classList.contains('selected'));
+            if (e.key === 'ArrowDown' || e.key === 'ArrowUp') {
+                selectQuickOpenResult(selected + (e.key === 'ArrowDown' ? 1 : -1));
+                e.preventDefault();
+            }
+            else if (e.key === 'Enter') {
+                results[selected]?.click();
+            }
+            else if (e.key === 'Escape') {
+                closeQuickOpen();
+            }
+        });
+        document.body.appendChild(quickOpen);
+    }
+    quickOpen.classList.add('open');
+    const input = quickOpen.querySelector('input');
+    input.value = '';
+    input.focus();
+    loadSymbols().then(() => updateQuickOpen(input.value));
+}
+
+function closeQuickOpen() {
+    quickOpen?.classList.remove('open');
+}
+
+document.addEventListener('keydown', e => {
+    if ((e.ctrlKey || e.metaKey) && e.key.toLowerCase() === 'k') {
+        e.preventDefault();
+        openQuickOpen();
+    }
+});
+
+// Preview cards of entities shown when hovering links to them
+let previewsMap = typeof FLASH_PREVIEWS !== 'undefined' ? FLASH_PREVIEWS : null;
+let previewCard = null;
+let previewTimeout = null;
+
+function loadPreviews() {
+    if (previewsMap) {
+        return Promise.resolve(previewsMap);
+    }
+    return fetch(`${FLASH_OUTPUT_URL}/previews.json`)
+        .then(res => res.json())
+        .catch(() => ({}))
+        .then(res => previewsMap = res);
+}
+
+function showPreview(link) {
+    loadPreviews().then(previews => {
+        const preview = previews[link.getAttribute('data-preview')];
+        if (!preview || !link.matches(':hover')) {
+            return;
+        }
+        if (!previewCard) {
+            previewCard = document.createElement('div');
+            previewCard.classList.add('preview-card');
+            previewCard.addEventListener('mouseenter', () => clearTimeout(previewTimeout));
+            previewCard.addEventListener('mouseleave', hidePreview);
+            document.body.appendChild(previewCard);
+        }
+        previewCard.innerHTML = `
+            <span class="kind"></span>
+            <pre class="signature"><code class="language-cpp"></code></pre>
+            <div class="brief">${preview.brief ?? ''}</div>
+        `;
+        previewCard.querySelector('.kind').textContent = preview.kind;
+        previewCard.querySelector('.signature > code').textContent = preview.signature;
+        Prism.highlightAllUnder(previewCard);
+
+        const rect = link.getBoundingClientRect();
+        previewCard.style.left = `${rect.left + window.scrollX}px`;
+        previewCard.style.top = `${rect.bottom + window.scrollY + 4}px`;
+        previewCard.classList.add('open');
+    });
+}
+
+function hidePreview() {
+    clearTimeout(previewTimeout);
+    previewTimeout = setTimeout(() => previewCard?.classList.remove('open'), 200);
+}
+
+document.addEventListener('mouseover', e => {
+    const link = e.target.closest('a[data-preview]');
+    if (link && !link.contains(e.relatedTarget)) {
+        clearTimeout(previewTimeout);
+        previewTimeout = setTimeout(() => showPreview(link), 400);
+    }
+});
+
+document.addEventListener('mouseout', e => {
+    const link = e.target.closest('a[data-preview]');
+    if (link && !link.contains(e.relatedTarget)) {
+        hidePreview();
+    }
 });
 
 // Restore selected theme by clicking the selected theme button
 document.querySelector(`[data-pick-theme="${
-    localStorage.getItem('theme') ?? 'dark'
+    localStorage.getItem('theme') ?? FLASH_DEFAULT_THEME
 }"]`)?.click();